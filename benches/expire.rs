@@ -0,0 +1,64 @@
+// 比较两种主动清除过期key的策略
+//   cargo bench --bench expire
+// 对每种策略写入大量带过期时间的key，统计:
+//   - 写入的吞吐量，Ordered每次写入都需要维护BTreeSet
+//   - 最后一个key过期之后，需要多久才能把所有过期的key从内存中清除
+//   - 清除期间其他请求的最大延迟，Ordered会在一次加锁中清除所有过期的key
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use mini_redis::db::{DbDropGuard, ExpireStrategy, Expiry, SetOptions};
+
+const KEYS: usize = 500_000;
+const TTL: Duration = Duration::from_millis(500);
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[tokio::main]
+async fn main() {
+    for strategy in [ExpireStrategy::Ordered, ExpireStrategy::Sampled] {
+        run(strategy).await;
+    }
+}
+
+async fn run(strategy: ExpireStrategy) {
+    let guard = DbDropGuard::with_strategy(strategy);
+    let db = guard.db();
+
+    let keys: Vec<String> = (0..KEYS).map(|i| format!("key:{}", i)).collect();
+    let value = Bytes::from_static(b"value");
+    let options = SetOptions {
+        expire: Expiry::After(TTL),
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    for key in keys {
+        db.set(key, value.clone(), options).unwrap();
+    }
+    let set_elapsed = start.elapsed();
+    let last_deadline = Instant::now() + TTL;
+
+    // 不停地读取一个不存在的key，记录最长的一次等待，直到所有的key都被清除
+    let mut max_latency = Duration::ZERO;
+    while db.dbsize() > 0 && start.elapsed() < TIMEOUT {
+        let now = Instant::now();
+        db.get("probe").unwrap();
+        max_latency = max_latency.max(now.elapsed());
+
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+
+    let remaining = db.dbsize();
+    let purged = Instant::now().saturating_duration_since(last_deadline);
+
+    println!(
+        "{:<8} set {} keys in {:>9.2?} ({:>8.0} ops/s), purged {:>9.2?} after the last deadline ({} left), worst GET latency {:>9.2?}",
+        strategy,
+        KEYS,
+        set_elapsed,
+        KEYS as f64 / set_elapsed.as_secs_f64(),
+        purged,
+        remaining,
+        max_latency,
+    );
+}
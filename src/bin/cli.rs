@@ -0,0 +1,379 @@
+use std::{num::ParseIntError, str, time::Duration};
+
+use bytes::Bytes;
+use clap::{Parser, Subcommand};
+use mini_redis::{
+    client::{self, Client, Subscriber},
+    frame::{self, Frame},
+    DEFAULT_PORT,
+};
+use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "mini-redis-cli",
+    author,
+    version,
+    about = "Issue Redis commands, or start an interactive shell when no command is given"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(id = "hostname", long, default_value = "127.0.0.1")]
+    host: String,
+
+    #[arg(long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+
+    /// Print raw replies instead of the formatted redis-cli style output
+    #[arg(long)]
+    raw: bool,
+
+    /// Switch the connection to RESP3 with HELLO 3 before issuing commands
+    #[arg(short = '3')]
+    resp3: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    Ping {
+        /// Message to ping
+        #[arg(value_parser = bytes_from_str)]
+        msg: Option<Bytes>,
+    },
+    /// Get the value of key.
+    Get {
+        /// Name of key to get
+        key: String,
+    },
+    /// Set key to hold the string value.
+    Set {
+        /// Name of key to set
+        key: String,
+
+        /// Value to set.
+        #[arg(value_parser = bytes_from_str)]
+        value: Bytes,
+
+        /// Expire the value after specified amount of time
+        #[arg(value_parser = duration_from_ms_str)]
+        expires: Option<Duration>,
+    },
+    ///  Publisher to send a message to a specific channel.
+    Publish {
+        /// Name of channel
+        channel: String,
+
+        #[arg(value_parser = bytes_from_str)]
+        /// Message to publish
+        message: Bytes,
+    },
+    /// Subscribe a client to a specific channel or channels.
+    Subscribe {
+        /// Specific channel or channels
+        #[arg(required = true)]
+        channels: Vec<String>,
+    },
+}
+
+// 命令行工具入口
+// 带子命令时执行一次命令后退出，不带子命令时进入交互模式(REPL)
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> mini_redis::Result<()> {
+    let cli = Cli::parse();
+
+    let addr = format!("{}:{}", cli.host, cli.port);
+
+    let mut client = Client::connect(&addr).await?;
+
+    if cli.resp3 {
+        client.hello(Some(3)).await?;
+    }
+
+    let command = match cli.command {
+        Some(command) => command,
+        None => return repl(client, &addr, cli.raw).await,
+    };
+
+    let response = match command {
+        // 不带参数时服务器返回的是简单字符串PONG，带参数时原样返回参数
+        Command::Ping { msg: None } => client
+            .ping(None)
+            .await
+            .map(|pong| Frame::Simple(String::from_utf8_lossy(&pong).into_owned())),
+        Command::Ping { msg } => client.ping(msg).await.map(Frame::Bulk),
+        Command::Get { key } => client
+            .get(&key)
+            .await
+            .map(|value| value.map(Frame::Bulk).unwrap_or(Frame::Null)),
+        Command::Set {
+            key,
+            value,
+            expires: None,
+        } => client
+            .set(&key, value)
+            .await
+            .map(|_| Frame::Simple("OK".to_string())),
+        Command::Set {
+            key,
+            value,
+            expires: Some(expires),
+        } => client
+            .set_expires(&key, value, expires)
+            .await
+            .map(|_| Frame::Simple("OK".to_string())),
+        Command::Publish { channel, message } => client
+            .publish(&channel, message)
+            .await
+            .map(|num| Frame::Integer(num as i64)),
+        Command::Subscribe { channels } => {
+            let subscriber = client.subscribe(channels).await?;
+            return print_messages(subscriber, cli.raw).await;
+        }
+    };
+
+    print_response(response, cli.raw).await
+}
+
+// 交互模式，每次读取一行输入，解析成命令发送给服务器并打印结果
+async fn repl(mut client: Client, addr: &str, raw: bool) -> mini_redis::Result<()> {
+    let mut lines = BufReader::new(io::stdin()).lines();
+    let mut stdout = io::stdout();
+
+    loop {
+        stdout.write_all(format!("{}> ", addr).as_bytes()).await?;
+        stdout.flush().await?;
+
+        // 读到EOF(例如Ctrl-D)时退出
+        let line = match lines.next_line().await? {
+            Some(line) => line,
+            None => return Ok(()),
+        };
+
+        let args = match frame::split_args(line.as_bytes()) {
+            Ok(args) => args,
+            Err(_) => {
+                println!("Invalid argument(s)");
+                continue;
+            }
+        };
+
+        let name = match args.first() {
+            Some(name) => str::from_utf8(name).unwrap_or_default().to_lowercase(),
+            None => continue,
+        };
+
+        match &name[..] {
+            "quit" | "exit" => return Ok(()),
+            // 订阅后连接进入订阅模式，只能一直接收消息直到用户按下Ctrl-C
+            "subscribe" => {
+                let channels = args[1..]
+                    .iter()
+                    .map(|channel| String::from_utf8_lossy(channel).into_owned())
+                    .collect();
+                let subscriber = client.subscribe(channels).await?;
+                return print_messages(subscriber, raw).await;
+            }
+            _ => {
+                // 不在订阅模式时UNSUBSCRIBE会为每个channel返回一个响应
+                let replies = if name == "unsubscribe" {
+                    args.len().max(2) - 1
+                } else {
+                    1
+                };
+
+                let mut response = client.execute(args).await;
+
+                for _ in 1..replies {
+                    if response.is_err() {
+                        break;
+                    }
+                    print_response(response, raw).await?;
+                    response = client.read_reply().await;
+                }
+
+                // 连接已经断开的话没有必要继续读取命令
+                if let Err(client::Error::ConnectionReset) = response {
+                    return Err(client::Error::ConnectionReset.into());
+                }
+
+                print_response(response, raw).await?;
+            }
+        }
+    }
+}
+
+async fn print_messages(mut subscriber: Subscriber, raw: bool) -> mini_redis::Result<()> {
+    if !raw {
+        println!("Reading messages... (press Ctrl-C to quit)");
+    }
+
+    while let Some(msg) = subscriber.next_message().await? {
+        let mut frame = vec![Frame::Bulk(Bytes::from_static(b"message"))];
+        frame.push(Frame::Bulk(Bytes::from(msg.channel)));
+        frame.push(Frame::Bulk(msg.content));
+
+        print_response(Ok(Frame::Array(frame)), raw).await?;
+    }
+
+    Ok(())
+}
+
+async fn print_response(response: client::Result<Frame>, raw: bool) -> mini_redis::Result<()> {
+    let frame = match response {
+        Ok(frame) => frame,
+        // 服务器返回的错误和其他frame一样打印出来
+        Err(client::Error::Server(msg)) => Frame::Error(msg),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut out = if raw {
+        format_raw(&frame)
+    } else {
+        format_pretty(&frame).into_bytes()
+    };
+    out.push(b'\n');
+
+    let mut stdout = io::stdout();
+    stdout.write_all(&out).await?;
+    stdout.flush().await?;
+
+    Ok(())
+}
+
+// 仿照redis-cli的输出格式
+//   "bar"            Bulk
+//   (integer) 1      Integer
+//   (nil)            Null
+//   1) "a"           Array，嵌套的数组会按照序号的宽度缩进
+//   2) 1) "b"
+//      2) "c"
+fn format_pretty(frame: &Frame) -> String {
+    match frame {
+        Frame::Simple(s) => s.clone(),
+        Frame::Error(msg) => format!("(error) {}", msg),
+        Frame::Integer(num) => format!("(integer) {}", num),
+        Frame::Bulk(bytes) => quote(bytes),
+        Frame::Null => "(nil)".to_string(),
+        Frame::Array(items) | Frame::Push(items) if items.is_empty() => "(empty array)".to_string(),
+        Frame::Set(items) if items.is_empty() => "(empty set)".to_string(),
+        Frame::Map(pairs) if pairs.is_empty() => "(empty hash)".to_string(),
+        Frame::Array(items) | Frame::Push(items) => {
+            format_items(items.iter().map(format_pretty).collect(), ')')
+        }
+        // RESP3的集合和Map分别使用 1~ 和 1# 作为序号
+        Frame::Set(items) => format_items(items.iter().map(format_pretty).collect(), '~'),
+        Frame::Map(pairs) => {
+            let items = pairs
+                .iter()
+                .map(|(key, value)| {
+                    let key = format!("{} => ", format_pretty(key));
+                    let padding = " ".repeat(key.len());
+
+                    format_pretty(value)
+                        .lines()
+                        .enumerate()
+                        .map(|(j, line)| {
+                            let indent = if j == 0 { &key } else { &padding };
+                            format!("{}{}", indent, line)
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .collect();
+
+            format_items(items, '#')
+        }
+        Frame::Double(num) => format!("(double) {}", format_double(*num)),
+        Frame::Boolean(b) => format!("({})", b),
+        Frame::BigNumber(num) => format!("(big number) {}", num),
+        // Verbatim通常是INFO这类多行文本，直接原样输出
+        Frame::Verbatim { data, .. } => String::from_utf8_lossy(data).into_owned(),
+        Frame::Attribute { data, .. } => format_pretty(data),
+    }
+}
+
+// 给每个元素加上序号，多行的元素按照序号的宽度缩进
+fn format_items(items: Vec<String>, sep: char) -> String {
+    let width = items.len().to_string().len();
+    let mut lines = vec![];
+
+    for (i, item) in items.iter().enumerate() {
+        let prefix = format!("{:>width$}{} ", i + 1, sep, width = width);
+        let padding = " ".repeat(prefix.len());
+
+        for (j, line) in item.lines().enumerate() {
+            let indent = if j == 0 { &prefix } else { &padding };
+            lines.push(format!("{}{}", indent, line));
+        }
+    }
+
+    lines.join("\n")
+}
+
+// --raw 模式下直接输出数据本身，数组的每个元素占一行
+fn format_raw(frame: &Frame) -> Vec<u8> {
+    match frame {
+        Frame::Simple(s) => s.as_bytes().to_vec(),
+        Frame::Error(msg) => msg.as_bytes().to_vec(),
+        Frame::Integer(num) => num.to_string().into_bytes(),
+        Frame::Bulk(bytes) => bytes.to_vec(),
+        Frame::Null => vec![],
+        Frame::Array(items) | Frame::Set(items) | Frame::Push(items) => items
+            .iter()
+            .map(format_raw)
+            .collect::<Vec<_>>()
+            .join(&b'\n'),
+        Frame::Map(pairs) => pairs
+            .iter()
+            .flat_map(|(key, value)| [format_raw(key), format_raw(value)])
+            .collect::<Vec<_>>()
+            .join(&b'\n'),
+        Frame::Double(num) => format_double(*num).into_bytes(),
+        Frame::Boolean(b) => vec![if *b { b'1' } else { b'0' }],
+        Frame::BigNumber(num) => num.as_bytes().to_vec(),
+        Frame::Verbatim { data, .. } => data.to_vec(),
+        Frame::Attribute { data, .. } => format_raw(data),
+    }
+}
+
+fn format_double(num: f64) -> String {
+    if num.is_nan() {
+        "nan".to_string()
+    } else {
+        num.to_string()
+    }
+}
+
+// 用双引号包裹字符串，不可打印的字符按照 \xHH 的形式转义
+fn quote(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() + 2);
+    s.push('"');
+
+    for &b in bytes {
+        match b {
+            b'\\' => s.push_str("\\\\"),
+            b'"' => s.push_str("\\\""),
+            b'\n' => s.push_str("\\n"),
+            b'\r' => s.push_str("\\r"),
+            b'\t' => s.push_str("\\t"),
+            0x07 => s.push_str("\\a"),
+            0x08 => s.push_str("\\b"),
+            b if b.is_ascii_graphic() || b == b' ' => s.push(b as char),
+            b => s.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+
+    s.push('"');
+    s
+}
+
+fn duration_from_ms_str(src: &str) -> Result<Duration, ParseIntError> {
+    let ms = src.parse::<u64>()?;
+    Ok(Duration::from_millis(ms))
+}
+
+fn bytes_from_str(src: &str) -> Result<Bytes, std::convert::Infallible> {
+    Ok(Bytes::from(src.to_string()))
+}
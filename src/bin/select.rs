@@ -1,6 +1,5 @@
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
-use tokio::sync::oneshot;
 
 #[tokio::main]
 async fn main() {
@@ -9,7 +8,7 @@ async fn main() {
                    Host: example.com\r\n\
                    \r\n";
 
-    stream.write_all(request.as_bytes()).await;
+    stream.write_all(request.as_bytes()).await.unwrap();
 
     let mut buffer = [0; 1024];
     let n = stream.read(&mut buffer).await.unwrap();
@@ -1,14 +1,54 @@
+use std::{fs, future::Future, io, num::ParseIntError, path::PathBuf, time::Duration};
+
 use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
 
 use clap::Parser;
-use mini_redis::{server, DEFAULT_PORT};
+#[cfg(unix)]
+use mini_redis::server::bind_unix;
+use mini_redis::{
+    db::{EncodingConfig, ExpireStrategy},
+    server, DEFAULT_PORT,
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "mini-redis-server", author, version, about = "A Redis Server")]
 struct Args {
-    /// Name of the persion to greet
+    /// TCP port to listen on, 0 disables the TCP listener
     #[clap(long)]
     port: Option<u16>,
+
+    /// Path of a Unix domain socket to listen on, in addition to the TCP port
+    #[clap(long)]
+    unixsocket: Option<PathBuf>,
+
+    /// Permissions of the Unix socket file in octal, e.g. 700
+    #[clap(long, value_parser = mode_from_octal_str, requires = "unixsocket")]
+    unixsocketperm: Option<u32>,
+
+    /// Seconds to wait for connections to finish their current command on shutdown
+    #[clap(long, default_value_t = 10)]
+    shutdown_timeout: u64,
+
+    /// How expired keys are actively purged: "ordered" tracks every deadline in a sorted set,
+    /// "sampled" periodically checks random keys like Redis does
+    #[clap(long, default_value_t = ExpireStrategy::default())]
+    active_expire: ExpireStrategy,
+
+    /// Hashes with more fields than this are converted from the compact listpack encoding
+    /// to a hash table
+    #[clap(long, default_value_t = EncodingConfig::default().hash_max_listpack_entries)]
+    hash_max_listpack_entries: usize,
+
+    /// Hashes with a field or value longer than this are converted to a hash table
+    #[clap(long, default_value_t = EncodingConfig::default().hash_max_listpack_value)]
+    hash_max_listpack_value: usize,
+
+    /// Sets containing only integers are converted from the compact intset encoding to a
+    /// hash table once they have more members than this
+    #[clap(long, default_value_t = EncodingConfig::default().set_max_intset_entries)]
+    set_max_intset_entries: usize,
 }
 
 #[tokio::main]
@@ -17,12 +57,70 @@ async fn main() -> mini_redis::Result<()> {
 
     let port = args.port.unwrap_or(DEFAULT_PORT);
 
-    let listen_url = format!("127.0.0.1:{}", port);
+    let mut acceptors = vec![];
+
+    // 和redis一样，端口为0时不监听TCP
+    if port != 0 {
+        let listen_url = format!("127.0.0.1:{}", port);
+
+        // 监听listen_url
+        acceptors.push(TcpListener::bind(listen_url).await?.into());
+    }
+
+    if let Some(path) = &args.unixsocket {
+        acceptors.push(bind_unix(path, args.unixsocketperm)?);
+    }
 
-    // 监听listen_url
-    let listner = TcpListener::bind(listen_url).await?;
+    if acceptors.is_empty() {
+        return Err("no listener configured, set a non-zero --port or --unixsocket".into());
+    }
 
-    server::run(listner, async {});
+    let config = server::Config {
+        shutdown_timeout: Duration::from_secs(args.shutdown_timeout),
+        expire_strategy: args.active_expire,
+        encoding: EncodingConfig {
+            hash_max_listpack_entries: args.hash_max_listpack_entries,
+            hash_max_listpack_value: args.hash_max_listpack_value,
+            set_max_intset_entries: args.set_max_intset_entries,
+        },
+    };
+
+    server::run(acceptors, config, shutdown_signal()?).await;
+
+    if let Some(path) = &args.unixsocket {
+        let _ = fs::remove_file(path);
+    }
 
     Ok(())
 }
+
+#[cfg(not(unix))]
+fn bind_unix(_path: &std::path::Path, _mode: Option<u32>) -> mini_redis::Result<server::Acceptor> {
+    Err("Unix domain sockets are not supported on this platform".into())
+}
+
+// Ctrl-C(SIGINT)和kill/systemd发送的SIGTERM都会触发优雅关闭
+#[cfg(unix)]
+fn shutdown_signal() -> io::Result<impl Future<Output = ()>> {
+    let mut sigint = signal(SignalKind::interrupt())?;
+    let mut sigterm = signal(SignalKind::terminate())?;
+
+    Ok(async move {
+        tokio::select! {
+            _ = sigint.recv() => {}
+            _ = sigterm.recv() => {}
+        }
+    })
+}
+
+// 其他平台上只有Ctrl-C
+#[cfg(not(unix))]
+fn shutdown_signal() -> io::Result<impl Future<Output = ()>> {
+    Ok(async {
+        let _ = tokio::signal::ctrl_c().await;
+    })
+}
+
+fn mode_from_octal_str(src: &str) -> Result<u32, ParseIntError> {
+    u32::from_str_radix(src, 8)
+}
@@ -0,0 +1,439 @@
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio_stream::Stream;
+use tracing::debug;
+
+use crate::{
+    cmd::{Get, Hello, Ping, Publish, Set, Subscribe, Unsubscribe},
+    connection::{Connection, Transport},
+    frame::Frame,
+};
+
+// 与redis服务器建立的连接，可以通过它发送get、set等命令
+// 每个命令都会等待服务器返回结果之后才会返回
+pub struct Client {
+    connection: Connection,
+}
+
+// 进入订阅模式的客户端，只能收取消息或者继续订阅/取消订阅channel
+// 实现了Stream，可以配合StreamExt逐条读取消息，连接被关闭时流结束
+pub struct Subscriber {
+    client: Client,
+    subscribed_channels: Vec<String>,
+}
+
+// 通过Client::pipeline创建，先把多个命令缓存起来，execute时一次性发送给服务器，
+// 再按照发送的顺序读取所有的响应，减少网络往返的次数
+pub struct Pipeline<'a> {
+    client: &'a mut Client,
+    frames: Vec<Frame>,
+}
+
+// 订阅的channel中收到的消息
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub channel: String,
+    pub content: Bytes,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    // 服务器返回的错误响应，例如 "ERR unknown command 'foo'"
+    Server(String),
+    // 服务器返回了与命令不匹配的frame
+    UnexpectedFrame(Frame),
+    // 服务器在返回结果之前关闭了连接
+    ConnectionReset,
+    Other(crate::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Client {
+    // 与addr上的redis服务器建立连接
+    pub async fn connect<T: ToSocketAddrs>(addr: T) -> Result<Client> {
+        let socket = TcpStream::connect(addr).await?;
+
+        Ok(Client::new(socket))
+    }
+
+    // 在已经建立好的传输层上创建客户端，例如UnixStream、TLS流或者测试用的DuplexStream
+    pub fn new<T: Transport + 'static>(stream: T) -> Client {
+        Client {
+            connection: Connection::new(Box::new(stream)),
+        }
+    }
+
+    // PING [message]，不带message时服务器返回PONG
+    pub async fn ping(&mut self, msg: Option<Bytes>) -> Result<Bytes> {
+        let frame = Ping::new(msg).into_frame();
+        self.send(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(value.into()),
+            Frame::Bulk(value) => Ok(value),
+            frame => Err(Error::UnexpectedFrame(frame)),
+        }
+    }
+
+    // HELLO [protover]，切换连接使用的协议版本，返回服务器的信息
+    // 切换到RESP3之后服务器可能返回Map、Double等RESP3类型的frame
+    pub async fn hello(&mut self, protover: Option<u64>) -> Result<Frame> {
+        let frame = Hello::new(protover).into_frame();
+        self.send(&frame).await?;
+
+        self.read_response().await
+    }
+
+    // 获取key对应的值，key不存在的话返回None
+    pub async fn get(&mut self, key: &str) -> Result<Option<Bytes>> {
+        let frame = Get::new(key).into_frame();
+        self.send(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(Some(value.into())),
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(Error::UnexpectedFrame(frame)),
+        }
+    }
+
+    // 设置key-value，key已经存在的话会覆盖之前的值以及过期时间
+    pub async fn set(&mut self, key: &str, value: Bytes) -> Result<()> {
+        self.set_cmd(Set::new(key, value, None)).await
+    }
+
+    // 设置key-value，并且在expiration之后过期
+    pub async fn set_expires(
+        &mut self,
+        key: &str,
+        value: Bytes,
+        expiration: Duration,
+    ) -> Result<()> {
+        self.set_cmd(Set::new(key, value, Some(expiration))).await
+    }
+
+    async fn set_cmd(&mut self, cmd: Set) -> Result<()> {
+        let frame = cmd.into_frame();
+        self.send(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(Error::UnexpectedFrame(frame)),
+        }
+    }
+
+    // 向channel发布消息，返回收到这条消息的订阅者数量
+    pub async fn publish(&mut self, channel: &str, message: Bytes) -> Result<u64> {
+        let frame = Publish::new(channel, message).into_frame();
+        self.send(&frame).await?;
+
+        match self.read_response().await? {
+            Frame::Integer(response) if response >= 0 => Ok(response as u64),
+            frame => Err(Error::UnexpectedFrame(frame)),
+        }
+    }
+
+    // 订阅channels，之后这个连接进入订阅模式，因此会消耗掉client并返回Subscriber
+    pub async fn subscribe(mut self, channels: Vec<String>) -> Result<Subscriber> {
+        self.subscribe_cmd(&channels).await?;
+
+        let mut subscriber = Subscriber {
+            client: self,
+            subscribed_channels: vec![],
+        };
+        subscriber.add_channels(&channels);
+
+        Ok(subscriber)
+    }
+
+    // 发送SUBSCRIBE命令，并等待每个channel的订阅确认
+    async fn subscribe_cmd(&mut self, channels: &[String]) -> Result<()> {
+        let frame = Subscribe::new(channels.to_vec()).into_frame();
+        self.send(&frame).await?;
+
+        // 服务器会为每个channel返回一个 ["subscribe", channel, 订阅数量]
+        for channel in channels {
+            let response = self.read_response().await?;
+
+            match response {
+                Frame::Array(ref frame) | Frame::Push(ref frame) => match frame.as_slice() {
+                    [subscribe, schannel, ..]
+                        if *subscribe == "subscribe" && *schannel == channel.as_str() => {}
+                    _ => return Err(Error::UnexpectedFrame(response)),
+                },
+                frame => return Err(Error::UnexpectedFrame(frame)),
+            }
+        }
+
+        Ok(())
+    }
+
+    // 创建一个pipeline，用于批量发送命令
+    //   let replies = client.pipeline().set("a", v).get("a").execute().await?;
+    pub fn pipeline(&mut self) -> Pipeline<'_> {
+        Pipeline {
+            client: self,
+            frames: vec![],
+        }
+    }
+
+    // 发送任意命令，args的第一个元素是命令名称，返回服务器响应的原始frame
+    // 主要用于命令行工具这类无法提前知道命令类型的场景
+    pub async fn execute(&mut self, args: Vec<Bytes>) -> Result<Frame> {
+        let mut frame = Frame::array();
+        for arg in args {
+            frame.push_bulk(arg);
+        }
+        self.send(&frame).await?;
+
+        self.read_response().await
+    }
+
+    // 发送一个命令frame，并立即flush给服务器
+    async fn send(&mut self, frame: &Frame) -> Result<()> {
+        debug!(request = ?frame);
+        self.connection.write_frame(frame).await?;
+        self.connection.flush().await?;
+        Ok(())
+    }
+
+    // 读取服务器的下一个响应，用于一个命令会返回多个响应的情况，例如UNSUBSCRIBE多个channel
+    pub async fn read_reply(&mut self) -> Result<Frame> {
+        self.read_response().await
+    }
+
+    // 读取一个响应frame，服务器返回的错误会被转换成Error::Server
+    async fn read_response(&mut self) -> Result<Frame> {
+        let response = self.connection.read_frame().await?;
+
+        debug!(?response);
+
+        match response {
+            Some(Frame::Error(msg)) => Err(Error::Server(msg)),
+            Some(frame) => Ok(frame),
+            // 服务器关闭了连接
+            None => Err(Error::ConnectionReset),
+        }
+    }
+}
+
+impl Pipeline<'_> {
+    pub fn ping(self, msg: Option<Bytes>) -> Self {
+        self.push(Ping::new(msg).into_frame())
+    }
+
+    pub fn get(self, key: &str) -> Self {
+        self.push(Get::new(key).into_frame())
+    }
+
+    pub fn set(self, key: &str, value: Bytes) -> Self {
+        self.push(Set::new(key, value, None).into_frame())
+    }
+
+    pub fn set_expires(self, key: &str, value: Bytes, expiration: Duration) -> Self {
+        self.push(Set::new(key, value, Some(expiration)).into_frame())
+    }
+
+    pub fn publish(self, channel: &str, message: Bytes) -> Self {
+        self.push(Publish::new(channel, message).into_frame())
+    }
+
+    // 添加任意命令，args的第一个元素是命令名称
+    pub fn cmd(self, args: Vec<Bytes>) -> Self {
+        let mut frame = Frame::array();
+        for arg in args {
+            frame.push_bulk(arg);
+        }
+        self.push(frame)
+    }
+
+    fn push(mut self, frame: Frame) -> Self {
+        self.frames.push(frame);
+        self
+    }
+
+    // pipeline中命令的数量
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // 发送所有的命令，并按顺序返回每个命令的响应
+    // 单个命令执行失败(服务器返回错误)不会影响其他命令，对应位置是Err(Error::Server)
+    pub async fn execute(self) -> Result<Vec<Result<Frame>>> {
+        let connection = &mut self.client.connection;
+
+        for frame in &self.frames {
+            debug!(request = ?frame);
+            connection.write_frame(frame).await?;
+        }
+        connection.flush().await?;
+
+        let mut replies = Vec::with_capacity(self.frames.len());
+
+        for _ in 0..self.frames.len() {
+            match self.client.read_response().await {
+                Ok(frame) => replies.push(Ok(frame)),
+                Err(Error::Server(msg)) => replies.push(Err(Error::Server(msg))),
+                // 连接出错时剩下的响应已经无法读取了
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(replies)
+    }
+}
+
+impl Subscriber {
+    // 当前订阅的所有channel
+    pub fn get_subscribed(&self) -> &[String] {
+        &self.subscribed_channels
+    }
+
+    // 等待下一条消息，连接被关闭时返回None
+    pub async fn next_message(&mut self) -> Result<Option<Message>> {
+        match self.client.connection.read_frame().await? {
+            Some(mframe) => parse_message(mframe).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // 将subscriber转换成消息流，Subscriber本身就是Stream，这里只是为了兼容之前的用法
+    pub fn into_stream(self) -> impl Stream<Item = Result<Message>> {
+        self
+    }
+
+    // 继续订阅更多的channel
+    pub async fn subscribe(&mut self, channels: &[String]) -> Result<()> {
+        self.client.subscribe_cmd(channels).await?;
+
+        self.add_channels(channels);
+
+        Ok(())
+    }
+
+    // 重复订阅同一个channel时服务器只会保留一个订阅，这里也只记录一次
+    fn add_channels(&mut self, channels: &[String]) {
+        for channel in channels {
+            if !self.subscribed_channels.contains(channel) {
+                self.subscribed_channels.push(channel.clone());
+            }
+        }
+    }
+
+    // 取消订阅channels，channels为空时取消所有的订阅
+    pub async fn unsubscribe(&mut self, channels: &[String]) -> Result<()> {
+        let frame = Unsubscribe::new(channels.to_vec()).into_frame();
+        self.client.send(&frame).await?;
+
+        // 服务器会为每个参数返回一个 ["unsubscribe", channel, 剩余订阅数量]，
+        // 即使这个channel并没有被订阅。没有参数时为每个被取消的channel返回一个，
+        // 一个订阅都没有的话也会返回一个channel为nil的响应，因此一直读到剩余数量为0
+        let mut remaining = channels.len();
+
+        loop {
+            let response = self.client.read_response().await?;
+
+            let num_subs = match response {
+                Frame::Array(ref frame) | Frame::Push(ref frame) => match frame.as_slice() {
+                    [unsubscribe, channel, Frame::Integer(num_subs)]
+                        if *unsubscribe == "unsubscribe" =>
+                    {
+                        // 从已订阅的列表中移除这个channel，没有订阅过的channel直接忽略
+                        if let Some(i) = self
+                            .subscribed_channels
+                            .iter()
+                            .position(|c| *channel == c.as_str())
+                        {
+                            self.subscribed_channels.remove(i);
+                        }
+
+                        *num_subs
+                    }
+                    _ => return Err(Error::UnexpectedFrame(response)),
+                },
+                frame => return Err(Error::UnexpectedFrame(frame)),
+            };
+
+            if channels.is_empty() {
+                if num_subs == 0 {
+                    break;
+                }
+            } else {
+                remaining -= 1;
+                if remaining == 0 {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Stream for Subscriber {
+    type Item = Result<Message>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let frame = ready!(self.get_mut().client.connection.poll_read_frame(cx));
+
+        Poll::Ready(match frame {
+            Ok(Some(mframe)) => Some(parse_message(mframe)),
+            Ok(None) => None,
+            Err(err) => Some(Err(err.into())),
+        })
+    }
+}
+
+// 将订阅模式下收到的frame转换成消息，只接受 ["message", channel, content]
+fn parse_message(mframe: Frame) -> Result<Message> {
+    debug!(?mframe);
+
+    match mframe {
+        Frame::Array(ref frame) | Frame::Push(ref frame) => match frame.as_slice() {
+            [message, channel, Frame::Bulk(content)] if *message == "message" => Ok(Message {
+                channel: channel.to_string(),
+                content: content.clone(),
+            }),
+            _ => Err(Error::UnexpectedFrame(mframe)),
+        },
+        Frame::Error(msg) => Err(Error::Server(msg)),
+        frame => Err(Error::UnexpectedFrame(frame)),
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Other(value.into())
+    }
+}
+
+impl From<crate::Error> for Error {
+    fn from(value: crate::Error) -> Self {
+        Error::Other(value)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Server(msg) => msg.fmt(f),
+            Error::UnexpectedFrame(frame) => write!(f, "unexpected frame: {}", frame),
+            Error::ConnectionReset => "connection reset by server".fmt(f),
+            Error::Other(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,45 @@
+use bytes::Bytes;
+
+use crate::{connection::Connection, db::Db, frame::Frame, parse::Parse};
+
+#[derive(Debug)]
+pub struct Append {
+    key: String,
+    value: Bytes,
+}
+
+impl Append {
+    pub fn new(key: impl ToString, value: Bytes) -> Append {
+        Append {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    // APPEND key value
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Append> {
+        let key = parse.next_string()?;
+        let value = parse.next_bytes()?;
+        Ok(Append { key, value })
+    }
+
+    // 追加数据，key不存在时相当于SET，返回追加后的长度
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.append(&self.key, self.value) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use std::{future, time::Duration};
+
+use bytes::Bytes;
+use tokio::time;
+
+use crate::{
+    connection::Connection,
+    db::{BlockingPop, Db, ListEnd},
+    frame::Frame,
+    parse::{parse_f64, Parse},
+    shutdown::Shutdown,
+};
+
+// timeout为None表示一直阻塞，直到有元素为止
+#[derive(Debug)]
+pub struct BLPop {
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct BRPop {
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct BLMove {
+    source: String,
+    destination: String,
+    // 从source的哪一端弹出
+    from: ListEnd,
+    // 写入destination的哪一端
+    to: ListEnd,
+    timeout: Option<Duration>,
+}
+
+// 阻塞等待的结果
+enum Wakeup {
+    // 弹出元素的key以及弹出的元素
+    Popped(String, Bytes),
+    TimedOut,
+    // 服务器关闭或者客户端断开，不需要再响应
+    Aborted,
+}
+
+impl BLPop {
+    pub fn new(keys: Vec<String>, timeout: Option<Duration>) -> BLPop {
+        BLPop { keys, timeout }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    // BLPOP key [key ...] timeout
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<BLPop> {
+        let keys = parse_keys(parse, "blpop")?;
+        let timeout = parse_timeout(parse)?;
+        Ok(BLPop { keys, timeout })
+    }
+
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        apply_pop(db, dst, shutdown, &self.keys, ListEnd::Left, self.timeout).await
+    }
+}
+
+impl BRPop {
+    pub fn new(keys: Vec<String>, timeout: Option<Duration>) -> BRPop {
+        BRPop { keys, timeout }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    // BRPOP key [key ...] timeout
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<BRPop> {
+        let keys = parse_keys(parse, "brpop")?;
+        let timeout = parse_timeout(parse)?;
+        Ok(BRPop { keys, timeout })
+    }
+
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        apply_pop(db, dst, shutdown, &self.keys, ListEnd::Right, self.timeout).await
+    }
+}
+
+impl BLMove {
+    pub fn new(
+        source: impl ToString,
+        destination: impl ToString,
+        from: ListEnd,
+        to: ListEnd,
+        timeout: Option<Duration>,
+    ) -> BLMove {
+        BLMove {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            from,
+            to,
+            timeout,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn from(&self) -> ListEnd {
+        self.from
+    }
+
+    pub fn to(&self) -> ListEnd {
+        self.to
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    // BLMOVE source destination <LEFT | RIGHT> <LEFT | RIGHT> timeout
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<BLMove> {
+        let source = parse.next_string()?;
+        let destination = parse.next_string()?;
+        let from = parse_end(parse)?;
+        let to = parse_end(parse)?;
+        let timeout = parse_timeout(parse)?;
+        Ok(BLMove {
+            source,
+            destination,
+            from,
+            to,
+            timeout,
+        })
+    }
+
+    // 返回被移动的元素，超时时返回Null
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let keys = [self.source];
+        let destination = Some((self.destination, self.to));
+
+        let response = match db.blocking_pop(&keys, self.from, destination) {
+            Ok(popped) => match wait(popped, self.timeout, dst, shutdown).await? {
+                Wakeup::Popped(_, value) => Frame::Bulk(value),
+                Wakeup::TimedOut => Frame::Null,
+                Wakeup::Aborted => return Ok(()),
+            },
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+// 最后一个参数是timeout，前面的都是key，至少要有一个key
+fn parse_keys(parse: &mut Parse, command: &str) -> crate::Result<Vec<String>> {
+    if parse.remaining() < 2 {
+        return Err(format!("wrong number of arguments for '{}' command", command).into());
+    }
+
+    let mut keys = Vec::with_capacity(parse.remaining() - 1);
+    while parse.remaining() > 1 {
+        keys.push(parse.next_string()?);
+    }
+
+    Ok(keys)
+}
+
+// 和redis一样，timeout的单位是秒，可以是小数，0表示一直阻塞
+fn parse_timeout(parse: &mut Parse) -> crate::Result<Option<Duration>> {
+    let timeout =
+        parse_f64(&parse.next_bytes()?).ok_or("timeout is not a float or out of range")?;
+
+    if timeout < 0.0 {
+        return Err("timeout is negative".into());
+    }
+
+    if timeout == 0.0 {
+        return Ok(None);
+    }
+
+    let timeout = Duration::try_from_secs_f64(timeout).map_err(|_| "timeout is out of range")?;
+    Ok(Some(timeout))
+}
+
+fn parse_end(parse: &mut Parse) -> crate::Result<ListEnd> {
+    match &parse.next_string()?.to_uppercase()[..] {
+        "LEFT" => Ok(ListEnd::Left),
+        "RIGHT" => Ok(ListEnd::Right),
+        _ => Err("syntax error".into()),
+    }
+}
+
+// 返回弹出元素的key和元素组成的数组，超时时返回Null
+async fn apply_pop(
+    db: &Db,
+    dst: &mut Connection,
+    shutdown: &mut Shutdown,
+    keys: &[String],
+    end: ListEnd,
+    timeout: Option<Duration>,
+) -> crate::Result<()> {
+    let response = match db.blocking_pop(keys, end, None) {
+        Ok(popped) => match wait(popped, timeout, dst, shutdown).await? {
+            Wakeup::Popped(key, value) => {
+                let mut response = Frame::array();
+                response.push_bulk(Bytes::from(key));
+                response.push_bulk(value);
+                response
+            }
+            Wakeup::TimedOut => Frame::Null,
+            Wakeup::Aborted => return Ok(()),
+        },
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+// 列表中没有元素时阻塞，直到拿到元素、超时、服务器关闭或者客户端断开
+async fn wait(
+    popped: BlockingPop,
+    timeout: Option<Duration>,
+    dst: &mut Connection,
+    shutdown: &mut Shutdown,
+) -> crate::Result<Wakeup> {
+    let mut waiter = match popped {
+        BlockingPop::Popped(key, value) => return Ok(Wakeup::Popped(key, value)),
+        BlockingPop::Blocked(waiter) => waiter,
+    };
+
+    // 同一批pipeline中前面的命令的响应还在缓冲区中，阻塞之前需要先发送给客户端
+    dst.flush().await?;
+
+    let sleep = async {
+        match timeout {
+            Some(timeout) => time::sleep(timeout).await,
+            None => future::pending().await,
+        }
+    };
+
+    let wakeup = tokio::select! {
+        popped = waiter.recv() => match popped {
+            Some((key, value)) => Wakeup::Popped(key, value),
+            // Db已经关闭
+            None => Wakeup::Aborted,
+        },
+        _ = sleep => match waiter.cancel() {
+            Some((key, value)) => Wakeup::Popped(key, value),
+            None => Wakeup::TimedOut,
+        },
+        _ = shutdown.recv() => Wakeup::Aborted,
+        _ = dst.closed() => Wakeup::Aborted,
+    };
+
+    Ok(wakeup)
+}
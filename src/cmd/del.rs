@@ -0,0 +1,91 @@
+use crate::{connection::Connection, db::Db, frame::Frame, parse::Parse};
+
+#[derive(Debug)]
+pub struct Del {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Unlink {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Exists {
+    keys: Vec<String>,
+}
+
+impl Del {
+    pub fn new(keys: Vec<String>) -> Del {
+        Del { keys }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    // DEL key [key ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Del> {
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.remaining_strings()?);
+        Ok(Del { keys })
+    }
+
+    // 返回实际删除的key的数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Integer(db.del(&self.keys) as i64);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl Unlink {
+    pub fn new(keys: Vec<String>) -> Unlink {
+        Unlink { keys }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    // UNLINK key [key ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Unlink> {
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.remaining_strings()?);
+        Ok(Unlink { keys })
+    }
+
+    // 和DEL的返回值相同，区别在于较大的值会在后台释放
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Integer(db.unlink(&self.keys) as i64);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl Exists {
+    pub fn new(keys: Vec<String>) -> Exists {
+        Exists { keys }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    // EXISTS key [key ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Exists> {
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.remaining_strings()?);
+        Ok(Exists { keys })
+    }
+
+    // 返回存在的key的数量，同一个key出现多次的话会被计算多次
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Integer(db.exists(&self.keys) as i64);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,403 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    connection::Connection,
+    db::{Db, ExpireCondition, Expiry},
+    frame::Frame,
+    parse::Parse,
+};
+
+// EXPIRE、PEXPIRE、EXPIREAT、PEXPIREAT的区别只在于时间的单位以及是相对时间还是unix时间，
+// 解析之后都转换成Expiry
+#[derive(Debug)]
+pub struct Expire {
+    key: String,
+    expiry: Expiry,
+    condition: ExpireCondition,
+}
+
+#[derive(Debug)]
+pub struct PExpire {
+    key: String,
+    expiry: Expiry,
+    condition: ExpireCondition,
+}
+
+#[derive(Debug)]
+pub struct ExpireAt {
+    key: String,
+    expiry: Expiry,
+    condition: ExpireCondition,
+}
+
+#[derive(Debug)]
+pub struct PExpireAt {
+    key: String,
+    expiry: Expiry,
+    condition: ExpireCondition,
+}
+
+#[derive(Debug)]
+pub struct Persist {
+    key: String,
+}
+
+// TTL、PTTL、EXPIRETIME、PEXPIRETIME都只需要一个key
+#[derive(Debug)]
+pub struct Ttl {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct PTtl {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ExpireTime {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct PExpireTime {
+    key: String,
+}
+
+impl Expire {
+    pub fn new(key: impl ToString, expire: Duration, condition: ExpireCondition) -> Expire {
+        Expire {
+            key: key.to_string(),
+            expiry: Expiry::After(expire),
+            condition,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // EXPIRE key seconds [NX | XX | GT | LT]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Expire> {
+        let key = parse.next_string()?;
+        let expiry = parse_relative(parse.next_signed()?.checked_mul(1000), "expire")?;
+        let condition = parse_condition(parse)?;
+        Ok(Expire {
+            key,
+            expiry,
+            condition,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_expire(db, dst, &self.key, self.expiry, self.condition).await
+    }
+}
+
+impl PExpire {
+    pub fn new(key: impl ToString, expire: Duration, condition: ExpireCondition) -> PExpire {
+        PExpire {
+            key: key.to_string(),
+            expiry: Expiry::After(expire),
+            condition,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // PEXPIRE key milliseconds [NX | XX | GT | LT]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<PExpire> {
+        let key = parse.next_string()?;
+        let expiry = parse_relative(Some(parse.next_signed()?), "pexpire")?;
+        let condition = parse_condition(parse)?;
+        Ok(PExpire {
+            key,
+            expiry,
+            condition,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_expire(db, dst, &self.key, self.expiry, self.condition).await
+    }
+}
+
+impl ExpireAt {
+    pub fn new(key: impl ToString, at: SystemTime, condition: ExpireCondition) -> ExpireAt {
+        ExpireAt {
+            key: key.to_string(),
+            expiry: Expiry::At(at),
+            condition,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // EXPIREAT key unix-time-seconds [NX | XX | GT | LT]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ExpireAt> {
+        let key = parse.next_string()?;
+        let expiry = parse_absolute(parse.next_signed()?.checked_mul(1000), "expireat")?;
+        let condition = parse_condition(parse)?;
+        Ok(ExpireAt {
+            key,
+            expiry,
+            condition,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_expire(db, dst, &self.key, self.expiry, self.condition).await
+    }
+}
+
+impl PExpireAt {
+    pub fn new(key: impl ToString, at: SystemTime, condition: ExpireCondition) -> PExpireAt {
+        PExpireAt {
+            key: key.to_string(),
+            expiry: Expiry::At(at),
+            condition,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // PEXPIREAT key unix-time-milliseconds [NX | XX | GT | LT]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<PExpireAt> {
+        let key = parse.next_string()?;
+        let expiry = parse_absolute(Some(parse.next_signed()?), "pexpireat")?;
+        let condition = parse_condition(parse)?;
+        Ok(PExpireAt {
+            key,
+            expiry,
+            condition,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_expire(db, dst, &self.key, self.expiry, self.condition).await
+    }
+}
+
+// 时间为0或者负数时key会被立即删除，这里统一转换成一个已经过去的时间
+fn parse_relative(ms: Option<i64>, command: &str) -> crate::Result<Expiry> {
+    match ms {
+        Some(ms) if ms > 0 && fits_unix_ms(ms) => {
+            Ok(Expiry::After(Duration::from_millis(ms as u64)))
+        }
+        Some(ms) if ms <= 0 => Ok(Expiry::At(UNIX_EPOCH)),
+        _ => Err(format!("invalid expire time in '{}' command", command).into()),
+    }
+}
+
+// 和redis一样，相对时间加上当前的unix时间(毫秒)之后不能超出i64的范围
+pub(crate) fn fits_unix_ms(ms: i64) -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    ms.checked_add(now).is_some()
+}
+
+fn parse_absolute(ms: Option<i64>, command: &str) -> crate::Result<Expiry> {
+    match ms {
+        Some(ms) => Ok(Expiry::At(
+            UNIX_EPOCH + Duration::from_millis(ms.max(0) as u64),
+        )),
+        None => Err(format!("invalid expire time in '{}' command", command).into()),
+    }
+}
+
+// [NX | XX | GT | LT]，XX可以和GT或者LT一起使用
+fn parse_condition(parse: &mut Parse) -> crate::Result<ExpireCondition> {
+    let mut condition = ExpireCondition::default();
+
+    for option in parse.remaining_strings()? {
+        match &option.to_uppercase()[..] {
+            "NX" => condition.nx = true,
+            "XX" => condition.xx = true,
+            "GT" => condition.gt = true,
+            "LT" => condition.lt = true,
+            _ => return Err(format!("Unsupported option {}", option).into()),
+        }
+    }
+
+    if condition.nx && (condition.xx || condition.gt || condition.lt) {
+        return Err("NX and XX, GT or LT options at the same time are not compatible".into());
+    }
+
+    if condition.gt && condition.lt {
+        return Err("GT and LT options at the same time are not compatible".into());
+    }
+
+    Ok(condition)
+}
+
+// 设置成功返回1，key不存在或者不满足条件时返回0
+async fn apply_expire(
+    db: &Db,
+    dst: &mut Connection,
+    key: &str,
+    expiry: Expiry,
+    condition: ExpireCondition,
+) -> crate::Result<()> {
+    let response = Frame::Integer(db.expire(key, expiry, condition) as i64);
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+impl Persist {
+    pub fn new(key: impl ToString) -> Persist {
+        Persist {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // PERSIST key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Persist> {
+        let key = parse.next_string()?;
+        Ok(Persist { key })
+    }
+
+    // 清除了过期时间返回1，key不存在或者本来就没有过期时间返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Integer(db.persist(&self.key) as i64);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl Ttl {
+    pub fn new(key: impl ToString) -> Ttl {
+        Ttl {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // TTL key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Ttl> {
+        let key = parse.next_string()?;
+        Ok(Ttl { key })
+    }
+
+    // 剩余的秒数，和redis一样四舍五入
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let ttl = db.ttl(&self.key);
+        write_ttl(dst, ttl, |ttl| {
+            (ttl.as_millis() as i64).saturating_add(500) / 1000
+        })
+        .await
+    }
+}
+
+impl PTtl {
+    pub fn new(key: impl ToString) -> PTtl {
+        PTtl {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // PTTL key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<PTtl> {
+        let key = parse.next_string()?;
+        Ok(PTtl { key })
+    }
+
+    // 剩余的毫秒数
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let ttl = db.ttl(&self.key);
+        write_ttl(dst, ttl, |ttl| ttl.as_millis() as i64).await
+    }
+}
+
+impl ExpireTime {
+    pub fn new(key: impl ToString) -> ExpireTime {
+        ExpireTime {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // EXPIRETIME key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ExpireTime> {
+        let key = parse.next_string()?;
+        Ok(ExpireTime { key })
+    }
+
+    // 过期时间对应的unix时间，单位为秒
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let ttl = db.ttl(&self.key);
+        write_ttl(dst, ttl, |ttl| unix_time(ttl).as_secs() as i64).await
+    }
+}
+
+impl PExpireTime {
+    pub fn new(key: impl ToString) -> PExpireTime {
+        PExpireTime {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // PEXPIRETIME key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<PExpireTime> {
+        let key = parse.next_string()?;
+        Ok(PExpireTime { key })
+    }
+
+    // 过期时间对应的unix时间，单位为毫秒
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let ttl = db.ttl(&self.key);
+        write_ttl(dst, ttl, |ttl| unix_time(ttl).as_millis() as i64).await
+    }
+}
+
+// 剩余的存活时间加上当前时间，得到过期时的unix时间
+// 过期时间保存为Instant，和SystemTime换算时会有微秒级的误差，四舍五入到毫秒，
+// 否则EXPIREAT设置的时间读出来可能少1
+fn unix_time(ttl: Duration) -> Duration {
+    let time = (SystemTime::now() + ttl)
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Duration::from_millis(((time.as_micros() + 500) / 1000) as u64)
+}
+
+// key不存在时返回-2，没有过期时间时返回-1，否则按照convert转换剩余的存活时间
+async fn write_ttl(
+    dst: &mut Connection,
+    ttl: Option<Option<Duration>>,
+    convert: impl FnOnce(Duration) -> i64,
+) -> crate::Result<()> {
+    let response = match ttl {
+        None => Frame::Integer(-2),
+        Some(None) => Frame::Integer(-1),
+        Some(Some(ttl)) => Frame::Integer(convert(ttl)),
+    };
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
@@ -1,18 +1,37 @@
-use crate::parse::Parse;
+use bytes::Bytes;
+
+use crate::{
+    cmd::set::parse_expire,
+    connection::Connection,
+    db::{Db, Expiry},
+    frame::Frame,
+    parse::{Parse, ParseError},
+};
 
 #[derive(Debug)]
 pub struct Get {
     key: String,
 }
 
+#[derive(Debug)]
+pub struct GetDel {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct GetEx {
+    key: String,
+    expire: Expiry,
+}
+
 impl Get {
-    fn new(key: impl ToString) -> Self {
+    pub fn new(key: impl ToString) -> Self {
         Get {
             key: key.to_string(),
         }
     }
 
-    fn key(&self) -> &str {
+    pub fn key(&self) -> &str {
         &self.key
     }
 
@@ -21,4 +40,113 @@ impl Get {
         let key = parse.next_string()?;
         Ok(Get { key })
     }
+
+    // 从db中读取key对应的值，不存在的话返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.get(&self.key) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    // 将命令转换成frame，由客户端发送给服务器
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from_static(b"get"));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
+
+impl GetDel {
+    pub fn new(key: impl ToString) -> GetDel {
+        GetDel {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // GETDEL key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<GetDel> {
+        let key = parse.next_string()?;
+        Ok(GetDel { key })
+    }
+
+    // 返回key对应的值并将key删除，不存在的话返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.get_del(&self.key) {
+            Ok(value) => value.map_or(Frame::Null, Frame::Bulk),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl GetEx {
+    pub fn new(key: impl ToString, expire: Expiry) -> GetEx {
+        GetEx {
+            key: key.to_string(),
+            expire,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn expire(&self) -> Expiry {
+        self.expire
+    }
+
+    // GETEX key [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+    //   PXAT unix-time-milliseconds | PERSIST]
+    // 不带参数时不会修改过期时间
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<GetEx> {
+        let key = parse.next_string()?;
+
+        let option = match parse.next_string() {
+            Ok(option) => option.to_uppercase(),
+            Err(ParseError::EndOfStream) => {
+                return Ok(GetEx {
+                    key,
+                    expire: Expiry::Keep,
+                })
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let expire = match &option[..] {
+            "PERSIST" => Expiry::Persist,
+            "EX" | "PX" | "EXAT" | "PXAT" => parse_expire(&option, parse, "getex")?,
+            _ => return Err("syntax error".into()),
+        };
+
+        // 只能指定一个过期时间参数
+        if parse.finish().is_err() {
+            return Err("syntax error".into());
+        }
+
+        Ok(GetEx { key, expire })
+    }
+
+    // 返回key对应的值，同时修改它的过期时间
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.get_ex(&self.key, self.expire) {
+            Ok(value) => value.map_or(Frame::Null, Frame::Bulk),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
 }
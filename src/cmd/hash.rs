@@ -0,0 +1,428 @@
+use bytes::Bytes;
+
+use crate::{connection::Connection, db::Db, frame::Frame, parse::Parse};
+
+#[derive(Debug)]
+pub struct HSet {
+    key: String,
+    pairs: Vec<(Bytes, Bytes)>,
+}
+
+#[derive(Debug)]
+pub struct HGet {
+    key: String,
+    field: Bytes,
+}
+
+#[derive(Debug)]
+pub struct HMGet {
+    key: String,
+    fields: Vec<Bytes>,
+}
+
+#[derive(Debug)]
+pub struct HDel {
+    key: String,
+    fields: Vec<Bytes>,
+}
+
+#[derive(Debug)]
+pub struct HGetAll {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct HIncrBy {
+    key: String,
+    field: Bytes,
+    increment: i64,
+}
+
+#[derive(Debug)]
+pub struct HExists {
+    key: String,
+    field: Bytes,
+}
+
+#[derive(Debug)]
+pub struct HLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct HKeys {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct HVals {
+    key: String,
+}
+
+impl HSet {
+    pub fn new(key: impl ToString, pairs: Vec<(Bytes, Bytes)>) -> HSet {
+        HSet {
+            key: key.to_string(),
+            pairs,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn pairs(&self) -> &[(Bytes, Bytes)] {
+        &self.pairs
+    }
+
+    // HSET key field value [field value ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HSet> {
+        let key = parse.next_string()?;
+
+        // 参数必须是成对的field value
+        if parse.remaining() == 0 || !parse.remaining().is_multiple_of(2) {
+            return Err("wrong number of arguments for 'hset' command".into());
+        }
+
+        let mut pairs = Vec::with_capacity(parse.remaining() / 2);
+        while parse.remaining() > 0 {
+            let field = parse.next_bytes()?;
+            let value = parse.next_bytes()?;
+            pairs.push((field, value));
+        }
+
+        Ok(HSet { key, pairs })
+    }
+
+    // 返回新增的field的数量，已经存在的field的值会被覆盖但不计算在内
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.hset(&self.key, self.pairs), dst).await
+    }
+}
+
+impl HGet {
+    pub fn new(key: impl ToString, field: Bytes) -> HGet {
+        HGet {
+            key: key.to_string(),
+            field,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn field(&self) -> &Bytes {
+        &self.field
+    }
+
+    // HGET key field
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HGet> {
+        let key = parse.next_string()?;
+        let field = parse.next_bytes()?;
+        Ok(HGet { key, field })
+    }
+
+    // 返回field对应的值，key或者field不存在时返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hget(&self.key, &self.field) {
+            Ok(value) => value.map_or(Frame::Null, Frame::Bulk),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl HMGet {
+    pub fn new(key: impl ToString, fields: Vec<Bytes>) -> HMGet {
+        HMGet {
+            key: key.to_string(),
+            fields,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn fields(&self) -> &[Bytes] {
+        &self.fields
+    }
+
+    // HMGET key field [field ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HMGet> {
+        let key = parse.next_string()?;
+        let fields = parse_fields(parse)?;
+        Ok(HMGet { key, fields })
+    }
+
+    // 按照field的顺序返回对应的值，不存在的field返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hmget(&self.key, &self.fields) {
+            Ok(values) => {
+                let mut response = Frame::array();
+                for value in values {
+                    match value {
+                        Some(value) => response.push_bulk(value),
+                        None => response.push_null(),
+                    }
+                }
+                response
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl HDel {
+    pub fn new(key: impl ToString, fields: Vec<Bytes>) -> HDel {
+        HDel {
+            key: key.to_string(),
+            fields,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn fields(&self) -> &[Bytes] {
+        &self.fields
+    }
+
+    // HDEL key field [field ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HDel> {
+        let key = parse.next_string()?;
+        let fields = parse_fields(parse)?;
+        Ok(HDel { key, fields })
+    }
+
+    // 返回实际删除的field的数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.hdel(&self.key, &self.fields), dst).await
+    }
+}
+
+impl HGetAll {
+    pub fn new(key: impl ToString) -> HGetAll {
+        HGetAll {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // HGETALL key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HGetAll> {
+        let key = parse.next_string()?;
+        Ok(HGetAll { key })
+    }
+
+    // RESP3下返回Map，RESP2下会被展开成 [field1, value1, field2, value2...] 的数组
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hgetall(&self.key) {
+            Ok(pairs) => Frame::Map(
+                pairs
+                    .into_iter()
+                    .map(|(field, value)| (Frame::Bulk(field), Frame::Bulk(value)))
+                    .collect(),
+            ),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl HIncrBy {
+    pub fn new(key: impl ToString, field: Bytes, increment: i64) -> HIncrBy {
+        HIncrBy {
+            key: key.to_string(),
+            field,
+            increment,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn field(&self) -> &Bytes {
+        &self.field
+    }
+
+    pub fn increment(&self) -> i64 {
+        self.increment
+    }
+
+    // HINCRBY key field increment
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HIncrBy> {
+        let key = parse.next_string()?;
+        let field = parse.next_bytes()?;
+        let increment = parse.next_signed()?;
+        Ok(HIncrBy {
+            key,
+            field,
+            increment,
+        })
+    }
+
+    // 返回计算之后的值
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hincr_by(&self.key, self.field, self.increment) {
+            Ok(value) => Frame::Integer(value),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl HExists {
+    pub fn new(key: impl ToString, field: Bytes) -> HExists {
+        HExists {
+            key: key.to_string(),
+            field,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn field(&self) -> &Bytes {
+        &self.field
+    }
+
+    // HEXISTS key field
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HExists> {
+        let key = parse.next_string()?;
+        let field = parse.next_bytes()?;
+        Ok(HExists { key, field })
+    }
+
+    // field存在时返回1，否则返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let exists = db.hexists(&self.key, &self.field).map(usize::from);
+        write_len(exists, dst).await
+    }
+}
+
+impl HLen {
+    pub fn new(key: impl ToString) -> HLen {
+        HLen {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // HLEN key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HLen> {
+        let key = parse.next_string()?;
+        Ok(HLen { key })
+    }
+
+    // 返回field的数量，key不存在时返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.hlen(&self.key), dst).await
+    }
+}
+
+impl HKeys {
+    pub fn new(key: impl ToString) -> HKeys {
+        HKeys {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // HKEYS key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HKeys> {
+        let key = parse.next_string()?;
+        Ok(HKeys { key })
+    }
+
+    // 返回所有的field，key不存在时返回空数组
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_bulks(db.hkeys(&self.key), dst).await
+    }
+}
+
+impl HVals {
+    pub fn new(key: impl ToString) -> HVals {
+        HVals {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // HVALS key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HVals> {
+        let key = parse.next_string()?;
+        Ok(HVals { key })
+    }
+
+    // 返回所有的value，key不存在时返回空数组
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_bulks(db.hvals(&self.key), dst).await
+    }
+}
+
+// field至少要有一个
+fn parse_fields(parse: &mut Parse) -> crate::Result<Vec<Bytes>> {
+    let mut fields = vec![parse.next_bytes()?];
+    while parse.remaining() > 0 {
+        fields.push(parse.next_bytes()?);
+    }
+    Ok(fields)
+}
+
+async fn write_len(len: crate::Result<usize>, dst: &mut Connection) -> crate::Result<()> {
+    let response = match len {
+        Ok(len) => Frame::Integer(len as i64),
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+async fn write_bulks(values: crate::Result<Vec<Bytes>>, dst: &mut Connection) -> crate::Result<()> {
+    let response = match values {
+        Ok(values) => {
+            let mut response = Frame::array();
+            for value in values {
+                response.push_bulk(value);
+            }
+            response
+        }
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use bytes::Bytes;
+
+use crate::{
+    connection::Connection,
+    frame::{Frame, Protocol},
+    parse::{Parse, ParseError},
+};
+
+#[derive(Debug, Default)]
+pub struct Hello {
+    protover: Option<u64>,
+}
+
+impl Hello {
+    pub fn new(protover: Option<u64>) -> Hello {
+        Hello { protover }
+    }
+
+    pub fn protover(&self) -> Option<u64> {
+        self.protover
+    }
+
+    // HELLO [protover]
+    // 不带参数时不切换协议，只返回服务器的信息
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Hello> {
+        match parse.next_int() {
+            Ok(protover) => Ok(Hello::new(Some(protover))),
+            Err(ParseError::EndOfStream) => Ok(Hello::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // 切换连接的协议版本，并返回服务器的信息
+    // RESP3下返回的是Map，RESP2下会被展开成 [key1, value1, key2, value2...] 的数组
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let protocol = match self.protover {
+            None => dst.protocol(),
+            Some(2) => Protocol::Resp2,
+            Some(3) => Protocol::Resp3,
+            Some(_) => {
+                let response = Frame::Error("NOPROTO unsupported protocol version".to_string());
+                dst.write_frame(&response).await?;
+                return Ok(());
+            }
+        };
+
+        dst.set_protocol(protocol);
+
+        let proto = match protocol {
+            Protocol::Resp2 => 2,
+            Protocol::Resp3 => 3,
+        };
+
+        let field = |name: &'static str| Frame::Bulk(Bytes::from_static(name.as_bytes()));
+
+        let response = Frame::Map(vec![
+            (field("server"), field("redis")),
+            (field("version"), field(env!("CARGO_PKG_VERSION"))),
+            (field("proto"), Frame::Integer(proto)),
+            (field("mode"), field("standalone")),
+            (field("role"), field("master")),
+            (field("modules"), Frame::Array(vec![])),
+        ]);
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from_static(b"hello"));
+        if let Some(protover) = self.protover {
+            frame.push_bulk(Bytes::from(protover.to_string()));
+        }
+        frame
+    }
+}
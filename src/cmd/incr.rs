@@ -0,0 +1,183 @@
+use crate::{connection::Connection, db::Db, frame::Frame, parse::Parse};
+
+#[derive(Debug)]
+pub struct Incr {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct Decr {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct IncrBy {
+    key: String,
+    increment: i64,
+}
+
+#[derive(Debug)]
+pub struct DecrBy {
+    key: String,
+    decrement: i64,
+}
+
+#[derive(Debug)]
+pub struct IncrByFloat {
+    key: String,
+    increment: f64,
+}
+
+impl Incr {
+    pub fn new(key: impl ToString) -> Incr {
+        Incr {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // INCR key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Incr> {
+        let key = parse.next_string()?;
+        Ok(Incr { key })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_integer(db.incr_by(&self.key, 1), dst).await
+    }
+}
+
+impl Decr {
+    pub fn new(key: impl ToString) -> Decr {
+        Decr {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // DECR key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Decr> {
+        let key = parse.next_string()?;
+        Ok(Decr { key })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_integer(db.incr_by(&self.key, -1), dst).await
+    }
+}
+
+impl IncrBy {
+    pub fn new(key: impl ToString, increment: i64) -> IncrBy {
+        IncrBy {
+            key: key.to_string(),
+            increment,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn increment(&self) -> i64 {
+        self.increment
+    }
+
+    // INCRBY key increment
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<IncrBy> {
+        let key = parse.next_string()?;
+        let increment = parse.next_signed()?;
+        Ok(IncrBy { key, increment })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_integer(db.incr_by(&self.key, self.increment), dst).await
+    }
+}
+
+impl DecrBy {
+    pub fn new(key: impl ToString, decrement: i64) -> DecrBy {
+        DecrBy {
+            key: key.to_string(),
+            decrement,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn decrement(&self) -> i64 {
+        self.decrement
+    }
+
+    // DECRBY key decrement
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<DecrBy> {
+        let key = parse.next_string()?;
+        let decrement = parse.next_signed()?;
+        Ok(DecrBy { key, decrement })
+    }
+
+    // i64::MIN取反会溢出，和redis一样直接返回错误
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let result = match self.decrement.checked_neg() {
+            Some(delta) => db.incr_by(&self.key, delta),
+            None => Err("ERR decrement would overflow".into()),
+        };
+
+        write_integer(result, dst).await
+    }
+}
+
+impl IncrByFloat {
+    pub fn new(key: impl ToString, increment: f64) -> IncrByFloat {
+        IncrByFloat {
+            key: key.to_string(),
+            increment,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn increment(&self) -> f64 {
+        self.increment
+    }
+
+    // INCRBYFLOAT key increment
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<IncrByFloat> {
+        let key = parse.next_string()?;
+        let increment = parse.next_float()?;
+        Ok(IncrByFloat { key, increment })
+    }
+
+    // 和redis一样以字符串的形式返回计算结果
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.incr_by_float(&self.key, self.increment) {
+            Ok(value) => Frame::Bulk(value),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+// 计算成功时返回新的值，值不是整数或者溢出时把错误返回给客户端
+async fn write_integer(result: crate::Result<i64>, dst: &mut Connection) -> crate::Result<()> {
+    let response = match result {
+        Ok(value) => Frame::Integer(value),
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
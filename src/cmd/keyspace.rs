@@ -0,0 +1,523 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use bytes::Bytes;
+
+use crate::{
+    cmd::expire::fits_unix_ms,
+    connection::Connection,
+    db::{Db, Expiry, RestoreOptions},
+    frame::Frame,
+    parse::{Parse, ParseError},
+};
+
+#[derive(Debug)]
+pub struct Keys {
+    pattern: Bytes,
+}
+
+#[derive(Debug)]
+pub struct Type {
+    key: String,
+}
+
+#[derive(Debug, Default)]
+pub struct RandomKey {}
+
+#[derive(Debug, Default)]
+pub struct DbSize {}
+
+#[derive(Debug)]
+pub struct Rename {
+    key: String,
+    newkey: String,
+}
+
+#[derive(Debug)]
+pub struct RenameNx {
+    key: String,
+    newkey: String,
+}
+
+#[derive(Debug)]
+pub struct Copy {
+    source: String,
+    destination: String,
+    replace: bool,
+}
+
+// 只有一个db，MOVE总是返回错误，保留这个命令是为了给出和redis一致的错误信息
+#[derive(Debug)]
+pub struct Move {
+    key: String,
+    db: i64,
+}
+
+#[derive(Debug)]
+pub struct Dump {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct Restore {
+    key: String,
+    payload: Bytes,
+    options: RestoreOptions,
+}
+
+#[derive(Debug)]
+pub struct Object {
+    subcommand: ObjectSubcommand,
+    key: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectSubcommand {
+    Encoding,
+    IdleTime,
+    Freq,
+}
+
+impl Keys {
+    pub fn new(pattern: Bytes) -> Keys {
+        Keys { pattern }
+    }
+
+    pub fn pattern(&self) -> &Bytes {
+        &self.pattern
+    }
+
+    // KEYS pattern
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Keys> {
+        let pattern = parse.next_bytes()?;
+        Ok(Keys { pattern })
+    }
+
+    // 返回所有匹配pattern的key，key很多时会长时间持有锁，和redis一样只建议在调试时使用
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let mut response = Frame::array();
+        for key in db.keys(&self.pattern) {
+            response.push_bulk(Bytes::from(key));
+        }
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl Type {
+    pub fn new(key: impl ToString) -> Type {
+        Type {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // TYPE key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Type> {
+        let key = parse.next_string()?;
+        Ok(Type { key })
+    }
+
+    // 返回string、list、hash、set、zset、stream，key不存在时返回none
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let key_type = db.key_type(&self.key).unwrap_or("none");
+
+        let response = Frame::Simple(key_type.to_string());
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl RandomKey {
+    pub fn new() -> RandomKey {
+        RandomKey {}
+    }
+
+    // RANDOMKEY
+    pub fn parse_frame(_parse: &mut Parse) -> crate::Result<RandomKey> {
+        Ok(RandomKey {})
+    }
+
+    // 随机返回一个key，db为空时返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = db
+            .random_key()
+            .map_or(Frame::Null, |key| Frame::Bulk(Bytes::from(key)));
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl DbSize {
+    pub fn new() -> DbSize {
+        DbSize {}
+    }
+
+    // DBSIZE
+    pub fn parse_frame(_parse: &mut Parse) -> crate::Result<DbSize> {
+        Ok(DbSize {})
+    }
+
+    // 返回key的数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Integer(db.dbsize() as i64);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl Rename {
+    pub fn new(key: impl ToString, newkey: impl ToString) -> Rename {
+        Rename {
+            key: key.to_string(),
+            newkey: newkey.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn newkey(&self) -> &str {
+        &self.newkey
+    }
+
+    // RENAME key newkey
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Rename> {
+        let key = parse.next_string()?;
+        let newkey = parse.next_string()?;
+        Ok(Rename { key, newkey })
+    }
+
+    // 成功时返回OK，key不存在时返回错误
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.rename(&self.key, &self.newkey, false) {
+            Ok(_) => Frame::Simple("OK".to_string()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl RenameNx {
+    pub fn new(key: impl ToString, newkey: impl ToString) -> RenameNx {
+        RenameNx {
+            key: key.to_string(),
+            newkey: newkey.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn newkey(&self) -> &str {
+        &self.newkey
+    }
+
+    // RENAMENX key newkey
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<RenameNx> {
+        let key = parse.next_string()?;
+        let newkey = parse.next_string()?;
+        Ok(RenameNx { key, newkey })
+    }
+
+    // 成功时返回1，newkey已经存在时返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.rename(&self.key, &self.newkey, true) {
+            Ok(renamed) => Frame::Integer(renamed as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl Copy {
+    pub fn new(source: impl ToString, destination: impl ToString, replace: bool) -> Copy {
+        Copy {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            replace,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn replace(&self) -> bool {
+        self.replace
+    }
+
+    // COPY source destination [DB destination-db] [REPLACE]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Copy> {
+        let source = parse.next_string()?;
+        let destination = parse.next_string()?;
+
+        let mut replace = false;
+
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            match &option[..] {
+                "REPLACE" => replace = true,
+                "DB" => {
+                    if parse.next_signed()? != 0 {
+                        return Err("DB index is out of range".into());
+                    }
+                }
+                _ => return Err("syntax error".into()),
+            }
+        }
+
+        Ok(Copy {
+            source,
+            destination,
+            replace,
+        })
+    }
+
+    // 复制成功返回1，source不存在或者destination已经存在时返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.copy(&self.source, &self.destination, self.replace) {
+            Ok(copied) => Frame::Integer(copied as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl Move {
+    pub fn new(key: impl ToString, db: i64) -> Move {
+        Move {
+            key: key.to_string(),
+            db,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn db(&self) -> i64 {
+        self.db
+    }
+
+    // MOVE key db
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Move> {
+        let key = parse.next_string()?;
+        let db = parse.next_signed()?;
+        Ok(Move { key, db })
+    }
+
+    // 和redis一样先检查db的下标，再检查源和目标是否是同一个db
+    pub(crate) async fn apply(self, _db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = if self.db != 0 {
+            Frame::Error("ERR DB index is out of range".to_string())
+        } else {
+            Frame::Error("ERR source and destination objects are the same".to_string())
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl Dump {
+    pub fn new(key: impl ToString) -> Dump {
+        Dump {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // DUMP key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Dump> {
+        let key = parse.next_string()?;
+        Ok(Dump { key })
+    }
+
+    // 返回序列化之后的值，key不存在时返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = db.dump(&self.key).map_or(Frame::Null, Frame::Bulk);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl Restore {
+    pub fn new(key: impl ToString, payload: Bytes, options: RestoreOptions) -> Restore {
+        Restore {
+            key: key.to_string(),
+            payload,
+            options,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    pub fn options(&self) -> &RestoreOptions {
+        &self.options
+    }
+
+    // RESTORE key ttl serialized-value [REPLACE] [ABSTTL] [IDLETIME seconds] [FREQ frequency]
+    // ttl为0表示不过期，带ABSTTL时ttl是unix时间(毫秒)，否则是相对时间(毫秒)
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Restore> {
+        let key = parse.next_string()?;
+        let ttl = parse.next_signed()?;
+        let payload = parse.next_bytes()?;
+
+        if ttl < 0 {
+            return Err("Invalid TTL value, must be >= 0".into());
+        }
+
+        let mut options = RestoreOptions::default();
+        let mut absttl = false;
+
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            match &option[..] {
+                "REPLACE" => options.replace = true,
+                "ABSTTL" => absttl = true,
+                // IDLETIME和FREQ不能同时使用
+                "IDLETIME" if options.freq.is_none() => {
+                    let idle_time = u64::try_from(parse.next_signed()?)
+                        .map_err(|_| "Invalid IDLETIME value, must be >= 0")?;
+                    options.idle_time = Some(Duration::from_secs(idle_time));
+                }
+                "FREQ" if options.idle_time.is_none() => {
+                    let freq = u8::try_from(parse.next_signed()?)
+                        .map_err(|_| "Invalid FREQ value, must be >= 0 and <= 255")?;
+                    options.freq = Some(freq);
+                }
+                _ => return Err("syntax error".into()),
+            }
+        }
+
+        options.expire = match ttl {
+            0 => Expiry::Persist,
+            ms if absttl => Expiry::At(UNIX_EPOCH + Duration::from_millis(ms as u64)),
+            ms if fits_unix_ms(ms) => Expiry::After(Duration::from_millis(ms as u64)),
+            _ => return Err("Invalid TTL value, must be >= 0".into()),
+        };
+
+        Ok(Restore {
+            key,
+            payload,
+            options,
+        })
+    }
+
+    // 恢复成功返回OK，key已经存在并且没有REPLACE时返回BUSYKEY错误
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.restore(&self.key, &self.payload, self.options) {
+            Ok(()) => Frame::Simple("OK".to_string()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl Object {
+    pub fn new(subcommand: ObjectSubcommand, key: impl ToString) -> Object {
+        Object {
+            subcommand,
+            key: key.to_string(),
+        }
+    }
+
+    pub fn subcommand(&self) -> ObjectSubcommand {
+        self.subcommand
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // OBJECT ENCODING key
+    // OBJECT IDLETIME key
+    // OBJECT FREQ key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Object> {
+        let subcommand = parse.next_string()?;
+
+        let unknown = || {
+            format!(
+                "unknown subcommand or wrong number of arguments for '{}'. Try OBJECT HELP.",
+                subcommand
+            )
+        };
+
+        let kind = match &subcommand.to_uppercase()[..] {
+            "ENCODING" => ObjectSubcommand::Encoding,
+            "IDLETIME" => ObjectSubcommand::IdleTime,
+            "FREQ" => ObjectSubcommand::Freq,
+            _ => return Err(unknown().into()),
+        };
+
+        if parse.remaining() != 1 {
+            return Err(unknown().into());
+        }
+        let key = parse.next_string()?;
+
+        Ok(Object {
+            subcommand: kind,
+            key,
+        })
+    }
+
+    // key不存在时返回Null，IDLETIME的单位是秒
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.subcommand {
+            ObjectSubcommand::Encoding => db
+                .object_encoding(&self.key)
+                .map_or(Frame::Null, |encoding| Frame::Bulk(Bytes::from(encoding))),
+            ObjectSubcommand::IdleTime => db
+                .object_idle_time(&self.key)
+                .map_or(Frame::Null, |idle| Frame::Integer(idle.as_secs() as i64)),
+            ObjectSubcommand::Freq => db
+                .object_freq(&self.key)
+                .map_or(Frame::Null, |freq| Frame::Integer(freq as i64)),
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,538 @@
+use bytes::Bytes;
+
+use crate::{
+    connection::Connection,
+    db::{Db, ListEnd},
+    frame::Frame,
+    parse::Parse,
+};
+
+#[derive(Debug)]
+pub struct LPush {
+    key: String,
+    values: Vec<Bytes>,
+}
+
+#[derive(Debug)]
+pub struct RPush {
+    key: String,
+    values: Vec<Bytes>,
+}
+
+// LPOP、RPOP不带count时返回单个元素，带count时返回数组
+#[derive(Debug)]
+pub struct LPop {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct RPop {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct LRange {
+    key: String,
+    start: i64,
+    stop: i64,
+}
+
+#[derive(Debug)]
+pub struct LLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct LIndex {
+    key: String,
+    index: i64,
+}
+
+#[derive(Debug)]
+pub struct LSet {
+    key: String,
+    index: i64,
+    value: Bytes,
+}
+
+#[derive(Debug)]
+pub struct LRem {
+    key: String,
+    count: i64,
+    value: Bytes,
+}
+
+#[derive(Debug)]
+pub struct LTrim {
+    key: String,
+    start: i64,
+    stop: i64,
+}
+
+#[derive(Debug)]
+pub struct LInsert {
+    key: String,
+    // true表示插入到pivot之前
+    before: bool,
+    pivot: Bytes,
+    value: Bytes,
+}
+
+impl LPush {
+    pub fn new(key: impl ToString, values: Vec<Bytes>) -> LPush {
+        LPush {
+            key: key.to_string(),
+            values,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn values(&self) -> &[Bytes] {
+        &self.values
+    }
+
+    // LPUSH key element [element ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<LPush> {
+        let key = parse.next_string()?;
+        let values = parse_values(parse)?;
+        Ok(LPush { key, values })
+    }
+
+    // 元素依次插入到头部，所以最后一个元素会在最前面，返回插入之后列表的长度
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let len = db.push(&self.key, self.values, ListEnd::Left);
+        write_len(len, dst).await
+    }
+}
+
+impl RPush {
+    pub fn new(key: impl ToString, values: Vec<Bytes>) -> RPush {
+        RPush {
+            key: key.to_string(),
+            values,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn values(&self) -> &[Bytes] {
+        &self.values
+    }
+
+    // RPUSH key element [element ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<RPush> {
+        let key = parse.next_string()?;
+        let values = parse_values(parse)?;
+        Ok(RPush { key, values })
+    }
+
+    // 元素依次插入到尾部，返回插入之后列表的长度
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let len = db.push(&self.key, self.values, ListEnd::Right);
+        write_len(len, dst).await
+    }
+}
+
+impl LPop {
+    pub fn new(key: impl ToString, count: Option<usize>) -> LPop {
+        LPop {
+            key: key.to_string(),
+            count,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    // LPOP key [count]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<LPop> {
+        let key = parse.next_string()?;
+        let count = parse_count(parse)?;
+        Ok(LPop { key, count })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_pop(db, dst, &self.key, ListEnd::Left, self.count).await
+    }
+}
+
+impl RPop {
+    pub fn new(key: impl ToString, count: Option<usize>) -> RPop {
+        RPop {
+            key: key.to_string(),
+            count,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    // RPOP key [count]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<RPop> {
+        let key = parse.next_string()?;
+        let count = parse_count(parse)?;
+        Ok(RPop { key, count })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_pop(db, dst, &self.key, ListEnd::Right, self.count).await
+    }
+}
+
+// 元素至少要有一个
+fn parse_values(parse: &mut Parse) -> crate::Result<Vec<Bytes>> {
+    let mut values = vec![parse.next_bytes()?];
+    while parse.remaining() > 0 {
+        values.push(parse.next_bytes()?);
+    }
+    Ok(values)
+}
+
+// count是可选的，但是不能是负数
+fn parse_count(parse: &mut Parse) -> crate::Result<Option<usize>> {
+    if parse.remaining() == 0 {
+        return Ok(None);
+    }
+
+    let count = usize::try_from(parse.next_signed()?)
+        .map_err(|_| "value is out of range, must be positive")?;
+    Ok(Some(count))
+}
+
+// 不带count时返回弹出的元素，带count时返回弹出的元素组成的数组，key不存在时都返回Null
+async fn apply_pop(
+    db: &Db,
+    dst: &mut Connection,
+    key: &str,
+    end: ListEnd,
+    count: Option<usize>,
+) -> crate::Result<()> {
+    let response = match db.pop(key, end, count.unwrap_or(1)) {
+        Ok(None) => Frame::Null,
+        Ok(Some(values)) => match count {
+            Some(_) => {
+                let mut response = Frame::array();
+                for value in values {
+                    response.push_bulk(value);
+                }
+                response
+            }
+            None => values.into_iter().next().map_or(Frame::Null, Frame::Bulk),
+        },
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+impl LRange {
+    pub fn new(key: impl ToString, start: i64, stop: i64) -> LRange {
+        LRange {
+            key: key.to_string(),
+            start,
+            stop,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    pub fn stop(&self) -> i64 {
+        self.stop
+    }
+
+    // LRANGE key start stop
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<LRange> {
+        let key = parse.next_string()?;
+        let start = parse.next_signed()?;
+        let stop = parse.next_signed()?;
+        Ok(LRange { key, start, stop })
+    }
+
+    // 返回范围内的元素，范围超出时截断，key不存在时返回空数组
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.lrange(&self.key, self.start, self.stop) {
+            Ok(values) => {
+                let mut response = Frame::array();
+                for value in values {
+                    response.push_bulk(value);
+                }
+                response
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl LLen {
+    pub fn new(key: impl ToString) -> LLen {
+        LLen {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // LLEN key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<LLen> {
+        let key = parse.next_string()?;
+        Ok(LLen { key })
+    }
+
+    // 返回列表的长度，key不存在时返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.llen(&self.key), dst).await
+    }
+}
+
+impl LIndex {
+    pub fn new(key: impl ToString, index: i64) -> LIndex {
+        LIndex {
+            key: key.to_string(),
+            index,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn index(&self) -> i64 {
+        self.index
+    }
+
+    // LINDEX key index
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<LIndex> {
+        let key = parse.next_string()?;
+        let index = parse.next_signed()?;
+        Ok(LIndex { key, index })
+    }
+
+    // 返回index位置的元素，超出范围或者key不存在时返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.lindex(&self.key, self.index) {
+            Ok(value) => value.map_or(Frame::Null, Frame::Bulk),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl LSet {
+    pub fn new(key: impl ToString, index: i64, value: Bytes) -> LSet {
+        LSet {
+            key: key.to_string(),
+            index,
+            value,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn index(&self) -> i64 {
+        self.index
+    }
+
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    // LSET key index element
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<LSet> {
+        let key = parse.next_string()?;
+        let index = parse.next_signed()?;
+        let value = parse.next_bytes()?;
+        Ok(LSet { key, index, value })
+    }
+
+    // 修改成功返回OK
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let result = db.lset(&self.key, self.index, self.value);
+        write_ok(result, dst).await
+    }
+}
+
+impl LRem {
+    pub fn new(key: impl ToString, count: i64, value: Bytes) -> LRem {
+        LRem {
+            key: key.to_string(),
+            count,
+            value,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn count(&self) -> i64 {
+        self.count
+    }
+
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    // LREM key count element
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<LRem> {
+        let key = parse.next_string()?;
+        let count = parse.next_signed()?;
+        let value = parse.next_bytes()?;
+        Ok(LRem { key, count, value })
+    }
+
+    // 返回删除的元素的数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.lrem(&self.key, self.count, &self.value), dst).await
+    }
+}
+
+impl LTrim {
+    pub fn new(key: impl ToString, start: i64, stop: i64) -> LTrim {
+        LTrim {
+            key: key.to_string(),
+            start,
+            stop,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    pub fn stop(&self) -> i64 {
+        self.stop
+    }
+
+    // LTRIM key start stop
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<LTrim> {
+        let key = parse.next_string()?;
+        let start = parse.next_signed()?;
+        let stop = parse.next_signed()?;
+        Ok(LTrim { key, start, stop })
+    }
+
+    // 总是返回OK，key不存在时也一样
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let result = db.ltrim(&self.key, self.start, self.stop);
+        write_ok(result, dst).await
+    }
+}
+
+impl LInsert {
+    pub fn new(key: impl ToString, before: bool, pivot: Bytes, value: Bytes) -> LInsert {
+        LInsert {
+            key: key.to_string(),
+            before,
+            pivot,
+            value,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn before(&self) -> bool {
+        self.before
+    }
+
+    pub fn pivot(&self) -> &Bytes {
+        &self.pivot
+    }
+
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    // LINSERT key <BEFORE | AFTER> pivot element
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<LInsert> {
+        let key = parse.next_string()?;
+        let before = match &parse.next_string()?.to_uppercase()[..] {
+            "BEFORE" => true,
+            "AFTER" => false,
+            _ => return Err("syntax error".into()),
+        };
+        let pivot = parse.next_bytes()?;
+        let value = parse.next_bytes()?;
+        Ok(LInsert {
+            key,
+            before,
+            pivot,
+            value,
+        })
+    }
+
+    // 返回插入之后列表的长度，key不存在时返回0，找不到pivot时返回-1
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.linsert(&self.key, self.before, &self.pivot, self.value) {
+            Ok(len) => Frame::Integer(len),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+async fn write_len(len: crate::Result<usize>, dst: &mut Connection) -> crate::Result<()> {
+    let response = match len {
+        Ok(len) => Frame::Integer(len as i64),
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+async fn write_ok(result: crate::Result<()>, dst: &mut Connection) -> crate::Result<()> {
+    let response = match result {
+        Ok(()) => Frame::Simple("OK".to_string()),
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
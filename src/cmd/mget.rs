@@ -0,0 +1,38 @@
+use crate::{connection::Connection, db::Db, frame::Frame, parse::Parse};
+
+#[derive(Debug)]
+pub struct MGet {
+    keys: Vec<String>,
+}
+
+impl MGet {
+    pub fn new(keys: Vec<String>) -> MGet {
+        MGet { keys }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    // MGET key [key ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<MGet> {
+        let mut keys = vec![parse.next_string()?];
+        keys.extend(parse.remaining_strings()?);
+        Ok(MGet { keys })
+    }
+
+    // 按照key的顺序返回对应的值，不存在的key返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let mut response = Frame::array();
+        for value in db.mget(&self.keys) {
+            match value {
+                Some(value) => response.push_bulk(value),
+                None => response.push_null(),
+            }
+        }
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
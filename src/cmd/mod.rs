@@ -1,43 +1,302 @@
-use crate::{frame::Frame, parse::Parse};
+use crate::{connection::Connection, db::Db, frame::Frame, parse::Parse, shutdown::Shutdown};
 
-use get::Get;
-use ping::Ping;
-use publish::Publish;
-use set::Set;
-use subscribe::{Subscribe, Unsubscribe};
-use unknown::Unknown;
+pub use append::Append;
+pub use blocking::{BLMove, BLPop, BRPop};
+pub use del::{Del, Exists, Unlink};
+pub use expire::{
+    Expire, ExpireAt, ExpireTime, PExpire, PExpireAt, PExpireTime, PTtl, Persist, Ttl,
+};
+pub use get::{Get, GetDel, GetEx};
+pub use hash::{HDel, HExists, HGet, HGetAll, HIncrBy, HKeys, HLen, HMGet, HSet, HVals};
+pub use hello::Hello;
+pub use incr::{Decr, DecrBy, Incr, IncrBy, IncrByFloat};
+pub use keyspace::{
+    Copy, DbSize, Dump, Keys, Move, Object, ObjectSubcommand, RandomKey, Rename, RenameNx, Restore,
+    Type,
+};
+pub use list::{LIndex, LInsert, LLen, LPop, LPush, LRange, LRem, LSet, LTrim, RPop, RPush};
+pub use mget::MGet;
+pub use mset::{MSet, MSetNx};
+pub use ping::Ping;
+pub use publish::Publish;
+pub use range::{GetRange, SetRange};
+pub use scan::{HScan, SScan, Scan, ZScan};
+pub use set::{GetSet, Set, SetNx};
+pub use sets::{
+    SAdd, SCard, SDiff, SDiffStore, SInter, SInterStore, SIsMember, SMembers, SPop, SRandMember,
+    SRem, SUnion, SUnionStore,
+};
+pub use stream::{
+    XAck, XAdd, XAutoClaim, XClaim, XGroup, XGroupAction, XLen, XPending, XRange, XRead,
+    XReadGroup, XRevRange,
+};
+pub use strlen::Strlen;
+pub use subscribe::{Subscribe, Unsubscribe};
+pub use unknown::Unknown;
+pub use zset::{
+    ZAdd, ZCard, ZCount, ZIncrBy, ZInterStore, ZPopMax, ZPopMin, ZRange, ZRank, ZRem, ZRevRank,
+    ZScore, ZUnionStore,
+};
 
+mod append;
+mod blocking;
+mod del;
+mod expire;
 mod get;
+mod hash;
+mod hello;
+mod incr;
+mod keyspace;
+mod list;
+mod mget;
+mod mset;
 mod ping;
 mod publish;
+mod range;
+mod scan;
 mod set;
+mod sets;
+mod stream;
+mod strlen;
 mod subscribe;
 mod unknown;
+mod zset;
 
 #[derive(Debug)]
 pub enum Command {
+    Append(Append),
+    BLMove(BLMove),
+    BLPop(BLPop),
+    BRPop(BRPop),
+    Copy(Copy),
+    DbSize(DbSize),
+    Decr(Decr),
+    DecrBy(DecrBy),
+    Del(Del),
+    Dump(Dump),
+    Exists(Exists),
+    Expire(Expire),
+    ExpireAt(ExpireAt),
+    ExpireTime(ExpireTime),
     Get(Get),
+    GetDel(GetDel),
+    GetEx(GetEx),
+    GetRange(GetRange),
+    GetSet(GetSet),
+    HDel(HDel),
+    HExists(HExists),
+    HGet(HGet),
+    HGetAll(HGetAll),
+    HIncrBy(HIncrBy),
+    HKeys(HKeys),
+    HLen(HLen),
+    HMGet(HMGet),
+    HScan(HScan),
+    HSet(HSet),
+    HVals(HVals),
+    Hello(Hello),
+    Incr(Incr),
+    IncrBy(IncrBy),
+    IncrByFloat(IncrByFloat),
+    Keys(Keys),
+    LIndex(LIndex),
+    LInsert(LInsert),
+    LLen(LLen),
+    LPop(LPop),
+    LPush(LPush),
+    LRange(LRange),
+    LRem(LRem),
+    LSet(LSet),
+    LTrim(LTrim),
+    MGet(MGet),
+    MSet(MSet),
+    MSetNx(MSetNx),
+    Move(Move),
+    Object(Object),
+    PExpire(PExpire),
+    PExpireAt(PExpireAt),
+    PExpireTime(PExpireTime),
+    PTtl(PTtl),
+    Persist(Persist),
     Publish(Publish),
+    RPop(RPop),
+    RPush(RPush),
+    RandomKey(RandomKey),
+    Rename(Rename),
+    RenameNx(RenameNx),
+    Restore(Restore),
+    SAdd(SAdd),
+    SCard(SCard),
+    SDiff(SDiff),
+    SDiffStore(SDiffStore),
+    SInter(SInter),
+    SInterStore(SInterStore),
+    SIsMember(SIsMember),
+    SMembers(SMembers),
+    SPop(SPop),
+    SRandMember(SRandMember),
+    SRem(SRem),
+    SScan(SScan),
+    SUnion(SUnion),
+    SUnionStore(SUnionStore),
+    Scan(Scan),
     Set(Set),
+    SetNx(SetNx),
+    SetRange(SetRange),
+    Strlen(Strlen),
     Subscribe(Subscribe),
+    Ttl(Ttl),
+    Type(Type),
+    Unlink(Unlink),
     Unsubscribe(Unsubscribe),
     Ping(Ping),
+    XAck(XAck),
+    XAdd(XAdd),
+    XAutoClaim(XAutoClaim),
+    XClaim(XClaim),
+    XGroup(XGroup),
+    XLen(XLen),
+    XPending(XPending),
+    XRange(XRange),
+    XRead(XRead),
+    XReadGroup(XReadGroup),
+    XRevRange(XRevRange),
+    ZAdd(ZAdd),
+    ZCard(ZCard),
+    ZCount(ZCount),
+    ZIncrBy(ZIncrBy),
+    ZInterStore(ZInterStore),
+    ZPopMax(ZPopMax),
+    ZPopMin(ZPopMin),
+    ZRange(ZRange),
+    ZRank(ZRank),
+    ZRem(ZRem),
+    ZRevRank(ZRevRank),
+    ZScan(ZScan),
+    ZScore(ZScore),
+    ZUnionStore(ZUnionStore),
     Unknown(Unknown),
 }
 
 impl Command {
-    fn from_frame(frame: Frame) -> crate::Result<Command> {
+    // 将客户端发过来的frame解析成对应的命令
+    pub fn from_frame(frame: Frame) -> crate::Result<Command> {
         let mut parse = Parse::new(frame)?;
 
         let command_name = parse.next_string()?.to_lowercase();
 
         let command = match &command_name[..] {
+            "append" => Command::Append(Append::parse_frame(&mut parse)?),
+            "blmove" => Command::BLMove(BLMove::parse_frame(&mut parse)?),
+            "blpop" => Command::BLPop(BLPop::parse_frame(&mut parse)?),
+            "brpop" => Command::BRPop(BRPop::parse_frame(&mut parse)?),
+            "copy" => Command::Copy(Copy::parse_frame(&mut parse)?),
+            "dbsize" => Command::DbSize(DbSize::parse_frame(&mut parse)?),
+            "decr" => Command::Decr(Decr::parse_frame(&mut parse)?),
+            "decrby" => Command::DecrBy(DecrBy::parse_frame(&mut parse)?),
+            "del" => Command::Del(Del::parse_frame(&mut parse)?),
+            "dump" => Command::Dump(Dump::parse_frame(&mut parse)?),
+            "exists" => Command::Exists(Exists::parse_frame(&mut parse)?),
+            "expire" => Command::Expire(Expire::parse_frame(&mut parse)?),
+            "expireat" => Command::ExpireAt(ExpireAt::parse_frame(&mut parse)?),
+            "expiretime" => Command::ExpireTime(ExpireTime::parse_frame(&mut parse)?),
             "get" => Command::Get(Get::parse_frame(&mut parse)?),
+            "getdel" => Command::GetDel(GetDel::parse_frame(&mut parse)?),
+            "getex" => Command::GetEx(GetEx::parse_frame(&mut parse)?),
+            "getrange" => Command::GetRange(GetRange::parse_frame(&mut parse)?),
+            "getset" => Command::GetSet(GetSet::parse_frame(&mut parse)?),
+            "hdel" => Command::HDel(HDel::parse_frame(&mut parse)?),
+            "hello" => Command::Hello(Hello::parse_frame(&mut parse)?),
+            "hexists" => Command::HExists(HExists::parse_frame(&mut parse)?),
+            "hget" => Command::HGet(HGet::parse_frame(&mut parse)?),
+            "hgetall" => Command::HGetAll(HGetAll::parse_frame(&mut parse)?),
+            "hincrby" => Command::HIncrBy(HIncrBy::parse_frame(&mut parse)?),
+            "hkeys" => Command::HKeys(HKeys::parse_frame(&mut parse)?),
+            "hlen" => Command::HLen(HLen::parse_frame(&mut parse)?),
+            "hmget" => Command::HMGet(HMGet::parse_frame(&mut parse)?),
+            "hscan" => Command::HScan(HScan::parse_frame(&mut parse)?),
+            "hset" => Command::HSet(HSet::parse_frame(&mut parse)?),
+            "hvals" => Command::HVals(HVals::parse_frame(&mut parse)?),
+            "incr" => Command::Incr(Incr::parse_frame(&mut parse)?),
+            "incrby" => Command::IncrBy(IncrBy::parse_frame(&mut parse)?),
+            "incrbyfloat" => Command::IncrByFloat(IncrByFloat::parse_frame(&mut parse)?),
+            "keys" => Command::Keys(Keys::parse_frame(&mut parse)?),
+            "lindex" => Command::LIndex(LIndex::parse_frame(&mut parse)?),
+            "linsert" => Command::LInsert(LInsert::parse_frame(&mut parse)?),
+            "llen" => Command::LLen(LLen::parse_frame(&mut parse)?),
+            "lpop" => Command::LPop(LPop::parse_frame(&mut parse)?),
+            "lpush" => Command::LPush(LPush::parse_frame(&mut parse)?),
+            "lrange" => Command::LRange(LRange::parse_frame(&mut parse)?),
+            "lrem" => Command::LRem(LRem::parse_frame(&mut parse)?),
+            "lset" => Command::LSet(LSet::parse_frame(&mut parse)?),
+            "ltrim" => Command::LTrim(LTrim::parse_frame(&mut parse)?),
+            "mget" => Command::MGet(MGet::parse_frame(&mut parse)?),
+            "move" => Command::Move(Move::parse_frame(&mut parse)?),
+            "mset" => Command::MSet(MSet::parse_frame(&mut parse)?),
+            "msetnx" => Command::MSetNx(MSetNx::parse_frame(&mut parse)?),
+            "object" => Command::Object(Object::parse_frame(&mut parse)?),
+            "persist" => Command::Persist(Persist::parse_frame(&mut parse)?),
+            "pexpire" => Command::PExpire(PExpire::parse_frame(&mut parse)?),
+            "pexpireat" => Command::PExpireAt(PExpireAt::parse_frame(&mut parse)?),
+            "pexpiretime" => Command::PExpireTime(PExpireTime::parse_frame(&mut parse)?),
+            "pttl" => Command::PTtl(PTtl::parse_frame(&mut parse)?),
             "publish" => Command::Publish(Publish::parse_frame(&mut parse)?),
+            "randomkey" => Command::RandomKey(RandomKey::parse_frame(&mut parse)?),
+            "rename" => Command::Rename(Rename::parse_frame(&mut parse)?),
+            "renamenx" => Command::RenameNx(RenameNx::parse_frame(&mut parse)?),
+            "restore" => Command::Restore(Restore::parse_frame(&mut parse)?),
+            "rpop" => Command::RPop(RPop::parse_frame(&mut parse)?),
+            "rpush" => Command::RPush(RPush::parse_frame(&mut parse)?),
+            "sadd" => Command::SAdd(SAdd::parse_frame(&mut parse)?),
+            "scan" => Command::Scan(Scan::parse_frame(&mut parse)?),
+            "scard" => Command::SCard(SCard::parse_frame(&mut parse)?),
+            "sdiff" => Command::SDiff(SDiff::parse_frame(&mut parse)?),
+            "sdiffstore" => Command::SDiffStore(SDiffStore::parse_frame(&mut parse)?),
             "set" => Command::Set(Set::parse_frame(&mut parse)?),
+            "setnx" => Command::SetNx(SetNx::parse_frame(&mut parse)?),
+            "setrange" => Command::SetRange(SetRange::parse_frame(&mut parse)?),
+            "sinter" => Command::SInter(SInter::parse_frame(&mut parse)?),
+            "sinterstore" => Command::SInterStore(SInterStore::parse_frame(&mut parse)?),
+            "sismember" => Command::SIsMember(SIsMember::parse_frame(&mut parse)?),
+            "smembers" => Command::SMembers(SMembers::parse_frame(&mut parse)?),
+            "spop" => Command::SPop(SPop::parse_frame(&mut parse)?),
+            "srandmember" => Command::SRandMember(SRandMember::parse_frame(&mut parse)?),
+            "srem" => Command::SRem(SRem::parse_frame(&mut parse)?),
+            "sscan" => Command::SScan(SScan::parse_frame(&mut parse)?),
+            "strlen" => Command::Strlen(Strlen::parse_frame(&mut parse)?),
             "subscribe" => Command::Subscribe(Subscribe::parse_frame(&mut parse)?),
+            "sunion" => Command::SUnion(SUnion::parse_frame(&mut parse)?),
+            "sunionstore" => Command::SUnionStore(SUnionStore::parse_frame(&mut parse)?),
+            "ttl" => Command::Ttl(Ttl::parse_frame(&mut parse)?),
+            "type" => Command::Type(Type::parse_frame(&mut parse)?),
+            "unlink" => Command::Unlink(Unlink::parse_frame(&mut parse)?),
             "unsubscribe" => Command::Unsubscribe(Unsubscribe::parse_frame(&mut parse)?),
             "ping" => Command::Ping(Ping::parse_frame(&mut parse)?),
+            "xack" => Command::XAck(XAck::parse_frame(&mut parse)?),
+            "xadd" => Command::XAdd(XAdd::parse_frame(&mut parse)?),
+            "xautoclaim" => Command::XAutoClaim(XAutoClaim::parse_frame(&mut parse)?),
+            "xclaim" => Command::XClaim(XClaim::parse_frame(&mut parse)?),
+            "xgroup" => Command::XGroup(XGroup::parse_frame(&mut parse)?),
+            "xlen" => Command::XLen(XLen::parse_frame(&mut parse)?),
+            "xpending" => Command::XPending(XPending::parse_frame(&mut parse)?),
+            "xrange" => Command::XRange(XRange::parse_frame(&mut parse)?),
+            "xread" => Command::XRead(XRead::parse_frame(&mut parse)?),
+            "xreadgroup" => Command::XReadGroup(XReadGroup::parse_frame(&mut parse)?),
+            "xrevrange" => Command::XRevRange(XRevRange::parse_frame(&mut parse)?),
+            "zadd" => Command::ZAdd(ZAdd::parse_frame(&mut parse)?),
+            "zcard" => Command::ZCard(ZCard::parse_frame(&mut parse)?),
+            "zcount" => Command::ZCount(ZCount::parse_frame(&mut parse)?),
+            "zincrby" => Command::ZIncrBy(ZIncrBy::parse_frame(&mut parse)?),
+            "zinterstore" => Command::ZInterStore(ZInterStore::parse_frame(&mut parse)?),
+            "zpopmax" => Command::ZPopMax(ZPopMax::parse_frame(&mut parse)?),
+            "zpopmin" => Command::ZPopMin(ZPopMin::parse_frame(&mut parse)?),
+            "zrange" => Command::ZRange(ZRange::parse_frame(&mut parse)?),
+            "zrank" => Command::ZRank(ZRank::parse_frame(&mut parse)?),
+            "zrem" => Command::ZRem(ZRem::parse_frame(&mut parse)?),
+            "zrevrank" => Command::ZRevRank(ZRevRank::parse_frame(&mut parse)?),
+            "zscan" => Command::ZScan(ZScan::parse_frame(&mut parse)?),
+            "zscore" => Command::ZScore(ZScore::parse_frame(&mut parse)?),
+            "zunionstore" => Command::ZUnionStore(ZUnionStore::parse_frame(&mut parse)?),
             _ => {
                 return Ok(Command::Unknown(Unknown::new(command_name)));
             }
@@ -47,4 +306,248 @@ impl Command {
 
         Ok(command)
     }
+
+    // 执行命令，将结果写入到connection中返回给客户端
+    // shutdown 用于在执行耗时较长的命令时(例如subscribe)监听服务器的关闭信号
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        use Command::*;
+
+        match self {
+            Append(cmd) => cmd.apply(db, dst).await,
+            BLMove(cmd) => cmd.apply(db, dst, shutdown).await,
+            BLPop(cmd) => cmd.apply(db, dst, shutdown).await,
+            BRPop(cmd) => cmd.apply(db, dst, shutdown).await,
+            Copy(cmd) => cmd.apply(db, dst).await,
+            DbSize(cmd) => cmd.apply(db, dst).await,
+            Decr(cmd) => cmd.apply(db, dst).await,
+            DecrBy(cmd) => cmd.apply(db, dst).await,
+            Del(cmd) => cmd.apply(db, dst).await,
+            Dump(cmd) => cmd.apply(db, dst).await,
+            Exists(cmd) => cmd.apply(db, dst).await,
+            Expire(cmd) => cmd.apply(db, dst).await,
+            ExpireAt(cmd) => cmd.apply(db, dst).await,
+            ExpireTime(cmd) => cmd.apply(db, dst).await,
+            Get(cmd) => cmd.apply(db, dst).await,
+            GetDel(cmd) => cmd.apply(db, dst).await,
+            GetEx(cmd) => cmd.apply(db, dst).await,
+            GetRange(cmd) => cmd.apply(db, dst).await,
+            GetSet(cmd) => cmd.apply(db, dst).await,
+            HDel(cmd) => cmd.apply(db, dst).await,
+            HExists(cmd) => cmd.apply(db, dst).await,
+            HGet(cmd) => cmd.apply(db, dst).await,
+            HGetAll(cmd) => cmd.apply(db, dst).await,
+            HIncrBy(cmd) => cmd.apply(db, dst).await,
+            HKeys(cmd) => cmd.apply(db, dst).await,
+            HLen(cmd) => cmd.apply(db, dst).await,
+            HMGet(cmd) => cmd.apply(db, dst).await,
+            HScan(cmd) => cmd.apply(db, dst).await,
+            HSet(cmd) => cmd.apply(db, dst).await,
+            HVals(cmd) => cmd.apply(db, dst).await,
+            Hello(cmd) => cmd.apply(dst).await,
+            Incr(cmd) => cmd.apply(db, dst).await,
+            IncrBy(cmd) => cmd.apply(db, dst).await,
+            IncrByFloat(cmd) => cmd.apply(db, dst).await,
+            Keys(cmd) => cmd.apply(db, dst).await,
+            LIndex(cmd) => cmd.apply(db, dst).await,
+            LInsert(cmd) => cmd.apply(db, dst).await,
+            LLen(cmd) => cmd.apply(db, dst).await,
+            LPop(cmd) => cmd.apply(db, dst).await,
+            LPush(cmd) => cmd.apply(db, dst).await,
+            LRange(cmd) => cmd.apply(db, dst).await,
+            LRem(cmd) => cmd.apply(db, dst).await,
+            LSet(cmd) => cmd.apply(db, dst).await,
+            LTrim(cmd) => cmd.apply(db, dst).await,
+            MGet(cmd) => cmd.apply(db, dst).await,
+            MSet(cmd) => cmd.apply(db, dst).await,
+            MSetNx(cmd) => cmd.apply(db, dst).await,
+            Move(cmd) => cmd.apply(db, dst).await,
+            Object(cmd) => cmd.apply(db, dst).await,
+            PExpire(cmd) => cmd.apply(db, dst).await,
+            PExpireAt(cmd) => cmd.apply(db, dst).await,
+            PExpireTime(cmd) => cmd.apply(db, dst).await,
+            PTtl(cmd) => cmd.apply(db, dst).await,
+            Persist(cmd) => cmd.apply(db, dst).await,
+            Publish(cmd) => cmd.apply(db, dst).await,
+            RPop(cmd) => cmd.apply(db, dst).await,
+            RPush(cmd) => cmd.apply(db, dst).await,
+            RandomKey(cmd) => cmd.apply(db, dst).await,
+            Rename(cmd) => cmd.apply(db, dst).await,
+            RenameNx(cmd) => cmd.apply(db, dst).await,
+            Restore(cmd) => cmd.apply(db, dst).await,
+            SAdd(cmd) => cmd.apply(db, dst).await,
+            SCard(cmd) => cmd.apply(db, dst).await,
+            SDiff(cmd) => cmd.apply(db, dst).await,
+            SDiffStore(cmd) => cmd.apply(db, dst).await,
+            SInter(cmd) => cmd.apply(db, dst).await,
+            SInterStore(cmd) => cmd.apply(db, dst).await,
+            SIsMember(cmd) => cmd.apply(db, dst).await,
+            SMembers(cmd) => cmd.apply(db, dst).await,
+            SPop(cmd) => cmd.apply(db, dst).await,
+            SRandMember(cmd) => cmd.apply(db, dst).await,
+            SRem(cmd) => cmd.apply(db, dst).await,
+            SScan(cmd) => cmd.apply(db, dst).await,
+            SUnion(cmd) => cmd.apply(db, dst).await,
+            SUnionStore(cmd) => cmd.apply(db, dst).await,
+            Scan(cmd) => cmd.apply(db, dst).await,
+            Set(cmd) => cmd.apply(db, dst).await,
+            SetNx(cmd) => cmd.apply(db, dst).await,
+            SetRange(cmd) => cmd.apply(db, dst).await,
+            Strlen(cmd) => cmd.apply(db, dst).await,
+            Subscribe(cmd) => cmd.apply(db, dst, shutdown).await,
+            Ttl(cmd) => cmd.apply(db, dst).await,
+            Type(cmd) => cmd.apply(db, dst).await,
+            Unlink(cmd) => cmd.apply(db, dst).await,
+            Unsubscribe(cmd) => cmd.apply(dst).await,
+            Ping(cmd) => cmd.apply(dst).await,
+            XAck(cmd) => cmd.apply(db, dst).await,
+            XAdd(cmd) => cmd.apply(db, dst).await,
+            XAutoClaim(cmd) => cmd.apply(db, dst).await,
+            XClaim(cmd) => cmd.apply(db, dst).await,
+            XGroup(cmd) => cmd.apply(db, dst).await,
+            XLen(cmd) => cmd.apply(db, dst).await,
+            XPending(cmd) => cmd.apply(db, dst).await,
+            XRange(cmd) => cmd.apply(db, dst).await,
+            XRead(cmd) => cmd.apply(db, dst, shutdown).await,
+            XReadGroup(cmd) => cmd.apply(db, dst, shutdown).await,
+            XRevRange(cmd) => cmd.apply(db, dst).await,
+            ZAdd(cmd) => cmd.apply(db, dst).await,
+            ZCard(cmd) => cmd.apply(db, dst).await,
+            ZCount(cmd) => cmd.apply(db, dst).await,
+            ZIncrBy(cmd) => cmd.apply(db, dst).await,
+            ZInterStore(cmd) => cmd.apply(db, dst).await,
+            ZPopMax(cmd) => cmd.apply(db, dst).await,
+            ZPopMin(cmd) => cmd.apply(db, dst).await,
+            ZRange(cmd) => cmd.apply(db, dst).await,
+            ZRank(cmd) => cmd.apply(db, dst).await,
+            ZRem(cmd) => cmd.apply(db, dst).await,
+            ZRevRank(cmd) => cmd.apply(db, dst).await,
+            ZScan(cmd) => cmd.apply(db, dst).await,
+            ZScore(cmd) => cmd.apply(db, dst).await,
+            ZUnionStore(cmd) => cmd.apply(db, dst).await,
+            Unknown(cmd) => cmd.apply(dst).await,
+        }
+    }
+
+    // 返回命令的名称
+    pub(crate) fn get_name(&self) -> &str {
+        match self {
+            Command::Append(_) => "append",
+            Command::BLMove(_) => "blmove",
+            Command::BLPop(_) => "blpop",
+            Command::BRPop(_) => "brpop",
+            Command::Copy(_) => "copy",
+            Command::DbSize(_) => "dbsize",
+            Command::Decr(_) => "decr",
+            Command::DecrBy(_) => "decrby",
+            Command::Del(_) => "del",
+            Command::Dump(_) => "dump",
+            Command::Exists(_) => "exists",
+            Command::Expire(_) => "expire",
+            Command::ExpireAt(_) => "expireat",
+            Command::ExpireTime(_) => "expiretime",
+            Command::Get(_) => "get",
+            Command::GetDel(_) => "getdel",
+            Command::GetEx(_) => "getex",
+            Command::GetRange(_) => "getrange",
+            Command::GetSet(_) => "getset",
+            Command::HDel(_) => "hdel",
+            Command::HExists(_) => "hexists",
+            Command::HGet(_) => "hget",
+            Command::HGetAll(_) => "hgetall",
+            Command::HIncrBy(_) => "hincrby",
+            Command::HKeys(_) => "hkeys",
+            Command::HLen(_) => "hlen",
+            Command::HMGet(_) => "hmget",
+            Command::HScan(_) => "hscan",
+            Command::HSet(_) => "hset",
+            Command::HVals(_) => "hvals",
+            Command::Hello(_) => "hello",
+            Command::Incr(_) => "incr",
+            Command::IncrBy(_) => "incrby",
+            Command::IncrByFloat(_) => "incrbyfloat",
+            Command::Keys(_) => "keys",
+            Command::LIndex(_) => "lindex",
+            Command::LInsert(_) => "linsert",
+            Command::LLen(_) => "llen",
+            Command::LPop(_) => "lpop",
+            Command::LPush(_) => "lpush",
+            Command::LRange(_) => "lrange",
+            Command::LRem(_) => "lrem",
+            Command::LSet(_) => "lset",
+            Command::LTrim(_) => "ltrim",
+            Command::MGet(_) => "mget",
+            Command::MSet(_) => "mset",
+            Command::MSetNx(_) => "msetnx",
+            Command::Move(_) => "move",
+            Command::Object(_) => "object",
+            Command::PExpire(_) => "pexpire",
+            Command::PExpireAt(_) => "pexpireat",
+            Command::PExpireTime(_) => "pexpiretime",
+            Command::PTtl(_) => "pttl",
+            Command::Persist(_) => "persist",
+            Command::Publish(_) => "publish",
+            Command::RPop(_) => "rpop",
+            Command::RPush(_) => "rpush",
+            Command::RandomKey(_) => "randomkey",
+            Command::Rename(_) => "rename",
+            Command::RenameNx(_) => "renamenx",
+            Command::Restore(_) => "restore",
+            Command::SAdd(_) => "sadd",
+            Command::SCard(_) => "scard",
+            Command::SDiff(_) => "sdiff",
+            Command::SDiffStore(_) => "sdiffstore",
+            Command::SInter(_) => "sinter",
+            Command::SInterStore(_) => "sinterstore",
+            Command::SIsMember(_) => "sismember",
+            Command::SMembers(_) => "smembers",
+            Command::SPop(_) => "spop",
+            Command::SRandMember(_) => "srandmember",
+            Command::SRem(_) => "srem",
+            Command::SScan(_) => "sscan",
+            Command::SUnion(_) => "sunion",
+            Command::SUnionStore(_) => "sunionstore",
+            Command::Scan(_) => "scan",
+            Command::Set(_) => "set",
+            Command::SetNx(_) => "setnx",
+            Command::SetRange(_) => "setrange",
+            Command::Strlen(_) => "strlen",
+            Command::Subscribe(_) => "subscribe",
+            Command::Ttl(_) => "ttl",
+            Command::Type(_) => "type",
+            Command::Unlink(_) => "unlink",
+            Command::Unsubscribe(_) => "unsubscribe",
+            Command::Ping(_) => "ping",
+            Command::XAck(_) => "xack",
+            Command::XAdd(_) => "xadd",
+            Command::XAutoClaim(_) => "xautoclaim",
+            Command::XClaim(_) => "xclaim",
+            Command::XGroup(_) => "xgroup",
+            Command::XLen(_) => "xlen",
+            Command::XPending(_) => "xpending",
+            Command::XRange(_) => "xrange",
+            Command::XRead(_) => "xread",
+            Command::XReadGroup(_) => "xreadgroup",
+            Command::XRevRange(_) => "xrevrange",
+            Command::ZAdd(_) => "zadd",
+            Command::ZCard(_) => "zcard",
+            Command::ZCount(_) => "zcount",
+            Command::ZIncrBy(_) => "zincrby",
+            Command::ZInterStore(_) => "zinterstore",
+            Command::ZPopMax(_) => "zpopmax",
+            Command::ZPopMin(_) => "zpopmin",
+            Command::ZRange(_) => "zrange",
+            Command::ZRank(_) => "zrank",
+            Command::ZRem(_) => "zrem",
+            Command::ZRevRank(_) => "zrevrank",
+            Command::ZScan(_) => "zscan",
+            Command::ZScore(_) => "zscore",
+            Command::ZUnionStore(_) => "zunionstore",
+            Command::Unknown(cmd) => cmd.get_name(),
+        }
+    }
 }
@@ -0,0 +1,79 @@
+use bytes::Bytes;
+
+use crate::{connection::Connection, db::Db, frame::Frame, parse::Parse};
+
+#[derive(Debug)]
+pub struct MSet {
+    pairs: Vec<(String, Bytes)>,
+}
+
+#[derive(Debug)]
+pub struct MSetNx {
+    pairs: Vec<(String, Bytes)>,
+}
+
+impl MSet {
+    pub fn new(pairs: Vec<(String, Bytes)>) -> MSet {
+        MSet { pairs }
+    }
+
+    pub fn pairs(&self) -> &[(String, Bytes)] {
+        &self.pairs
+    }
+
+    // MSET key value [key value ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<MSet> {
+        let pairs = parse_pairs(parse, "mset")?;
+        Ok(MSet { pairs })
+    }
+
+    // 所有的key-value在同一次加锁中写入，总是返回OK
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        db.mset(self.pairs);
+
+        let response = Frame::Simple("OK".to_string());
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl MSetNx {
+    pub fn new(pairs: Vec<(String, Bytes)>) -> MSetNx {
+        MSetNx { pairs }
+    }
+
+    pub fn pairs(&self) -> &[(String, Bytes)] {
+        &self.pairs
+    }
+
+    // MSETNX key value [key value ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<MSetNx> {
+        let pairs = parse_pairs(parse, "msetnx")?;
+        Ok(MSetNx { pairs })
+    }
+
+    // 只要有一个key已经存在就不会写入任何值，写入时返回1，否则返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Integer(db.msetnx(self.pairs) as i64);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+// 参数必须是成对的key value
+fn parse_pairs(parse: &mut Parse, command: &str) -> crate::Result<Vec<(String, Bytes)>> {
+    if parse.remaining() == 0 || !parse.remaining().is_multiple_of(2) {
+        return Err(format!("wrong number of arguments for '{}' command", command).into());
+    }
+
+    let mut pairs = Vec::with_capacity(parse.remaining() / 2);
+    while parse.remaining() > 0 {
+        let key = parse.next_string()?;
+        let value = parse.next_bytes()?;
+        pairs.push((key, value));
+    }
+
+    Ok(pairs)
+}
@@ -1,6 +1,10 @@
 use bytes::Bytes;
 
-use crate::parse::{Parse, ParseError};
+use crate::{
+    connection::Connection,
+    frame::Frame,
+    parse::{Parse, ParseError},
+};
 
 #[derive(Debug, Default)]
 pub struct Ping {
@@ -8,10 +12,14 @@ pub struct Ping {
 }
 
 impl Ping {
-    fn new(msg: Option<Bytes>) -> Ping {
+    pub fn new(msg: Option<Bytes>) -> Ping {
         Ping { msg }
     }
 
+    pub fn msg(&self) -> Option<&Bytes> {
+        self.msg.as_ref()
+    }
+
     pub fn parse_frame(parse: &mut Parse) -> crate::Result<Ping> {
         match parse.next_bytes() {
             Ok(msg) => Ok(Ping::new(Some(msg))),
@@ -19,4 +27,25 @@ impl Ping {
             Err(err) => Err(err.into()),
         }
     }
+
+    // 没有参数时返回PONG，否则原样返回参数
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.msg {
+            None => Frame::Simple("PONG".to_string()),
+            Some(msg) => Frame::Bulk(msg),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from_static(b"ping"));
+        if let Some(msg) = self.msg {
+            frame.push_bulk(msg);
+        }
+        frame
+    }
 }
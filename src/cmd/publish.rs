@@ -1,6 +1,6 @@
 use bytes::Bytes;
 
-use crate::parse::Parse;
+use crate::{connection::Connection, db::Db, frame::Frame, parse::Parse};
 
 #[derive(Debug)]
 pub struct Publish {
@@ -9,7 +9,7 @@ pub struct Publish {
 }
 
 impl Publish {
-    fn new(channel: impl ToString, message: Bytes) -> Publish {
+    pub fn new(channel: impl ToString, message: Bytes) -> Publish {
         Publish {
             channel: channel.to_string(),
             message,
@@ -20,9 +20,24 @@ impl Publish {
     pub fn parse_frame(parse: &mut Parse) -> crate::Result<Publish> {
         let channel = parse.next_string()?;
         let message = parse.next_bytes()?;
-        Ok(Publish {
-            channel: channel,
-            message: message,
-        })
+        Ok(Publish { channel, message })
+    }
+
+    // 将消息发布到channel中，返回收到消息的订阅者数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let num_subscribers = db.publish(&self.channel, self.message);
+
+        let response = Frame::Integer(num_subscribers as i64);
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from_static(b"publish"));
+        frame.push_bulk(Bytes::from(self.channel.into_bytes()));
+        frame.push_bulk(self.message);
+        frame
     }
 }
@@ -0,0 +1,372 @@
+use bytes::Bytes;
+
+use crate::{
+    connection::Connection,
+    db::Db,
+    frame::Frame,
+    parse::{Parse, ParseError},
+};
+
+// 没有指定COUNT时每次大约返回的元素数量
+const DEFAULT_COUNT: usize = 10;
+
+#[derive(Debug)]
+pub struct Scan {
+    cursor: u64,
+    // 只返回匹配这个glob模式的key
+    pattern: Option<Bytes>,
+    count: usize,
+    // 只返回这个类型的key，和TYPE命令返回的类型名相同
+    key_type: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct HScan {
+    key: String,
+    cursor: u64,
+    // 只返回field匹配这个glob模式的元素
+    pattern: Option<Bytes>,
+    count: usize,
+    // 只返回field，不返回value
+    novalues: bool,
+}
+
+#[derive(Debug)]
+pub struct SScan {
+    key: String,
+    cursor: u64,
+    pattern: Option<Bytes>,
+    count: usize,
+}
+
+#[derive(Debug)]
+pub struct ZScan {
+    key: String,
+    cursor: u64,
+    pattern: Option<Bytes>,
+    count: usize,
+}
+
+// TYPE选项可以使用的类型名
+const TYPE_NAMES: [&str; 6] = ["string", "list", "hash", "set", "zset", "stream"];
+
+impl Scan {
+    pub fn new(cursor: u64) -> Scan {
+        Scan {
+            cursor,
+            pattern: None,
+            count: DEFAULT_COUNT,
+            key_type: None,
+        }
+    }
+
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    pub fn pattern(&self) -> Option<&Bytes> {
+        self.pattern.as_ref()
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn key_type(&self) -> Option<&str> {
+        self.key_type.as_deref()
+    }
+
+    // SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Scan> {
+        let cursor = parse_cursor(parse)?;
+
+        let mut scan = Scan::new(cursor);
+
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            match &option[..] {
+                "MATCH" => scan.pattern = Some(parse.next_bytes()?),
+                "COUNT" => scan.count = parse_count(parse)?,
+                "TYPE" => {
+                    let key_type = parse.next_string()?.to_lowercase();
+                    if !TYPE_NAMES.contains(&&key_type[..]) {
+                        return Err("unknown type name".into());
+                    }
+                    scan.key_type = Some(key_type);
+                }
+                _ => return Err("syntax error".into()),
+            }
+        }
+
+        Ok(scan)
+    }
+
+    // 返回 [下一次的游标, [key1, key2...]]，游标为0表示遍历结束
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let (cursor, keys) = db.scan(
+            self.cursor,
+            self.pattern.as_deref(),
+            self.key_type.as_deref(),
+            self.count,
+        );
+
+        let mut elements = Frame::array();
+        for key in keys {
+            elements.push_bulk(Bytes::from(key));
+        }
+
+        dst.write_frame(&scan_reply(cursor, elements)).await?;
+
+        Ok(())
+    }
+}
+
+impl HScan {
+    pub fn new(key: impl ToString, cursor: u64) -> HScan {
+        HScan {
+            key: key.to_string(),
+            cursor,
+            pattern: None,
+            count: DEFAULT_COUNT,
+            novalues: false,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    pub fn pattern(&self) -> Option<&Bytes> {
+        self.pattern.as_ref()
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    // HSCAN key cursor [MATCH pattern] [COUNT count] [NOVALUES]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<HScan> {
+        let key = parse.next_string()?;
+        let cursor = parse_cursor(parse)?;
+
+        let mut scan = HScan::new(key, cursor);
+
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            match &option[..] {
+                "MATCH" => scan.pattern = Some(parse.next_bytes()?),
+                "COUNT" => scan.count = parse_count(parse)?,
+                "NOVALUES" => scan.novalues = true,
+                _ => return Err("syntax error".into()),
+            }
+        }
+
+        Ok(scan)
+    }
+
+    // 返回 [下一次的游标, [field1, value1, field2, value2...]]，游标为0表示遍历结束
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let pattern = self.pattern.as_deref();
+
+        let response = match db.hscan(&self.key, self.cursor, pattern, self.count) {
+            Ok((cursor, pairs)) => {
+                let mut elements = Frame::array();
+                for (field, value) in pairs {
+                    elements.push_bulk(field);
+                    if !self.novalues {
+                        elements.push_bulk(value);
+                    }
+                }
+                scan_reply(cursor, elements)
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl SScan {
+    pub fn new(key: impl ToString, cursor: u64) -> SScan {
+        SScan {
+            key: key.to_string(),
+            cursor,
+            pattern: None,
+            count: DEFAULT_COUNT,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    pub fn pattern(&self) -> Option<&Bytes> {
+        self.pattern.as_ref()
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    // SSCAN key cursor [MATCH pattern] [COUNT count]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SScan> {
+        let key = parse.next_string()?;
+        let cursor = parse_cursor(parse)?;
+        let (pattern, count) = parse_options(parse)?;
+
+        Ok(SScan {
+            key,
+            cursor,
+            pattern,
+            count,
+        })
+    }
+
+    // 返回 [下一次的游标, [member1, member2...]]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let pattern = self.pattern.as_deref();
+
+        let response = match db.sscan(&self.key, self.cursor, pattern, self.count) {
+            Ok((cursor, members)) => {
+                let mut elements = Frame::array();
+                for member in members {
+                    elements.push_bulk(member);
+                }
+                scan_reply(cursor, elements)
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl ZScan {
+    pub fn new(key: impl ToString, cursor: u64) -> ZScan {
+        ZScan {
+            key: key.to_string(),
+            cursor,
+            pattern: None,
+            count: DEFAULT_COUNT,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    pub fn pattern(&self) -> Option<&Bytes> {
+        self.pattern.as_ref()
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    // ZSCAN key cursor [MATCH pattern] [COUNT count]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZScan> {
+        let key = parse.next_string()?;
+        let cursor = parse_cursor(parse)?;
+        let (pattern, count) = parse_options(parse)?;
+
+        Ok(ZScan {
+            key,
+            cursor,
+            pattern,
+            count,
+        })
+    }
+
+    // 返回 [下一次的游标, [member1, score1, member2, score2...]]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let pattern = self.pattern.as_deref();
+
+        let response = match db.zscan(&self.key, self.cursor, pattern, self.count) {
+            Ok((cursor, members)) => {
+                let mut elements = Vec::with_capacity(members.len() * 2);
+                for (member, score) in members {
+                    elements.push(Frame::Bulk(member));
+                    elements.push(Frame::Double(score));
+                }
+                scan_reply(cursor, Frame::Array(elements))
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+// SSCAN、ZSCAN的[MATCH pattern] [COUNT count]
+fn parse_options(parse: &mut Parse) -> crate::Result<(Option<Bytes>, usize)> {
+    let mut pattern = None;
+    let mut count = DEFAULT_COUNT;
+
+    loop {
+        let option = match parse.next_string() {
+            Ok(option) => option.to_uppercase(),
+            Err(ParseError::EndOfStream) => break,
+            Err(err) => return Err(err.into()),
+        };
+
+        match &option[..] {
+            "MATCH" => pattern = Some(parse.next_bytes()?),
+            "COUNT" => count = parse_count(parse)?,
+            _ => return Err("syntax error".into()),
+        }
+    }
+
+    Ok((pattern, count))
+}
+
+// 游标是一个无符号整数，和redis一样，不合法时返回invalid cursor
+fn parse_cursor(parse: &mut Parse) -> crate::Result<u64> {
+    let cursor = parse.next_bytes()?;
+
+    if cursor.is_empty() || !cursor.iter().all(u8::is_ascii_digit) {
+        return Err("invalid cursor".into());
+    }
+
+    atoi::atoi::<u64>(&cursor).ok_or_else(|| "invalid cursor".into())
+}
+
+// COUNT必须大于0
+fn parse_count(parse: &mut Parse) -> crate::Result<usize> {
+    match usize::try_from(parse.next_signed()?) {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err("syntax error".into()),
+    }
+}
+
+// 游标和redis一样按照字符串返回
+fn scan_reply(cursor: u64, elements: Frame) -> Frame {
+    Frame::Array(vec![Frame::Bulk(Bytes::from(cursor.to_string())), elements])
+}
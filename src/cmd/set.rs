@@ -1,54 +1,253 @@
 use bytes::Bytes;
-use std::time::Duration;
+use std::time::{Duration, UNIX_EPOCH};
 
-use crate::parse::{Parse, ParseError};
+use crate::{
+    cmd::expire::fits_unix_ms,
+    connection::Connection,
+    db::{Db, Expiry, SetCondition, SetOptions},
+    frame::Frame,
+    parse::{Parse, ParseError},
+};
 
 #[derive(Debug)]
 pub struct Set {
     key: String,
     value: Bytes,
-    expire: Option<Duration>,
+    options: SetOptions,
+}
+
+#[derive(Debug)]
+pub struct GetSet {
+    key: String,
+    value: Bytes,
+}
+
+#[derive(Debug)]
+pub struct SetNx {
+    key: String,
+    value: Bytes,
 }
 
 impl Set {
-    fn new(key: impl ToString, value: Bytes, expire: Option<Duration>) -> Set {
+    pub fn new(key: impl ToString, value: Bytes, expire: Option<Duration>) -> Set {
         Set {
             key: key.to_string(),
             value,
-            expire,
+            options: SetOptions {
+                condition: SetCondition::Always,
+                expire: expire.map_or(Expiry::Persist, Expiry::After),
+                get: false,
+            },
         }
     }
 
-    fn key(&self) -> &str {
+    pub fn key(&self) -> &str {
         &self.key
     }
 
-    fn value(&self) -> &Bytes {
+    pub fn value(&self) -> &Bytes {
         &self.value
     }
 
-    fn expire(&self) -> Option<Duration> {
-        self.expire
+    pub fn options(&self) -> &SetOptions {
+        &self.options
     }
 
-    // SET key value [EX seconds|PX milliseconds]
+    // SET key value [NX | XX] [GET] [EX seconds | PX milliseconds |
+    //   EXAT unix-time-seconds | PXAT unix-time-milliseconds | KEEPTTL]
+    // 可选参数的顺序任意，但是互相冲突的参数不能同时出现
     pub fn parse_frame(parse: &mut Parse) -> crate::Result<Set> {
         let key = parse.next_string()?;
         let value = parse.next_bytes()?;
-        let mut expire: Option<Duration> = None;
-        match parse.next_string() {
-            Ok(s) if s.to_uppercase() == "EX" => {
-                let secs = parse.next_int()?;
-                expire = Some(Duration::from_secs(secs));
+
+        let mut condition = None;
+        let mut expire = None;
+        let mut get = false;
+
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            match &option[..] {
+                "NX" if condition.is_none() => condition = Some(SetCondition::NotExists),
+                "XX" if condition.is_none() => condition = Some(SetCondition::Exists),
+                "GET" if !get => get = true,
+                "KEEPTTL" if expire.is_none() => expire = Some(Expiry::Keep),
+                "EX" | "PX" | "EXAT" | "PXAT" if expire.is_none() => {
+                    expire = Some(parse_expire(&option, parse, "set")?);
+                }
+                _ => return Err("syntax error".into()),
             }
-            Ok(s) if s.to_uppercase() == "PX" => {
-                let ms = parse.next_int()?;
-                expire = Some(Duration::from_millis(ms));
+        }
+
+        Ok(Set {
+            key,
+            value,
+            options: SetOptions {
+                condition: condition.unwrap_or_default(),
+                expire: expire.unwrap_or_default(),
+                get,
+            },
+        })
+    }
+
+    // 将key-value写入db，成功后返回OK，因为NX/XX的条件没有写入时返回Null
+    // 带GET参数时无论是否写入都返回key原来的值
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let get = self.options.get;
+
+        let response = match db.set(self.key, self.value, self.options) {
+            Ok((_, previous)) if get => previous.map_or(Frame::Null, Frame::Bulk),
+            Ok((true, _)) => Frame::Simple("OK".to_string()),
+            Ok((false, _)) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    // 将命令转换成frame，相对的过期时间统一使用PX毫秒的形式发送
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from_static(b"set"));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.value);
+
+        match self.options.condition {
+            SetCondition::Always => {}
+            SetCondition::NotExists => frame.push_bulk(Bytes::from_static(b"nx")),
+            SetCondition::Exists => frame.push_bulk(Bytes::from_static(b"xx")),
+        }
+
+        if self.options.get {
+            frame.push_bulk(Bytes::from_static(b"get"));
+        }
+
+        match self.options.expire {
+            Expiry::Persist => {}
+            Expiry::Keep => frame.push_bulk(Bytes::from_static(b"keepttl")),
+            Expiry::After(expire) => {
+                frame.push_bulk(Bytes::from_static(b"px"));
+                frame.push_bulk(Bytes::from(expire.as_millis().to_string()));
+            }
+            Expiry::At(at) => {
+                let ms = at
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+                frame.push_bulk(Bytes::from_static(b"pxat"));
+                frame.push_bulk(Bytes::from(ms.to_string()));
             }
-            Ok(_) => return Err("currently set only supports expire".into()),
-            Err(ParseError::EndOfStream) => {}
-            Err(err) => return Err(err.into()),
         }
-        Ok(Set { key, value, expire })
+
+        frame
+    }
+}
+
+// 解析EX/PX/EXAT/PXAT后面的时间，时间必须大于0，command用于错误信息
+pub(crate) fn parse_expire(
+    option: &str,
+    parse: &mut Parse,
+    command: &str,
+) -> crate::Result<Expiry> {
+    let time = parse.next_signed()?;
+
+    // 秒转换成毫秒时不能溢出
+    let ms = match option {
+        "EX" | "EXAT" => time.checked_mul(1000),
+        _ => Some(time),
+    };
+
+    let ms = match ms {
+        Some(ms) if ms > 0 && (option.ends_with("AT") || fits_unix_ms(ms)) => ms as u64,
+        _ => return Err(format!("invalid expire time in '{}' command", command).into()),
+    };
+
+    let expire = match option {
+        "EX" | "PX" => Expiry::After(Duration::from_millis(ms)),
+        _ => Expiry::At(UNIX_EPOCH + Duration::from_millis(ms)),
+    };
+
+    Ok(expire)
+}
+
+impl GetSet {
+    pub fn new(key: impl ToString, value: Bytes) -> GetSet {
+        GetSet {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    // GETSET key value
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<GetSet> {
+        let key = parse.next_string()?;
+        let value = parse.next_bytes()?;
+        Ok(GetSet { key, value })
+    }
+
+    // 相当于SET key value GET，原来的过期时间会被清除
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let set = Set {
+            key: self.key,
+            value: self.value,
+            options: SetOptions {
+                get: true,
+                ..Default::default()
+            },
+        };
+        set.apply(db, dst).await
+    }
+}
+
+impl SetNx {
+    pub fn new(key: impl ToString, value: Bytes) -> SetNx {
+        SetNx {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    // SETNX key value
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SetNx> {
+        let key = parse.next_string()?;
+        let value = parse.next_bytes()?;
+        Ok(SetNx { key, value })
+    }
+
+    // key不存在时写入并返回1，否则返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let options = SetOptions {
+            condition: SetCondition::NotExists,
+            ..Default::default()
+        };
+
+        let response = match db.set(self.key, self.value, options) {
+            Ok((written, _)) => Frame::Integer(written as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
     }
 }
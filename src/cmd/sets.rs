@@ -0,0 +1,520 @@
+use bytes::Bytes;
+
+use crate::{
+    connection::Connection,
+    db::{Db, SetOp},
+    frame::Frame,
+    parse::Parse,
+};
+
+#[derive(Debug)]
+pub struct SAdd {
+    key: String,
+    members: Vec<Bytes>,
+}
+
+#[derive(Debug)]
+pub struct SRem {
+    key: String,
+    members: Vec<Bytes>,
+}
+
+#[derive(Debug)]
+pub struct SMembers {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct SIsMember {
+    key: String,
+    member: Bytes,
+}
+
+#[derive(Debug)]
+pub struct SCard {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct SInter {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SUnion {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SDiff {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SInterStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SUnionStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SDiffStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SPop {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct SRandMember {
+    key: String,
+    count: Option<i64>,
+}
+
+impl SAdd {
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> SAdd {
+        SAdd {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn members(&self) -> &[Bytes] {
+        &self.members
+    }
+
+    // SADD key member [member ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SAdd> {
+        let key = parse.next_string()?;
+        let members = parse_members(parse)?;
+        Ok(SAdd { key, members })
+    }
+
+    // 返回新增的成员数量，已经存在的成员不计算在内
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.sadd(&self.key, self.members), dst).await
+    }
+}
+
+impl SRem {
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> SRem {
+        SRem {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn members(&self) -> &[Bytes] {
+        &self.members
+    }
+
+    // SREM key member [member ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SRem> {
+        let key = parse.next_string()?;
+        let members = parse_members(parse)?;
+        Ok(SRem { key, members })
+    }
+
+    // 返回实际删除的成员数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.srem(&self.key, &self.members), dst).await
+    }
+}
+
+impl SMembers {
+    pub fn new(key: impl ToString) -> SMembers {
+        SMembers {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // SMEMBERS key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SMembers> {
+        let key = parse.next_string()?;
+        Ok(SMembers { key })
+    }
+
+    // 返回所有的成员，key不存在时返回空集合
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_set(db.smembers(&self.key), dst).await
+    }
+}
+
+impl SIsMember {
+    pub fn new(key: impl ToString, member: Bytes) -> SIsMember {
+        SIsMember {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+
+    // SISMEMBER key member
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SIsMember> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+        Ok(SIsMember { key, member })
+    }
+
+    // member存在时返回1，否则返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let exists = db.sismember(&self.key, &self.member).map(usize::from);
+        write_len(exists, dst).await
+    }
+}
+
+impl SCard {
+    pub fn new(key: impl ToString) -> SCard {
+        SCard {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // SCARD key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SCard> {
+        let key = parse.next_string()?;
+        Ok(SCard { key })
+    }
+
+    // 返回成员的数量，key不存在时返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.scard(&self.key), dst).await
+    }
+}
+
+impl SInter {
+    pub fn new(keys: Vec<String>) -> SInter {
+        SInter { keys }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    // SINTER key [key ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SInter> {
+        let keys = parse_keys(parse)?;
+        Ok(SInter { keys })
+    }
+
+    // 返回所有set的交集
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_set(db.set_op(SetOp::Inter, &self.keys), dst).await
+    }
+}
+
+impl SUnion {
+    pub fn new(keys: Vec<String>) -> SUnion {
+        SUnion { keys }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    // SUNION key [key ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SUnion> {
+        let keys = parse_keys(parse)?;
+        Ok(SUnion { keys })
+    }
+
+    // 返回所有set的并集
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_set(db.set_op(SetOp::Union, &self.keys), dst).await
+    }
+}
+
+impl SDiff {
+    pub fn new(keys: Vec<String>) -> SDiff {
+        SDiff { keys }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    // SDIFF key [key ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SDiff> {
+        let keys = parse_keys(parse)?;
+        Ok(SDiff { keys })
+    }
+
+    // 返回第一个set中不属于其他任何一个set的成员
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_set(db.set_op(SetOp::Diff, &self.keys), dst).await
+    }
+}
+
+impl SInterStore {
+    pub fn new(destination: impl ToString, keys: Vec<String>) -> SInterStore {
+        SInterStore {
+            destination: destination.to_string(),
+            keys,
+        }
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    // SINTERSTORE destination key [key ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SInterStore> {
+        let destination = parse.next_string()?;
+        let keys = parse_keys(parse)?;
+        Ok(SInterStore { destination, keys })
+    }
+
+    // 把交集写入destination，返回交集中的成员数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let len = db.set_op_store(SetOp::Inter, &self.destination, &self.keys);
+        write_len(len, dst).await
+    }
+}
+
+impl SUnionStore {
+    pub fn new(destination: impl ToString, keys: Vec<String>) -> SUnionStore {
+        SUnionStore {
+            destination: destination.to_string(),
+            keys,
+        }
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    // SUNIONSTORE destination key [key ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SUnionStore> {
+        let destination = parse.next_string()?;
+        let keys = parse_keys(parse)?;
+        Ok(SUnionStore { destination, keys })
+    }
+
+    // 把并集写入destination，返回并集中的成员数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let len = db.set_op_store(SetOp::Union, &self.destination, &self.keys);
+        write_len(len, dst).await
+    }
+}
+
+impl SDiffStore {
+    pub fn new(destination: impl ToString, keys: Vec<String>) -> SDiffStore {
+        SDiffStore {
+            destination: destination.to_string(),
+            keys,
+        }
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    // SDIFFSTORE destination key [key ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SDiffStore> {
+        let destination = parse.next_string()?;
+        let keys = parse_keys(parse)?;
+        Ok(SDiffStore { destination, keys })
+    }
+
+    // 把差集写入destination，返回差集中的成员数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let len = db.set_op_store(SetOp::Diff, &self.destination, &self.keys);
+        write_len(len, dst).await
+    }
+}
+
+impl SPop {
+    pub fn new(key: impl ToString, count: Option<usize>) -> SPop {
+        SPop {
+            key: key.to_string(),
+            count,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    // SPOP key [count]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SPop> {
+        let key = parse.next_string()?;
+
+        let count = if parse.remaining() > 0 {
+            let count = usize::try_from(parse.next_signed()?)
+                .map_err(|_| "value is out of range, must be positive")?;
+            Some(count)
+        } else {
+            None
+        };
+
+        Ok(SPop { key, count })
+    }
+
+    // 不带count时返回被删除的成员，key不存在时返回Null
+    // 带count时返回被删除的成员组成的集合，key不存在时返回空集合
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let members = db.spop(&self.key, self.count.unwrap_or(1));
+
+        match self.count {
+            Some(_) => write_set(members, dst).await,
+            None => write_single(members, dst).await,
+        }
+    }
+}
+
+impl SRandMember {
+    pub fn new(key: impl ToString, count: Option<i64>) -> SRandMember {
+        SRandMember {
+            key: key.to_string(),
+            count,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn count(&self) -> Option<i64> {
+        self.count
+    }
+
+    // SRANDMEMBER key [count]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<SRandMember> {
+        let key = parse.next_string()?;
+
+        // 负数的范围由Db::srandmember检查
+        let count = if parse.remaining() > 0 {
+            Some(parse.next_signed()?)
+        } else {
+            None
+        };
+
+        Ok(SRandMember { key, count })
+    }
+
+    // 不带count时随机返回一个成员，key不存在时返回Null
+    // 带count时返回成员组成的数组，count为负数时同一个成员可能出现多次，key不存在时返回空数组
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let members = db.srandmember(&self.key, self.count.unwrap_or(1));
+
+        let response = match (members, self.count) {
+            (Ok(members), Some(_)) => {
+                let mut response = Frame::array();
+                for member in members {
+                    response.push_bulk(member);
+                }
+                response
+            }
+            (Ok(members), None) => members.into_iter().next().map_or(Frame::Null, Frame::Bulk),
+            (Err(err), _) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+// 成员至少要有一个
+fn parse_members(parse: &mut Parse) -> crate::Result<Vec<Bytes>> {
+    let mut members = vec![parse.next_bytes()?];
+    while parse.remaining() > 0 {
+        members.push(parse.next_bytes()?);
+    }
+    Ok(members)
+}
+
+// key至少要有一个
+fn parse_keys(parse: &mut Parse) -> crate::Result<Vec<String>> {
+    let mut keys = vec![parse.next_string()?];
+    while parse.remaining() > 0 {
+        keys.push(parse.next_string()?);
+    }
+    Ok(keys)
+}
+
+async fn write_len(len: crate::Result<usize>, dst: &mut Connection) -> crate::Result<()> {
+    let response = match len {
+        Ok(len) => Frame::Integer(len as i64),
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+// RESP3下返回Set，RESP2下返回数组
+async fn write_set(members: crate::Result<Vec<Bytes>>, dst: &mut Connection) -> crate::Result<()> {
+    let response = match members {
+        Ok(members) => Frame::Set(members.into_iter().map(Frame::Bulk).collect()),
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+// 只返回第一个成员，没有成员时返回Null
+async fn write_single(
+    members: crate::Result<Vec<Bytes>>,
+    dst: &mut Connection,
+) -> crate::Result<()> {
+    let response = match members {
+        Ok(members) => members.into_iter().next().map_or(Frame::Null, Frame::Bulk),
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
@@ -0,0 +1,1260 @@
+use std::{future, time::Duration};
+
+use bytes::Bytes;
+use tokio::time::{self, Instant};
+
+use crate::{
+    connection::Connection,
+    db::{
+        AutoClaimOptions, Db, PendingRange, StreamEntry, StreamId, StreamRead, XAddId,
+        XClaimOptions, XReadGroupOptions,
+    },
+    frame::{Frame, Protocol},
+    parse::{Parse, ParseError},
+    shutdown::Shutdown,
+};
+
+#[derive(Debug)]
+pub struct XAdd {
+    key: String,
+    id: XAddId,
+    fields: Vec<(Bytes, Bytes)>,
+    // key不存在时不创建stream
+    nomkstream: bool,
+    maxlen: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct XLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct XRange {
+    key: String,
+    start: StreamId,
+    end: StreamId,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct XRevRange {
+    key: String,
+    start: StreamId,
+    end: StreamId,
+    count: Option<usize>,
+}
+
+// ids中的None表示$，block为Some(Duration::ZERO)时和redis一样一直阻塞
+#[derive(Debug)]
+pub struct XRead {
+    keys: Vec<String>,
+    ids: Vec<Option<StreamId>>,
+    count: Option<usize>,
+    block: Option<Duration>,
+}
+
+// ids中的None表示>，block为Some(Duration::ZERO)时一直阻塞
+#[derive(Debug)]
+pub struct XReadGroup {
+    group: String,
+    consumer: String,
+    keys: Vec<String>,
+    ids: Vec<Option<StreamId>>,
+    count: Option<usize>,
+    block: Option<Duration>,
+    noack: bool,
+}
+
+#[derive(Debug)]
+pub struct XGroup {
+    key: String,
+    group: String,
+    action: XGroupAction,
+}
+
+// XGROUP的子命令，StreamId为None时表示$
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XGroupAction {
+    Create {
+        id: Option<StreamId>,
+        mkstream: bool,
+    },
+    Destroy,
+    CreateConsumer(String),
+    DelConsumer(String),
+    SetId(Option<StreamId>),
+}
+
+#[derive(Debug)]
+pub struct XAck {
+    key: String,
+    group: String,
+    ids: Vec<StreamId>,
+}
+
+// range为None时返回汇总信息
+#[derive(Debug)]
+pub struct XPending {
+    key: String,
+    group: String,
+    range: Option<PendingRange>,
+}
+
+#[derive(Debug)]
+pub struct XClaim {
+    key: String,
+    group: String,
+    consumer: String,
+    min_idle: u64,
+    ids: Vec<StreamId>,
+    options: XClaimOptions,
+}
+
+#[derive(Debug)]
+pub struct XAutoClaim {
+    key: String,
+    group: String,
+    consumer: String,
+    options: AutoClaimOptions,
+}
+
+const INVALID_ID: &str = "Invalid stream ID specified as stream command argument";
+
+impl XAdd {
+    pub fn new(key: impl ToString, id: XAddId, fields: Vec<(Bytes, Bytes)>) -> XAdd {
+        XAdd {
+            key: key.to_string(),
+            id,
+            fields,
+            nomkstream: false,
+            maxlen: None,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn id(&self) -> XAddId {
+        self.id
+    }
+
+    pub fn fields(&self) -> &[(Bytes, Bytes)] {
+        &self.fields
+    }
+
+    // XADD key [NOMKSTREAM] [MAXLEN [= | ~] threshold [LIMIT count]] <* | id> field value [field value ...]
+    // ~和LIMIT只是为了兼容redis的客户端，这里总是精确地裁剪到threshold
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XAdd> {
+        let key = parse.next_string()?;
+
+        let mut nomkstream = false;
+        let mut maxlen = None;
+        let mut approx = false;
+
+        // 选项都在ID之前，遇到第一个不是选项的参数时把它作为ID
+        let id = loop {
+            let arg = parse.next_bytes()?;
+
+            match &arg.to_ascii_uppercase()[..] {
+                b"NOMKSTREAM" => nomkstream = true,
+                b"MAXLEN" => {
+                    let mut threshold = parse.next_bytes()?;
+                    if &threshold[..] == b"=" || &threshold[..] == b"~" {
+                        approx = &threshold[..] == b"~";
+                        threshold = parse.next_bytes()?;
+                    }
+
+                    let threshold = crate::parse::parse_i64(&threshold)
+                        .ok_or("value is not an integer or out of range")?;
+                    let threshold = usize::try_from(threshold)
+                        .map_err(|_| "The MAXLEN argument must be >= 0.")?;
+                    maxlen = Some(threshold);
+                }
+                b"LIMIT" => {
+                    if parse.next_signed()? < 0 {
+                        return Err("The LIMIT argument must be >= 0.".into());
+                    }
+                    if !approx {
+                        return Err(
+                            "syntax error, LIMIT cannot be used without the special ~ option"
+                                .into(),
+                        );
+                    }
+                }
+                _ => break parse_xadd_id(&arg)?,
+            }
+        };
+
+        // field和value必须成对出现，并且至少有一对
+        if parse.remaining() == 0 || !parse.remaining().is_multiple_of(2) {
+            return Err("wrong number of arguments for 'xadd' command".into());
+        }
+
+        let mut fields = Vec::with_capacity(parse.remaining() / 2);
+        while parse.remaining() > 0 {
+            let field = parse.next_bytes()?;
+            let value = parse.next_bytes()?;
+            fields.push((field, value));
+        }
+
+        Ok(XAdd {
+            key,
+            id,
+            fields,
+            nomkstream,
+            maxlen,
+        })
+    }
+
+    // 返回新条目的ID，带NOMKSTREAM并且key不存在时返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.xadd(
+            &self.key,
+            self.id,
+            self.fields,
+            self.nomkstream,
+            self.maxlen,
+        ) {
+            Ok(Some(id)) => id_frame(id),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl XLen {
+    pub fn new(key: impl ToString) -> XLen {
+        XLen {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // XLEN key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XLen> {
+        let key = parse.next_string()?;
+        Ok(XLen { key })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.xlen(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl XRange {
+    pub fn new(key: impl ToString, start: StreamId, end: StreamId) -> XRange {
+        XRange {
+            key: key.to_string(),
+            start,
+            end,
+            count: None,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn start(&self) -> StreamId {
+        self.start
+    }
+
+    pub fn end(&self) -> StreamId {
+        self.end
+    }
+
+    // XRANGE key start end [COUNT count]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XRange> {
+        let key = parse.next_string()?;
+        let start = parse_range_start(parse)?;
+        let end = parse_range_end(parse)?;
+        let count = parse_range_count(parse)?;
+
+        Ok(XRange {
+            key,
+            start,
+            end,
+            count,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let entries = db.xrange(&self.key, self.start, self.end, self.count, false);
+        write_entries(entries, dst).await
+    }
+}
+
+impl XRevRange {
+    pub fn new(key: impl ToString, end: StreamId, start: StreamId) -> XRevRange {
+        XRevRange {
+            key: key.to_string(),
+            start,
+            end,
+            count: None,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn start(&self) -> StreamId {
+        self.start
+    }
+
+    pub fn end(&self) -> StreamId {
+        self.end
+    }
+
+    // XREVRANGE key end start [COUNT count]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XRevRange> {
+        let key = parse.next_string()?;
+        let end = parse_range_end(parse)?;
+        let start = parse_range_start(parse)?;
+        let count = parse_range_count(parse)?;
+
+        Ok(XRevRange {
+            key,
+            start,
+            end,
+            count,
+        })
+    }
+
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let entries = db.xrange(&self.key, self.start, self.end, self.count, true);
+        write_entries(entries, dst).await
+    }
+}
+
+impl XRead {
+    pub fn new(keys: Vec<String>, ids: Vec<Option<StreamId>>) -> XRead {
+        XRead {
+            keys,
+            ids,
+            count: None,
+            block: None,
+        }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn ids(&self) -> &[Option<StreamId>] {
+        &self.ids
+    }
+
+    // XREAD [COUNT count] [BLOCK milliseconds] STREAMS key [key ...] id [id ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XRead> {
+        let mut count = None;
+        let mut block = None;
+
+        loop {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "COUNT" => count = parse_read_count(parse)?,
+                "BLOCK" => block = Some(parse_block(parse)?),
+                "STREAMS" => break,
+                _ => return Err("syntax error".into()),
+            }
+        }
+
+        let (keys, ids) = parse_streams(parse, "xread", "$")?;
+        let ids = ids
+            .iter()
+            .map(|id| match &id[..] {
+                b"$" => Ok(None),
+                id => parse_id(id, 0).map(Some).ok_or(INVALID_ID),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(XRead {
+            keys,
+            ids,
+            count,
+            block,
+        })
+    }
+
+    // 返回每个有新条目的key以及新条目，RESP3下是map，RESP2下是[key, entries]组成的数组
+    // 没有新条目或者阻塞超时的时候返回Null
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let mut ids = self.ids;
+        let block = self.block.is_some();
+
+        let read = || db.xread(&self.keys, &mut ids, self.count, block);
+        apply_read(read, self.block, dst, shutdown).await
+    }
+}
+
+impl XReadGroup {
+    pub fn new(
+        group: impl ToString,
+        consumer: impl ToString,
+        keys: Vec<String>,
+        ids: Vec<Option<StreamId>>,
+    ) -> XReadGroup {
+        XReadGroup {
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            keys,
+            ids,
+            count: None,
+            block: None,
+            noack: false,
+        }
+    }
+
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn consumer(&self) -> &str {
+        &self.consumer
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn ids(&self) -> &[Option<StreamId>] {
+        &self.ids
+    }
+
+    // XREADGROUP GROUP group consumer [COUNT count] [BLOCK milliseconds] [NOACK]
+    //   STREAMS key [key ...] id [id ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XReadGroup> {
+        let mut group = None;
+        let mut count = None;
+        let mut block = None;
+        let mut noack = false;
+
+        loop {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "GROUP" => group = Some((parse.next_string()?, parse.next_string()?)),
+                "COUNT" => count = parse_read_count(parse)?,
+                "BLOCK" => block = Some(parse_block(parse)?),
+                "NOACK" => noack = true,
+                "STREAMS" => break,
+                _ => return Err("syntax error".into()),
+            }
+        }
+
+        let (group, consumer) = group.ok_or("Missing GROUP option for XREADGROUP")?;
+
+        let (keys, ids) = parse_streams(parse, "xreadgroup", ">")?;
+        let ids = ids
+            .iter()
+            .map(|id| match &id[..] {
+                b">" => Ok(None),
+                b"$" => Err("The $ ID is meaningless in the context of XREADGROUP: \
+                    you want to read the history of this consumer by specifying a proper ID, \
+                    or use the > ID to get new messages. The $ ID would just return an empty result set."),
+                id => parse_id(id, 0).map(Some).ok_or(INVALID_ID),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(XReadGroup {
+            group,
+            consumer,
+            keys,
+            ids,
+            count,
+            block,
+            noack,
+        })
+    }
+
+    // 回复的格式和XREAD相同，读取历史消息时已经被删除的条目返回[id, Null]
+    pub(crate) async fn apply(
+        self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let options = XReadGroupOptions {
+            count: self.count,
+            noack: self.noack,
+            block: self.block.is_some(),
+        };
+
+        let read = || db.xread_group(&self.group, &self.consumer, &self.keys, &self.ids, &options);
+        apply_read(read, self.block, dst, shutdown).await
+    }
+}
+
+impl XGroup {
+    pub fn new(key: impl ToString, group: impl ToString, action: XGroupAction) -> XGroup {
+        XGroup {
+            key: key.to_string(),
+            group: group.to_string(),
+            action,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn action(&self) -> &XGroupAction {
+        &self.action
+    }
+
+    // XGROUP CREATE key group <id | $> [MKSTREAM]
+    // XGROUP DESTROY key group
+    // XGROUP CREATECONSUMER key group consumer
+    // XGROUP DELCONSUMER key group consumer
+    // XGROUP SETID key group <id | $>
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XGroup> {
+        let subcommand = parse.next_string()?.to_uppercase();
+
+        let unknown = || {
+            format!(
+                "unknown subcommand or wrong number of arguments for '{}'. Try XGROUP HELP.",
+                subcommand
+            )
+        };
+
+        let (key, group) = match (parse.next_string(), parse.next_string()) {
+            (Ok(key), Ok(group)) => (key, group),
+            (Err(ParseError::EndOfStream), _) | (_, Err(ParseError::EndOfStream)) => {
+                return Err(unknown().into())
+            }
+            (Err(err), _) | (_, Err(err)) => return Err(err.into()),
+        };
+
+        let action = match &subcommand[..] {
+            "CREATE" => {
+                let id = parse_group_id(parse)?;
+                let mkstream = match parse.next_string() {
+                    Ok(option) if option.eq_ignore_ascii_case("MKSTREAM") => true,
+                    Ok(_) => return Err("syntax error".into()),
+                    Err(ParseError::EndOfStream) => false,
+                    Err(err) => return Err(err.into()),
+                };
+                XGroupAction::Create { id, mkstream }
+            }
+            "DESTROY" => XGroupAction::Destroy,
+            "CREATECONSUMER" => XGroupAction::CreateConsumer(parse.next_string()?),
+            "DELCONSUMER" => XGroupAction::DelConsumer(parse.next_string()?),
+            "SETID" => XGroupAction::SetId(parse_group_id(parse)?),
+            _ => return Err(unknown().into()),
+        };
+
+        Ok(XGroup { key, group, action })
+    }
+
+    // CREATE、SETID返回OK，DESTROY、CREATECONSUMER返回是否删除或者创建成功
+    // DELCONSUMER返回被删除的消费者还没有确认的条目数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let (key, group) = (&self.key[..], &self.group[..]);
+
+        let result = match self.action {
+            XGroupAction::Create { id, mkstream } => db
+                .xgroup_create(key, group, id, mkstream)
+                .map(|_| Frame::Simple("OK".to_string())),
+            XGroupAction::Destroy => db
+                .xgroup_destroy(key, group)
+                .map(|destroyed| Frame::Integer(destroyed as i64)),
+            XGroupAction::CreateConsumer(consumer) => db
+                .xgroup_create_consumer(key, group, &consumer)
+                .map(|created| Frame::Integer(created as i64)),
+            XGroupAction::DelConsumer(consumer) => db
+                .xgroup_delete_consumer(key, group, &consumer)
+                .map(|pending| Frame::Integer(pending as i64)),
+            XGroupAction::SetId(id) => db
+                .xgroup_set_id(key, group, id)
+                .map(|_| Frame::Simple("OK".to_string())),
+        };
+
+        let response = result.unwrap_or_else(|err| Frame::Error(err.to_string()));
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl XAck {
+    pub fn new(key: impl ToString, group: impl ToString, ids: Vec<StreamId>) -> XAck {
+        XAck {
+            key: key.to_string(),
+            group: group.to_string(),
+            ids,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn ids(&self) -> &[StreamId] {
+        &self.ids
+    }
+
+    // XACK key group id [id ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XAck> {
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+
+        let mut ids = vec![parse_next_id(parse)?];
+        while parse.remaining() > 0 {
+            ids.push(parse_next_id(parse)?);
+        }
+
+        Ok(XAck { key, group, ids })
+    }
+
+    // 返回确认成功的数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.xack(&self.key, &self.group, &self.ids) {
+            Ok(acked) => Frame::Integer(acked as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl XPending {
+    pub fn new(key: impl ToString, group: impl ToString, range: Option<PendingRange>) -> XPending {
+        XPending {
+            key: key.to_string(),
+            group: group.to_string(),
+            range,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn range(&self) -> Option<&PendingRange> {
+        self.range.as_ref()
+    }
+
+    // XPENDING key group [[IDLE min-idle-time] start end count [consumer]]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XPending> {
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+
+        if parse.remaining() == 0 {
+            return Ok(XPending {
+                key,
+                group,
+                range: None,
+            });
+        }
+
+        let mut start = parse.next_bytes()?;
+        let mut min_idle = None;
+        if start.eq_ignore_ascii_case(b"IDLE") {
+            min_idle = Some(parse.next_signed()?.max(0) as u64);
+            start = parse.next_bytes()?;
+        }
+
+        let start = range_start(&start)?;
+        let end = parse_range_end(parse)?;
+        // 和redis一样，负数的count视为0
+        let count = parse.next_signed()?.max(0) as usize;
+        let consumer = match parse.next_string() {
+            Ok(consumer) => Some(consumer),
+            Err(ParseError::EndOfStream) => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        if parse.remaining() > 0 {
+            return Err("syntax error".into());
+        }
+
+        Ok(XPending {
+            key,
+            group,
+            range: Some(PendingRange {
+                min_idle,
+                start,
+                end,
+                count,
+                consumer,
+            }),
+        })
+    }
+
+    // 不带范围时返回[数量, 最小ID, 最大ID, [[消费者, 数量], ...]]，没有待确认的条目时后面三项为Null
+    // 带范围时返回[[ID, 消费者, 空闲毫秒数, 投递次数], ...]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let range = match self.range {
+            Some(range) => range,
+            None => return self.apply_summary(db, dst).await,
+        };
+
+        let response = match db.xpending(&self.key, &self.group, &range) {
+            Ok(entries) => Frame::Array(
+                entries
+                    .into_iter()
+                    .map(|entry| {
+                        Frame::Array(vec![
+                            id_frame(entry.id),
+                            Frame::Bulk(Bytes::from(entry.consumer)),
+                            Frame::Integer(entry.idle as i64),
+                            Frame::Integer(entry.delivery_count as i64),
+                        ])
+                    })
+                    .collect(),
+            ),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    async fn apply_summary(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.xpending_summary(&self.key, &self.group) {
+            Ok(summary) => {
+                let (min, max) = match summary.bounds {
+                    Some((min, max)) => (id_frame(min), id_frame(max)),
+                    None => (Frame::Null, Frame::Null),
+                };
+
+                // 和redis一样，每个消费者的数量以字符串的形式返回
+                let consumers = if summary.consumers.is_empty() {
+                    Frame::Null
+                } else {
+                    Frame::Array(
+                        summary
+                            .consumers
+                            .into_iter()
+                            .map(|(consumer, count)| {
+                                Frame::Array(vec![
+                                    Frame::Bulk(Bytes::from(consumer)),
+                                    Frame::Bulk(Bytes::from(count.to_string())),
+                                ])
+                            })
+                            .collect(),
+                    )
+                };
+
+                Frame::Array(vec![
+                    Frame::Integer(summary.count as i64),
+                    min,
+                    max,
+                    consumers,
+                ])
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl XClaim {
+    pub fn new(
+        key: impl ToString,
+        group: impl ToString,
+        consumer: impl ToString,
+        min_idle: u64,
+        ids: Vec<StreamId>,
+    ) -> XClaim {
+        XClaim {
+            key: key.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            min_idle,
+            ids,
+            options: XClaimOptions::default(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn consumer(&self) -> &str {
+        &self.consumer
+    }
+
+    pub fn ids(&self) -> &[StreamId] {
+        &self.ids
+    }
+
+    pub fn options(&self) -> &XClaimOptions {
+        &self.options
+    }
+
+    // XCLAIM key group consumer min-idle-time id [id ...] [IDLE ms] [TIME unix-time-milliseconds]
+    //   [RETRYCOUNT count] [FORCE] [JUSTID] [LASTID lastid]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XClaim> {
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+        let consumer = parse.next_string()?;
+        let min_idle = parse_min_idle(parse)?;
+
+        // ID之后是可选参数，遇到第一个不是ID的参数时开始解析可选参数
+        let mut ids = vec![parse_next_id(parse)?];
+        let mut options = XClaimOptions::default();
+        let mut parsing_ids = true;
+
+        while parse.remaining() > 0 {
+            let arg = parse.next_bytes()?;
+
+            if parsing_ids {
+                if let Some(id) = parse_id(&arg, 0) {
+                    ids.push(id);
+                    continue;
+                }
+                parsing_ids = false;
+            }
+
+            match &arg.to_ascii_uppercase()[..] {
+                b"IDLE" => options.idle = Some(parse.next_signed()?.max(0) as u64),
+                b"TIME" => options.time = Some(parse.next_signed()?.max(0) as u64),
+                b"RETRYCOUNT" => {
+                    let retry_count = parse.next_signed()?;
+                    let retry_count = u64::try_from(retry_count)
+                        .map_err(|_| "Invalid RETRYCOUNT option argument for XCLAIM")?;
+                    options.retry_count = Some(retry_count);
+                }
+                b"FORCE" => options.force = true,
+                b"JUSTID" => options.justid = true,
+                b"LASTID" => options.last_id = Some(parse_next_id(parse)?),
+                _ => {
+                    return Err(format!(
+                        "Unrecognized XCLAIM option '{}'",
+                        String::from_utf8_lossy(&arg)
+                    )
+                    .into())
+                }
+            }
+        }
+
+        Ok(XClaim {
+            key,
+            group,
+            consumer,
+            min_idle,
+            ids,
+            options,
+        })
+    }
+
+    // 返回转移成功的条目，带JUSTID时只返回ID
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let claimed = db.xclaim(
+            &self.key,
+            &self.group,
+            &self.consumer,
+            self.min_idle,
+            &self.ids,
+            &self.options,
+        );
+
+        let response = match claimed {
+            Ok(claimed) => claimed_frame(claimed, self.options.justid),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl XAutoClaim {
+    pub fn new(
+        key: impl ToString,
+        group: impl ToString,
+        consumer: impl ToString,
+        min_idle: u64,
+        start: StreamId,
+    ) -> XAutoClaim {
+        XAutoClaim {
+            key: key.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            options: AutoClaimOptions {
+                min_idle,
+                start,
+                count: 100,
+                justid: false,
+            },
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn consumer(&self) -> &str {
+        &self.consumer
+    }
+
+    pub fn options(&self) -> &AutoClaimOptions {
+        &self.options
+    }
+
+    // XAUTOCLAIM key group consumer min-idle-time start [COUNT count] [JUSTID]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<XAutoClaim> {
+        let key = parse.next_string()?;
+        let group = parse.next_string()?;
+        let consumer = parse.next_string()?;
+        let min_idle = parse_min_idle(parse)?;
+        let start = parse_range_start(parse)?;
+
+        let mut options = AutoClaimOptions {
+            min_idle,
+            start,
+            count: 100,
+            justid: false,
+        };
+
+        loop {
+            match parse.next_string() {
+                Ok(option) => match &option.to_uppercase()[..] {
+                    "COUNT" => {
+                        // 和redis一样限制count的上限，避免count * 10溢出
+                        let count = parse.next_signed()?;
+                        if !(1..=i64::MAX / 10).contains(&count) {
+                            return Err("COUNT must be > 0".into());
+                        }
+                        options.count = count as usize;
+                    }
+                    "JUSTID" => options.justid = true,
+                    _ => return Err("syntax error".into()),
+                },
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(XAutoClaim {
+            key,
+            group,
+            consumer,
+            options,
+        })
+    }
+
+    // 返回[下一次扫描的起点, 转移成功的条目, 已经被删除的ID]，带JUSTID时第二项只包含ID
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let claimed = db.xautoclaim(&self.key, &self.group, &self.consumer, &self.options);
+
+        let response = match claimed {
+            Ok(result) => Frame::Array(vec![
+                id_frame(result.cursor),
+                claimed_frame(result.claimed, self.options.justid),
+                Frame::Array(result.deleted.into_iter().map(id_frame).collect()),
+            ]),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+// XREAD、XREADGROUP共用，没有新条目并且带BLOCK时等待新条目写入，被唤醒之后重新读取
+// stream的条目不会被取走，所以超时的时候即使刚好有新条目写入也不会丢失
+async fn apply_read(
+    mut read: impl FnMut() -> crate::Result<StreamRead>,
+    block: Option<Duration>,
+    dst: &mut Connection,
+    shutdown: &mut Shutdown,
+) -> crate::Result<()> {
+    // 多次被唤醒时超时时间从第一次阻塞开始计算
+    let deadline = block
+        .filter(|block| !block.is_zero())
+        .map(|block| Instant::now() + block);
+
+    let response = loop {
+        let mut waiter = match read() {
+            Ok(StreamRead::Entries(streams)) if streams.is_empty() => break Frame::Null,
+            Ok(StreamRead::Entries(streams)) => break streams_frame(streams, dst.protocol()),
+            Ok(StreamRead::Blocked(waiter)) => waiter,
+            Err(err) => break Frame::Error(err.to_string()),
+        };
+
+        // 同一批pipeline中前面的命令的响应还在缓冲区中，阻塞之前需要先发送给客户端
+        dst.flush().await?;
+
+        let sleep = async {
+            match deadline {
+                Some(deadline) => time::sleep_until(deadline).await,
+                None => future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            woken = waiter.recv() => {
+                // Db已经关闭
+                if !woken {
+                    return Ok(());
+                }
+            }
+            _ = sleep => break Frame::Null,
+            // 服务器关闭或者客户端断开，不需要再响应
+            _ = shutdown.recv() => return Ok(()),
+            _ = dst.closed() => return Ok(()),
+        }
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+// XADD的ID：*、ms-*或者完整的ID，只有ms时序号为0
+fn parse_xadd_id(src: &[u8]) -> crate::Result<XAddId> {
+    if src == b"*" {
+        return Ok(XAddId::Auto);
+    }
+
+    if let Some(ms) = src.strip_suffix(b"-*") {
+        return parse_u64(ms)
+            .map(XAddId::AutoSeq)
+            .ok_or_else(|| INVALID_ID.into());
+    }
+
+    parse_id(src, 0)
+        .map(XAddId::Explicit)
+        .ok_or_else(|| INVALID_ID.into())
+}
+
+fn parse_range_start(parse: &mut Parse) -> crate::Result<StreamId> {
+    range_start(&parse.next_bytes()?)
+}
+
+// 范围的起点，-表示最小的ID，(表示不包含这个ID，只有ms时序号为0
+fn range_start(src: &[u8]) -> crate::Result<StreamId> {
+    match src {
+        b"-" => Ok(StreamId::MIN),
+        [b'(', id @ ..] => parse_id(id, 0)
+            .ok_or(INVALID_ID)?
+            .next()
+            .ok_or_else(|| "invalid start ID for the interval".into()),
+        id => parse_id(id, 0).ok_or_else(|| INVALID_ID.into()),
+    }
+}
+
+// 范围的终点，+表示最大的ID，(表示不包含这个ID，只有ms时包含这一毫秒内所有的序号
+fn parse_range_end(parse: &mut Parse) -> crate::Result<StreamId> {
+    let src = parse.next_bytes()?;
+
+    match &src[..] {
+        b"+" => Ok(StreamId::MAX),
+        [b'(', id @ ..] => parse_id(id, u64::MAX)
+            .ok_or(INVALID_ID)?
+            .prev()
+            .ok_or_else(|| "invalid end ID for the interval".into()),
+        id => parse_id(id, u64::MAX).ok_or_else(|| INVALID_ID.into()),
+    }
+}
+
+// XRANGE、XREVRANGE的[COUNT count]，负数的count视为0
+fn parse_range_count(parse: &mut Parse) -> crate::Result<Option<usize>> {
+    if parse.remaining() == 0 {
+        return Ok(None);
+    }
+
+    if !parse.next_string()?.eq_ignore_ascii_case("COUNT") || parse.remaining() != 1 {
+        return Err("syntax error".into());
+    }
+
+    Ok(Some(parse.next_signed()?.max(0) as usize))
+}
+
+// XREAD、XREADGROUP的COUNT，和redis一样小于等于0时不限制数量
+fn parse_read_count(parse: &mut Parse) -> crate::Result<Option<usize>> {
+    let count = parse.next_signed()?;
+    Ok((count > 0).then_some(count as usize))
+}
+
+// BLOCK的毫秒数，0表示一直阻塞
+fn parse_block(parse: &mut Parse) -> crate::Result<Duration> {
+    let timeout = parse
+        .next_signed()
+        .map_err(|_| "timeout is not an integer or out of range")?;
+    let timeout = u64::try_from(timeout).map_err(|_| "timeout is negative")?;
+    Ok(Duration::from_millis(timeout))
+}
+
+// STREAMS之后的key和ID，数量必须相同，special是command中表示特殊ID的符号，用于错误信息
+fn parse_streams(
+    parse: &mut Parse,
+    command: &str,
+    special: &str,
+) -> crate::Result<(Vec<String>, Vec<Bytes>)> {
+    let remaining = parse.remaining();
+    if remaining == 0 || !remaining.is_multiple_of(2) {
+        return Err(format!(
+            "Unbalanced '{}' list of streams: for each stream key an ID or '{}' must be specified.",
+            command, special
+        )
+        .into());
+    }
+
+    let mut keys = Vec::with_capacity(remaining / 2);
+    for _ in 0..remaining / 2 {
+        keys.push(parse.next_string()?);
+    }
+
+    let mut ids = Vec::with_capacity(remaining / 2);
+    while parse.remaining() > 0 {
+        ids.push(parse.next_bytes()?);
+    }
+
+    Ok((keys, ids))
+}
+
+// XGROUP CREATE、SETID的ID，$表示stream当前的last_id
+fn parse_group_id(parse: &mut Parse) -> crate::Result<Option<StreamId>> {
+    match &parse.next_bytes()?[..] {
+        b"$" => Ok(None),
+        id => parse_id(id, 0).map(Some).ok_or_else(|| INVALID_ID.into()),
+    }
+}
+
+// XCLAIM、XAUTOCLAIM的min-idle-time，负数视为0
+fn parse_min_idle(parse: &mut Parse) -> crate::Result<u64> {
+    let min_idle = parse
+        .next_signed()
+        .map_err(|_| "Invalid min-idle-time argument for XCLAIM")?;
+    Ok(min_idle.max(0) as u64)
+}
+
+fn parse_next_id(parse: &mut Parse) -> crate::Result<StreamId> {
+    parse_id(&parse.next_bytes()?, 0).ok_or_else(|| INVALID_ID.into())
+}
+
+// ms-seq或者只有ms，只有ms时序号为missing_seq
+fn parse_id(src: &[u8], missing_seq: u64) -> Option<StreamId> {
+    let (ms, seq) = match src.iter().position(|&b| b == b'-') {
+        Some(index) => (&src[..index], parse_u64(&src[index + 1..])?),
+        None => (src, missing_seq),
+    };
+
+    Some(StreamId::new(parse_u64(ms)?, seq))
+}
+
+// 只接受数字，不接受符号和空字符串
+fn parse_u64(src: &[u8]) -> Option<u64> {
+    if src.is_empty() || !src.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    std::str::from_utf8(src).ok()?.parse().ok()
+}
+
+fn id_frame(id: StreamId) -> Frame {
+    Frame::Bulk(Bytes::from(id.to_string()))
+}
+
+// [id, [field, value, ...]]，条目已经被删除时为[id, Null]
+fn entry_frame(entry: StreamEntry) -> Frame {
+    let fields = match entry.fields {
+        Some(fields) => Frame::Array(
+            fields
+                .into_iter()
+                .flat_map(|(field, value)| [Frame::Bulk(field), Frame::Bulk(value)])
+                .collect(),
+        ),
+        None => Frame::Null,
+    };
+
+    Frame::Array(vec![id_frame(entry.id), fields])
+}
+
+fn entries_frame(entries: Vec<StreamEntry>) -> Frame {
+    Frame::Array(entries.into_iter().map(entry_frame).collect())
+}
+
+// XCLAIM、XAUTOCLAIM转移成功的条目，justid为true时只返回ID
+fn claimed_frame(claimed: Vec<StreamEntry>, justid: bool) -> Frame {
+    if justid {
+        Frame::Array(
+            claimed
+                .into_iter()
+                .map(|entry| id_frame(entry.id))
+                .collect(),
+        )
+    } else {
+        entries_frame(claimed)
+    }
+}
+
+// RESP3下是key到条目的map，RESP2下是[key, entries]组成的数组
+fn streams_frame(streams: Vec<(String, Vec<StreamEntry>)>, protocol: Protocol) -> Frame {
+    let streams = streams
+        .into_iter()
+        .map(|(key, entries)| (Frame::Bulk(Bytes::from(key)), entries_frame(entries)));
+
+    match protocol {
+        Protocol::Resp3 => Frame::Map(streams.collect()),
+        Protocol::Resp2 => Frame::Array(
+            streams
+                .map(|(key, entries)| Frame::Array(vec![key, entries]))
+                .collect(),
+        ),
+    }
+}
+
+async fn write_entries(
+    entries: crate::Result<Vec<StreamEntry>>,
+    dst: &mut Connection,
+) -> crate::Result<()> {
+    let response = match entries {
+        Ok(entries) => entries_frame(entries),
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use crate::{connection::Connection, db::Db, frame::Frame, parse::Parse};
+
+#[derive(Debug)]
+pub struct Strlen {
+    key: String,
+}
+
+impl Strlen {
+    pub fn new(key: impl ToString) -> Strlen {
+        Strlen {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // STRLEN key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<Strlen> {
+        let key = parse.next_string()?;
+        Ok(Strlen { key })
+    }
+
+    // 返回值的长度，key不存在时返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.strlen(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
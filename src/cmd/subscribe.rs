@@ -1,4 +1,16 @@
-use crate::parse::{Parse, ParseError};
+use std::pin::Pin;
+
+use bytes::Bytes;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt, StreamMap};
+
+use crate::{
+    cmd::{Command, Ping},
+    connection::Connection,
+    db::Db,
+    frame::{Frame, Protocol},
+    parse::{Parse, ParseError},
+    shutdown::Shutdown,
+};
 
 #[derive(Debug)]
 pub struct Subscribe {
@@ -10,11 +22,18 @@ pub struct Unsubscribe {
     channels: Vec<String>,
 }
 
+// 每个channel对应一个消息流，订阅者通过StreamMap同时监听所有channel的消息
+type Messages = Pin<Box<dyn Stream<Item = Bytes> + Send>>;
+
 impl Subscribe {
-    fn new(channels: Vec<String>) -> Subscribe {
+    pub fn new(channels: Vec<String>) -> Subscribe {
         Subscribe { channels }
     }
 
+    pub fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
     // SUBSCRIBE channel1 channel2
     pub fn parse_frame(parse: &mut Parse) -> crate::Result<Subscribe> {
         let mut channels = vec![parse.next_string()?];
@@ -29,15 +48,201 @@ impl Subscribe {
 
         Ok(Subscribe { channels })
     }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from_static(b"subscribe"));
+        for channel in self.channels {
+            frame.push_bulk(Bytes::from(channel.into_bytes()));
+        }
+        frame
+    }
+
+    // 进入订阅模式，连接会一直停留在这里，直到客户端取消了所有的订阅、断开连接或者服务器关闭
+    // RESP2的订阅模式下客户端只能继续发送SUBSCRIBE、UNSUBSCRIBE以及PING命令
+    // RESP3下消息以Push的形式发送，可以和普通命令的响应区分开，因此其他命令也可以正常执行
+    pub(crate) async fn apply(
+        mut self,
+        db: &Db,
+        dst: &mut Connection,
+        shutdown: &mut Shutdown,
+    ) -> crate::Result<()> {
+        let mut subscriptions: StreamMap<String, Messages> = StreamMap::new();
+
+        loop {
+            // 处理新加入的channel，初次进入或者订阅模式下收到SUBSCRIBE时channels都不为空
+            for channel_name in self.channels.drain(..) {
+                subscribe_to_channel(channel_name, &mut subscriptions, db, dst).await?;
+            }
+
+            // 等待下一个事件之前，把这一轮写入的响应全部发送给客户端
+            dst.flush().await?;
+
+            tokio::select! {
+                // 收到某个channel发布的消息，转发给客户端
+                Some((channel_name, msg)) = subscriptions.next() => {
+                    dst.write_frame(&make_message_frame(channel_name, msg)).await?;
+                }
+                res = dst.read_frame() => {
+                    let frame = match res? {
+                        Some(frame) => frame,
+                        // 客户端断开了连接
+                        None => return Ok(()),
+                    };
+
+                    handle_command(
+                        frame,
+                        &mut self.channels,
+                        &mut subscriptions,
+                        db,
+                        dst,
+                        shutdown,
+                    )
+                    .await?;
+
+                    // 所有订阅都被取消后，退出订阅模式，回到普通的命令处理流程
+                    if self.channels.is_empty() && subscriptions.is_empty() {
+                        return Ok(());
+                    }
+                }
+                _ = shutdown.recv() => {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+async fn subscribe_to_channel(
+    channel_name: String,
+    subscriptions: &mut StreamMap<String, Messages>,
+    db: &Db,
+    dst: &mut Connection,
+) -> crate::Result<()> {
+    // 重复订阅同一个channel不会产生新的消息流，只需要再回复一次确认
+    if !subscriptions.contains_key(&channel_name) {
+        let rx = db.subscribe(channel_name.clone());
+
+        // 订阅者处理得太慢时broadcast会丢弃旧消息并返回Lagged错误，这里直接跳过这些错误
+        let rx: Messages = Box::pin(BroadcastStream::new(rx).filter_map(|msg| msg.ok()));
+
+        subscriptions.insert(channel_name.clone(), rx);
+    }
+
+    let response = make_subscribe_frame(channel_name, subscriptions.len());
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+// 处理订阅模式下收到的命令
+async fn handle_command(
+    frame: Frame,
+    subscribe_to: &mut Vec<String>,
+    subscriptions: &mut StreamMap<String, Messages>,
+    db: &Db,
+    dst: &mut Connection,
+    shutdown: &mut Shutdown,
+) -> crate::Result<()> {
+    // 和订阅模式之外一样，参数有误时返回错误，连接可以继续使用
+    let command = match Command::from_frame(frame) {
+        Ok(command) => command,
+        Err(err) => {
+            let response = Frame::Error(format!("ERR {}", err));
+            dst.write_frame(&response).await?;
+            return Ok(());
+        }
+    };
+
+    match command {
+        // 新的channel会在下一轮循环中订阅
+        Command::Subscribe(subscribe) => {
+            subscribe_to.extend(subscribe.channels);
+        }
+        Command::Unsubscribe(mut unsubscribe) => {
+            // 没有指定channel的话，取消所有的订阅
+            if unsubscribe.channels.is_empty() {
+                unsubscribe.channels = subscriptions
+                    .keys()
+                    .map(|channel_name| channel_name.to_string())
+                    .collect();
+            }
+
+            for channel_name in unsubscribe.channels {
+                subscriptions.remove(&channel_name);
+
+                let response = make_unsubscribe_frame(channel_name, subscriptions.len());
+                dst.write_frame(&response).await?;
+            }
+        }
+        // RESP2订阅模式下的PING返回 ["pong", msg]
+        Command::Ping(ping) if dst.protocol() == Protocol::Resp2 => {
+            dst.write_frame(&make_pong_frame(ping)).await?;
+        }
+        // Command::apply中也会调用Subscribe::apply，递归的async fn需要Box::pin
+        command if dst.protocol() == Protocol::Resp3 => {
+            Box::pin(command.apply(db, dst, shutdown)).await?;
+        }
+        command => {
+            let response = Frame::Error(format!(
+                "ERR Can't execute '{}': only SUBSCRIBE / UNSUBSCRIBE / PING are allowed in this context",
+                command.get_name()
+            ));
+            dst.write_frame(&response).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// 订阅相关的响应都使用Push类型，RESP2的连接上会被编码成普通的数组
+// ["subscribe", channel, 当前订阅的数量]
+fn make_subscribe_frame(channel_name: String, num_subs: usize) -> Frame {
+    let mut response = Frame::Push(vec![]);
+    response.push_bulk(Bytes::from_static(b"subscribe"));
+    response.push_bulk(Bytes::from(channel_name));
+    response.push_int(num_subs as i64);
+    response
+}
+
+// ["unsubscribe", channel, 剩余订阅的数量]
+fn make_unsubscribe_frame(channel_name: String, num_subs: usize) -> Frame {
+    let mut response = Frame::Push(vec![]);
+    response.push_bulk(Bytes::from_static(b"unsubscribe"));
+    response.push_bulk(Bytes::from(channel_name));
+    response.push_int(num_subs as i64);
+    response
+}
+
+// ["message", channel, 消息内容]
+fn make_message_frame(channel_name: String, msg: Bytes) -> Frame {
+    let mut response = Frame::Push(vec![]);
+    response.push_bulk(Bytes::from_static(b"message"));
+    response.push_bulk(Bytes::from(channel_name));
+    response.push_bulk(msg);
+    response
+}
+
+fn make_pong_frame(ping: Ping) -> Frame {
+    let mut response = Frame::array();
+    response.push_bulk(Bytes::from_static(b"pong"));
+    response.push_bulk(ping.msg().cloned().unwrap_or_default());
+    response
 }
 
 impl Unsubscribe {
-    fn new(channels: Vec<String>) -> Unsubscribe {
+    pub fn new(channels: Vec<String>) -> Unsubscribe {
         Unsubscribe { channels }
     }
 
+    pub fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    // UNSUBSCRIBE [channel1 channel2]
+    // 不带参数时表示取消所有的订阅
     pub fn parse_frame(parse: &mut Parse) -> crate::Result<Unsubscribe> {
-        let mut channels = vec![parse.next_string()?];
+        let mut channels = vec![];
 
         loop {
             match parse.next_string() {
@@ -49,4 +254,31 @@ impl Unsubscribe {
 
         Ok(Unsubscribe { channels })
     }
+
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from_static(b"unsubscribe"));
+        for channel in self.channels {
+            frame.push_bulk(Bytes::from(channel.into_bytes()));
+        }
+        frame
+    }
+
+    // 不在订阅模式中时，没有任何订阅可以取消，按redis的行为对每个channel回复剩余数量0
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        if self.channels.is_empty() {
+            let mut response = Frame::Push(vec![]);
+            response.push_bulk(Bytes::from_static(b"unsubscribe"));
+            response.push_null();
+            response.push_int(0);
+            dst.write_frame(&response).await?;
+        }
+
+        for channel_name in self.channels {
+            dst.write_frame(&make_unsubscribe_frame(channel_name, 0))
+                .await?;
+        }
+
+        Ok(())
+    }
 }
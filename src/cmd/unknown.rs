@@ -1,4 +1,4 @@
-use crate::parse::Parse;
+use crate::{connection::Connection, frame::Frame};
 
 #[derive(Debug)]
 pub struct Unknown {
@@ -12,7 +12,16 @@ impl Unknown {
         }
     }
 
-    fn get_name(&self) -> &str {
+    pub fn get_name(&self) -> &str {
         &self.command_name
     }
+
+    // 不支持的命令，返回错误信息给客户端
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Error(format!("ERR unknown command '{}'", self.command_name));
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
 }
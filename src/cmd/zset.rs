@@ -0,0 +1,885 @@
+use bytes::Bytes;
+
+use crate::{
+    connection::Connection,
+    db::{Aggregate, Db, LexBound, ScoreBound, SetOp, ZAddOptions, ZRange as Range},
+    frame::{Frame, Protocol},
+    parse::{parse_i64, parse_score, Parse, ParseError},
+};
+
+#[derive(Debug)]
+pub struct ZAdd {
+    key: String,
+    pairs: Vec<(f64, Bytes)>,
+    options: ZAddOptions,
+    // 分数作为增量，相当于ZINCRBY
+    incr: bool,
+}
+
+#[derive(Debug)]
+pub struct ZRem {
+    key: String,
+    members: Vec<Bytes>,
+}
+
+#[derive(Debug)]
+pub struct ZScore {
+    key: String,
+    member: Bytes,
+}
+
+#[derive(Debug)]
+pub struct ZRank {
+    key: String,
+    member: Bytes,
+    withscore: bool,
+}
+
+#[derive(Debug)]
+pub struct ZRevRank {
+    key: String,
+    member: Bytes,
+    withscore: bool,
+}
+
+#[derive(Debug)]
+pub struct ZCard {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ZRange {
+    key: String,
+    range: Range,
+    rev: bool,
+    limit: Option<(i64, i64)>,
+    withscores: bool,
+}
+
+#[derive(Debug)]
+pub struct ZCount {
+    key: String,
+    min: ScoreBound,
+    max: ScoreBound,
+}
+
+#[derive(Debug)]
+pub struct ZIncrBy {
+    key: String,
+    increment: f64,
+    member: Bytes,
+}
+
+#[derive(Debug)]
+pub struct ZPopMin {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct ZPopMax {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct ZUnionStore {
+    destination: String,
+    keys: Vec<String>,
+    weights: Vec<f64>,
+    aggregate: Aggregate,
+}
+
+#[derive(Debug)]
+pub struct ZInterStore {
+    destination: String,
+    keys: Vec<String>,
+    weights: Vec<f64>,
+    aggregate: Aggregate,
+}
+
+// ZUNIONSTORE、ZINTERSTORE解析之后的参数
+struct StoreArgs {
+    destination: String,
+    keys: Vec<String>,
+    weights: Vec<f64>,
+    aggregate: Aggregate,
+}
+
+impl ZAdd {
+    pub fn new(key: impl ToString, pairs: Vec<(f64, Bytes)>, options: ZAddOptions) -> ZAdd {
+        ZAdd {
+            key: key.to_string(),
+            pairs,
+            options,
+            incr: false,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn pairs(&self) -> &[(f64, Bytes)] {
+        &self.pairs
+    }
+
+    pub fn options(&self) -> &ZAddOptions {
+        &self.options
+    }
+
+    // ZADD key [NX | XX] [GT | LT] [CH] [INCR] score member [score member ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZAdd> {
+        let key = parse.next_string()?;
+
+        let mut options = ZAddOptions::default();
+        let mut incr = false;
+
+        // 选项都在score member之前，遇到第一个不是选项的参数时开始解析score member
+        let mut args = vec![];
+        while parse.remaining() > 0 {
+            args.push(parse.next_bytes()?);
+        }
+
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.peek() {
+            match &arg.to_ascii_uppercase()[..] {
+                b"NX" => options.nx = true,
+                b"XX" => options.xx = true,
+                b"GT" => options.gt = true,
+                b"LT" => options.lt = true,
+                b"CH" => options.ch = true,
+                b"INCR" => incr = true,
+                _ => break,
+            }
+            args.next();
+        }
+
+        if options.nx && options.xx {
+            return Err("XX and NX options at the same time are not compatible".into());
+        }
+
+        let conditions = [options.nx, options.gt, options.lt];
+        if conditions.iter().filter(|&&v| v).count() > 1 {
+            return Err("GT, LT, and/or NX options at the same time are not compatible".into());
+        }
+
+        let args: Vec<Bytes> = args.collect();
+        if args.is_empty() || !args.len().is_multiple_of(2) {
+            return Err("syntax error".into());
+        }
+
+        if incr && args.len() > 2 {
+            return Err("INCR option supports a single increment-element pair".into());
+        }
+
+        let mut pairs = Vec::with_capacity(args.len() / 2);
+        for pair in args.chunks(2) {
+            let score = parse_score(&pair[0]).ok_or("value is not a valid float")?;
+            pairs.push((score, pair[1].clone()));
+        }
+
+        Ok(ZAdd {
+            key,
+            pairs,
+            options,
+            incr,
+        })
+    }
+
+    // 返回新增的成员数量，带CH时还包括分数发生变化的成员
+    // 带INCR时返回成员新的分数，因为条件没有写入时返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        if self.incr {
+            let (increment, member) = self.pairs.into_iter().next().expect("one pair");
+            return write_score(db.zincr_by(&self.key, member, increment, self.options), dst).await;
+        }
+
+        write_len(db.zadd(&self.key, self.pairs, self.options), dst).await
+    }
+}
+
+impl ZRem {
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> ZRem {
+        ZRem {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn members(&self) -> &[Bytes] {
+        &self.members
+    }
+
+    // ZREM key member [member ...]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZRem> {
+        let key = parse.next_string()?;
+
+        let mut members = vec![parse.next_bytes()?];
+        while parse.remaining() > 0 {
+            members.push(parse.next_bytes()?);
+        }
+
+        Ok(ZRem { key, members })
+    }
+
+    // 返回实际删除的成员数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.zrem(&self.key, &self.members), dst).await
+    }
+}
+
+impl ZScore {
+    pub fn new(key: impl ToString, member: Bytes) -> ZScore {
+        ZScore {
+            key: key.to_string(),
+            member,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+
+    // ZSCORE key member
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZScore> {
+        let key = parse.next_string()?;
+        let member = parse.next_bytes()?;
+        Ok(ZScore { key, member })
+    }
+
+    // 返回成员的分数，key或者成员不存在时返回Null
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_score(db.zscore(&self.key, &self.member), dst).await
+    }
+}
+
+impl ZRank {
+    pub fn new(key: impl ToString, member: Bytes) -> ZRank {
+        ZRank {
+            key: key.to_string(),
+            member,
+            withscore: false,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+
+    // ZRANK key member [WITHSCORE]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZRank> {
+        let (key, member, withscore) = parse_rank(parse)?;
+        Ok(ZRank {
+            key,
+            member,
+            withscore,
+        })
+    }
+
+    // 返回成员按照分数从低到高的排名，从0开始
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let rank = db.zrank(&self.key, &self.member, false);
+        write_rank(rank, self.withscore, dst).await
+    }
+}
+
+impl ZRevRank {
+    pub fn new(key: impl ToString, member: Bytes) -> ZRevRank {
+        ZRevRank {
+            key: key.to_string(),
+            member,
+            withscore: false,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+
+    // ZREVRANK key member [WITHSCORE]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZRevRank> {
+        let (key, member, withscore) = parse_rank(parse)?;
+        Ok(ZRevRank {
+            key,
+            member,
+            withscore,
+        })
+    }
+
+    // 返回成员按照分数从高到低的排名，从0开始
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let rank = db.zrank(&self.key, &self.member, true);
+        write_rank(rank, self.withscore, dst).await
+    }
+}
+
+impl ZCard {
+    pub fn new(key: impl ToString) -> ZCard {
+        ZCard {
+            key: key.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // ZCARD key
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZCard> {
+        let key = parse.next_string()?;
+        Ok(ZCard { key })
+    }
+
+    // 返回成员的数量，key不存在时返回0
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.zcard(&self.key), dst).await
+    }
+}
+
+impl ZRange {
+    pub fn new(key: impl ToString, range: Range) -> ZRange {
+        ZRange {
+            key: key.to_string(),
+            range,
+            rev: false,
+            limit: None,
+            withscores: false,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn range(&self) -> &Range {
+        &self.range
+    }
+
+    // ZRANGE key start stop [BYSCORE | BYLEX] [REV] [LIMIT offset count] [WITHSCORES]
+    // 带REV并且按照分数或者字典序时，start是范围的上界，stop是下界
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZRange> {
+        let key = parse.next_string()?;
+        let start = parse.next_bytes()?;
+        let stop = parse.next_bytes()?;
+
+        let (mut byscore, mut bylex, mut rev, mut withscores) = (false, false, false, false);
+        let mut limit = None;
+
+        loop {
+            let option = match parse.next_string() {
+                Ok(option) => option.to_uppercase(),
+                Err(ParseError::EndOfStream) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            match &option[..] {
+                "BYSCORE" if !bylex => byscore = true,
+                "BYLEX" if !byscore => bylex = true,
+                "REV" => rev = true,
+                "WITHSCORES" => withscores = true,
+                "LIMIT" => limit = Some((parse.next_signed()?, parse.next_signed()?)),
+                _ => return Err("syntax error".into()),
+            }
+        }
+
+        if limit.is_some() && !byscore && !bylex {
+            return Err(
+                "syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX"
+                    .into(),
+            );
+        }
+
+        if withscores && bylex {
+            return Err("syntax error, WITHSCORES not supported in combination with BYLEX".into());
+        }
+
+        // 按照排名时REV不交换start和stop，而是表示从分数最高的一端开始计算排名
+        let (min, max) = if rev { (stop, start) } else { (start, stop) };
+        let range = if byscore {
+            Range::Score(parse_score_bound(&min)?, parse_score_bound(&max)?)
+        } else if bylex {
+            Range::Lex(parse_lex_bound(min)?, parse_lex_bound(max)?)
+        } else {
+            const MSG: &str = "value is not an integer or out of range";
+            let (start, stop) = if rev { (max, min) } else { (min, max) };
+            Range::Rank(parse_i64(&start).ok_or(MSG)?, parse_i64(&stop).ok_or(MSG)?)
+        };
+
+        Ok(ZRange {
+            key,
+            range,
+            rev,
+            limit,
+            withscores,
+        })
+    }
+
+    // 返回范围内的成员，带WITHSCORES时同时返回分数
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zrange(&self.key, self.range, self.rev, self.limit) {
+            Ok(members) => {
+                let nested = dst.protocol() == Protocol::Resp3;
+                scored_frame(members, self.withscores, nested)
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+impl ZCount {
+    pub fn new(key: impl ToString, min: ScoreBound, max: ScoreBound) -> ZCount {
+        ZCount {
+            key: key.to_string(),
+            min,
+            max,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn min(&self) -> ScoreBound {
+        self.min
+    }
+
+    pub fn max(&self) -> ScoreBound {
+        self.max
+    }
+
+    // ZCOUNT key min max
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZCount> {
+        let key = parse.next_string()?;
+        let min = parse_score_bound(&parse.next_bytes()?)?;
+        let max = parse_score_bound(&parse.next_bytes()?)?;
+        Ok(ZCount { key, min, max })
+    }
+
+    // 返回分数在[min, max]之间的成员数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        write_len(db.zcount(&self.key, self.min, self.max), dst).await
+    }
+}
+
+impl ZIncrBy {
+    pub fn new(key: impl ToString, increment: f64, member: Bytes) -> ZIncrBy {
+        ZIncrBy {
+            key: key.to_string(),
+            increment,
+            member,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn increment(&self) -> f64 {
+        self.increment
+    }
+
+    pub fn member(&self) -> &Bytes {
+        &self.member
+    }
+
+    // ZINCRBY key increment member
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZIncrBy> {
+        let key = parse.next_string()?;
+        let increment = parse.next_score()?;
+        let member = parse.next_bytes()?;
+        Ok(ZIncrBy {
+            key,
+            increment,
+            member,
+        })
+    }
+
+    // 返回成员新的分数，成员不存在时以increment作为分数添加
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let score = db.zincr_by(
+            &self.key,
+            self.member,
+            self.increment,
+            ZAddOptions::default(),
+        );
+        write_score(score, dst).await
+    }
+}
+
+impl ZPopMin {
+    pub fn new(key: impl ToString, count: Option<usize>) -> ZPopMin {
+        ZPopMin {
+            key: key.to_string(),
+            count,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    // ZPOPMIN key [count]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZPopMin> {
+        let key = parse.next_string()?;
+        let count = parse_count(parse)?;
+        Ok(ZPopMin { key, count })
+    }
+
+    // 删除并返回分数最低的成员以及分数
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_pop(db, dst, &self.key, self.count, false).await
+    }
+}
+
+impl ZPopMax {
+    pub fn new(key: impl ToString, count: Option<usize>) -> ZPopMax {
+        ZPopMax {
+            key: key.to_string(),
+            count,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    // ZPOPMAX key [count]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZPopMax> {
+        let key = parse.next_string()?;
+        let count = parse_count(parse)?;
+        Ok(ZPopMax { key, count })
+    }
+
+    // 删除并返回分数最高的成员以及分数
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        apply_pop(db, dst, &self.key, self.count, true).await
+    }
+}
+
+impl ZUnionStore {
+    pub fn new(destination: impl ToString, keys: Vec<String>) -> ZUnionStore {
+        ZUnionStore {
+            destination: destination.to_string(),
+            weights: vec![1.0; keys.len()],
+            keys,
+            aggregate: Aggregate::default(),
+        }
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn aggregate(&self) -> Aggregate {
+        self.aggregate
+    }
+
+    // ZUNIONSTORE destination numkeys key [key ...] [WEIGHTS weight [weight ...]]
+    //   [AGGREGATE SUM | MIN | MAX]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZUnionStore> {
+        let StoreArgs {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        } = parse_store(parse, "zunionstore")?;
+        Ok(ZUnionStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        })
+    }
+
+    // 把并集写入destination，返回并集中的成员数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let len = db.zset_op_store(
+            SetOp::Union,
+            &self.destination,
+            &self.keys,
+            &self.weights,
+            self.aggregate,
+        );
+        write_len(len, dst).await
+    }
+}
+
+impl ZInterStore {
+    pub fn new(destination: impl ToString, keys: Vec<String>) -> ZInterStore {
+        ZInterStore {
+            destination: destination.to_string(),
+            weights: vec![1.0; keys.len()],
+            keys,
+            aggregate: Aggregate::default(),
+        }
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn aggregate(&self) -> Aggregate {
+        self.aggregate
+    }
+
+    // ZINTERSTORE destination numkeys key [key ...] [WEIGHTS weight [weight ...]]
+    //   [AGGREGATE SUM | MIN | MAX]
+    pub fn parse_frame(parse: &mut Parse) -> crate::Result<ZInterStore> {
+        let StoreArgs {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        } = parse_store(parse, "zinterstore")?;
+        Ok(ZInterStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        })
+    }
+
+    // 把交集写入destination，返回交集中的成员数量
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let len = db.zset_op_store(
+            SetOp::Inter,
+            &self.destination,
+            &self.keys,
+            &self.weights,
+            self.aggregate,
+        );
+        write_len(len, dst).await
+    }
+}
+
+// ZRANK、ZREVRANK的参数相同
+fn parse_rank(parse: &mut Parse) -> crate::Result<(String, Bytes, bool)> {
+    let key = parse.next_string()?;
+    let member = parse.next_bytes()?;
+
+    let withscore = match parse.next_string() {
+        Ok(option) if option.eq_ignore_ascii_case("WITHSCORE") => true,
+        Ok(_) => return Err("syntax error".into()),
+        Err(ParseError::EndOfStream) => false,
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok((key, member, withscore))
+}
+
+// "(1.5"表示不包含1.5，"-inf"、"+inf"表示没有下界、上界
+fn parse_score_bound(src: &[u8]) -> crate::Result<ScoreBound> {
+    let bound = match src.strip_prefix(b"(") {
+        Some(score) => parse_score(score).map(ScoreBound::Exclusive),
+        None => parse_score(src).map(ScoreBound::Inclusive),
+    };
+
+    bound.ok_or_else(|| "min or max is not a float".into())
+}
+
+// "[a"表示包含a，"(a"表示不包含a，"-"、"+"表示没有下界、上界
+fn parse_lex_bound(src: Bytes) -> crate::Result<LexBound> {
+    match src.first() {
+        Some(b'-') if src.len() == 1 => Ok(LexBound::Min),
+        Some(b'+') if src.len() == 1 => Ok(LexBound::Max),
+        Some(b'[') => Ok(LexBound::Inclusive(src.slice(1..))),
+        Some(b'(') => Ok(LexBound::Exclusive(src.slice(1..))),
+        _ => Err("min or max not valid string range item".into()),
+    }
+}
+
+// count可以省略，不能是负数
+fn parse_count(parse: &mut Parse) -> crate::Result<Option<usize>> {
+    if parse.remaining() == 0 {
+        return Ok(None);
+    }
+
+    let count = usize::try_from(parse.next_signed()?)
+        .map_err(|_| "value is out of range, must be positive")?;
+    Ok(Some(count))
+}
+
+// ZUNIONSTORE、ZINTERSTORE的参数相同，command用于错误信息
+fn parse_store(parse: &mut Parse, command: &str) -> crate::Result<StoreArgs> {
+    let destination = parse.next_string()?;
+
+    let numkeys = parse.next_signed()?;
+    if numkeys <= 0 {
+        return Err(format!("at least 1 input key is needed for '{}' command", command).into());
+    }
+    if numkeys as u64 > parse.remaining() as u64 {
+        return Err("syntax error".into());
+    }
+
+    let mut keys = Vec::with_capacity(numkeys as usize);
+    for _ in 0..numkeys {
+        keys.push(parse.next_string()?);
+    }
+
+    let mut weights = vec![1.0; keys.len()];
+    let mut aggregate = Aggregate::default();
+
+    loop {
+        let option = match parse.next_string() {
+            Ok(option) => option.to_uppercase(),
+            Err(ParseError::EndOfStream) => break,
+            Err(err) => return Err(err.into()),
+        };
+
+        match &option[..] {
+            "WEIGHTS" if parse.remaining() >= weights.len() => {
+                for weight in weights.iter_mut() {
+                    *weight =
+                        parse_score(&parse.next_bytes()?).ok_or("weight value is not a float")?;
+                }
+            }
+            "AGGREGATE" => {
+                aggregate = match &parse.next_string()?.to_uppercase()[..] {
+                    "SUM" => Aggregate::Sum,
+                    "MIN" => Aggregate::Min,
+                    "MAX" => Aggregate::Max,
+                    _ => return Err("syntax error".into()),
+                };
+            }
+            _ => return Err("syntax error".into()),
+        }
+    }
+
+    Ok(StoreArgs {
+        destination,
+        keys,
+        weights,
+        aggregate,
+    })
+}
+
+// 不带count时返回 [member, score]，key不存在时返回空数组
+// 带count时RESP3下返回 [[member, score], ...]，RESP2下展开成 [member1, score1, member2, score2...]
+async fn apply_pop(
+    db: &Db,
+    dst: &mut Connection,
+    key: &str,
+    count: Option<usize>,
+    rev: bool,
+) -> crate::Result<()> {
+    let response = match db.zpop(key, count.unwrap_or(1), rev) {
+        Ok(members) => {
+            let nested = count.is_some() && dst.protocol() == Protocol::Resp3;
+            scored_frame(members, true, nested)
+        }
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+// withscores为false时只返回成员，nested为true时每个成员和分数组成一个数组
+fn scored_frame(members: Vec<(Bytes, f64)>, withscores: bool, nested: bool) -> Frame {
+    let mut frames = Vec::with_capacity(members.len() * if withscores { 2 } else { 1 });
+
+    for (member, score) in members {
+        match (withscores, nested) {
+            (false, _) => frames.push(Frame::Bulk(member)),
+            (true, false) => {
+                frames.push(Frame::Bulk(member));
+                frames.push(Frame::Double(score));
+            }
+            (true, true) => frames.push(Frame::Array(vec![
+                Frame::Bulk(member),
+                Frame::Double(score),
+            ])),
+        }
+    }
+
+    Frame::Array(frames)
+}
+
+async fn write_len(len: crate::Result<usize>, dst: &mut Connection) -> crate::Result<()> {
+    let response = match len {
+        Ok(len) => Frame::Integer(len as i64),
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+// RESP3下返回Double，RESP2下返回字符串，没有分数时返回Null
+async fn write_score(score: crate::Result<Option<f64>>, dst: &mut Connection) -> crate::Result<()> {
+    let response = match score {
+        Ok(score) => score.map_or(Frame::Null, Frame::Double),
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
+
+// 带WITHSCORE时返回 [rank, score]，成员不存在时返回Null
+async fn write_rank(
+    rank: crate::Result<Option<(usize, f64)>>,
+    withscore: bool,
+    dst: &mut Connection,
+) -> crate::Result<()> {
+    let response = match rank {
+        Ok(Some((rank, score))) if withscore => {
+            Frame::Array(vec![Frame::Integer(rank as i64), Frame::Double(score)])
+        }
+        Ok(Some((rank, _))) => Frame::Integer(rank as i64),
+        Ok(None) => Frame::Null,
+        Err(err) => Frame::Error(err.to_string()),
+    };
+
+    dst.write_frame(&response).await?;
+
+    Ok(())
+}
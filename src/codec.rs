@@ -0,0 +1,150 @@
+use std::io::{self, Cursor};
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::frame::{self, Frame, Limits, Protocol};
+
+// RESP协议的编解码器，配合tokio_util的Framed可以在任意实现了AsyncRead + AsyncWrite的传输层上
+// 收发Frame，例如TcpStream、UnixStream、内存中的DuplexStream以及TLS流
+//   let mut framed = Framed::new(stream, RespCodec::new());
+//   framed.send(frame).await?;
+//   let response = framed.next().await;
+// Connection内部也使用它来解析和编码frame
+#[derive(Debug, Default, Clone)]
+pub struct RespCodec {
+    // 编码时使用的协议版本
+    protocol: Protocol,
+    // 解码frame时的长度、嵌套层数限制
+    limits: Limits,
+    // 服务端解码的是客户端的请求，和redis一样只有*开头的才是RESP数组，其余的都按内联命令处理；
+    // 客户端解码的是服务器的响应，可以是任意类型的frame
+    server: bool,
+}
+
+impl RespCodec {
+    pub fn new() -> RespCodec {
+        RespCodec::default()
+    }
+
+    pub fn with_limits(limits: Limits) -> RespCodec {
+        RespCodec {
+            protocol: Protocol::default(),
+            limits,
+            server: false,
+        }
+    }
+
+    // 服务端使用的编解码器，用于解码客户端发送的请求
+    pub fn for_server() -> RespCodec {
+        RespCodec {
+            server: true,
+            ..RespCodec::default()
+        }
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    // HELLO命令切换协议版本之后，后续的frame都会按照新的协议编码
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = protocol;
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    // 修改解码frame时的限制，超出限制的frame会返回错误
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    // 解析一行内联命令，例如 SET foo "hello world"\r\n，转换成和RESP请求一样的数组frame
+    // 只有\n结尾(nc等工具)的行也可以正常处理
+    fn decode_inline(&self, src: &mut BytesMut) -> crate::Result<Option<Frame>> {
+        let max = self.limits.max_inline_len;
+
+        let end = match src.iter().position(|&b| b == b'\n') {
+            Some(end) if end <= max => end,
+            // 一直没有收到换行符的话，缓冲区会无限增长，超出限制后直接返回错误
+            Some(_) => return Err("protocol error;too big inline request".into()),
+            None if src.len() > max => return Err("protocol error;too big inline request".into()),
+            None => return Ok(None),
+        };
+
+        let line = src.split_to(end + 1);
+        let args = frame::split_args(&line)?;
+
+        Ok(Some(Frame::Array(
+            args.into_iter().map(Frame::Bulk).collect(),
+        )))
+    }
+}
+
+impl Decoder for RespCodec {
+    type Item = Frame;
+    type Error = crate::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> crate::Result<Option<Frame>> {
+        use frame::Error::Incomplete;
+
+        // 第一个字节不是RESP的类型前缀时，说明是telnet等客户端直接输入的内联命令
+        skip_empty_lines(src);
+        match src.first() {
+            None => return Ok(None),
+            Some(&b'*') => {}
+            Some(_) if self.server => return self.decode_inline(src),
+            Some(&b) if !frame::is_type_byte(b) => return self.decode_inline(src),
+            Some(_) => {}
+        }
+
+        // Cursor 用于记录现在读取到buffer中的哪个位置
+        let mut buf = Cursor::new(&src[..]);
+
+        match Frame::check(&mut buf, &self.limits) {
+            Ok(_) => {
+                let len = buf.position() as usize;
+
+                // 把完整的frame从缓冲区中切分出来，解析出的Bulk字符串直接引用这块内存，不需要拷贝
+                let data = src.split_to(len).freeze();
+
+                let frame = Frame::parse(&mut Cursor::new(data))?;
+
+                Ok(Some(frame))
+            }
+            Err(Incomplete) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl Encoder<Frame> for RespCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Frame, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode(&item, dst)
+    }
+}
+
+impl Encoder<&Frame> for RespCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &Frame, dst: &mut BytesMut) -> io::Result<()> {
+        item.encode(dst, self.protocol);
+        Ok(())
+    }
+}
+
+// 内联命令之间的空行(包括只有空格的行)会被忽略
+fn skip_empty_lines(src: &mut BytesMut) {
+    loop {
+        let blank = src.iter().position(|&b| !matches!(b, b' ' | b'\t' | b'\r'));
+
+        match blank {
+            Some(i) if src[i] == b'\n' => src.advance(i + 1),
+            _ => return,
+        }
+    }
+}
@@ -1,130 +1,136 @@
-use std::io::{self, Cursor, Write};
+use std::{
+    future::{self, poll_fn},
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio_util::{
+    codec::{Decoder, Encoder},
+    io::poll_read_buf,
+};
 
-use bytes::{Buf, BytesMut};
-use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt, BufWriter},
-    net::TcpStream,
+use crate::{
+    codec::RespCodec,
+    frame::{Frame, Limits, Protocol},
 };
 
-use crate::frame::{self, Frame};
+// 阻塞的命令等待期间，最多读取这么多客户端提前发送的数据到缓冲区中
+const CLOSED_READ_LIMIT: usize = 64 * 1024;
+
+// 连接底层的传输层，任何实现了AsyncRead + AsyncWrite的类型都可以，例如TcpStream、UnixStream、
+// 内存中的DuplexStream以及TLS流
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Transport for T {}
 
-// Connetction用于将Tcp流中的数据按照redis的协议，读取和写入为完整的Frame
-pub struct Connection {
-    stream: BufWriter<TcpStream>,
+// Connetction用于将流中的数据按照redis的协议，读取和写入为完整的Frame
+// 默认的传输层是Box<dyn Transport>，这样服务器和客户端可以用同一种Connection处理不同的传输层
+pub struct Connection<S = Box<dyn Transport>> {
+    stream: BufWriter<S>,
     buffer: BytesMut,
+    // 编码响应时使用的缓冲区，避免每次写入都重新分配内存
+    write_buf: BytesMut,
+    // 负责frame的解码和编码，同时记录了协议版本和解码时的限制
+    codec: RespCodec,
 }
 
-impl Connection {
-    pub fn new(stream: TcpStream) -> Self {
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    pub fn new(stream: S) -> Self {
         Connection {
             stream: BufWriter::new(stream),
             buffer: BytesMut::with_capacity(1024 * 4),
+            write_buf: BytesMut::with_capacity(1024),
+            codec: RespCodec::new(),
+        }
+    }
+
+    // 服务端的连接，客户端发送的请求中不是*开头的都按内联命令解析
+    pub fn for_server(stream: S) -> Self {
+        Connection {
+            codec: RespCodec::for_server(),
+            ..Connection::new(stream)
         }
     }
 
     pub async fn read_frame(&mut self) -> crate::Result<Option<Frame>> {
-        //
+        poll_fn(|cx| self.poll_read_frame(cx)).await
+    }
+
+    // read_frame的poll版本，用于在手写的Future、Stream中读取frame，例如Subscriber的Stream实现
+    pub fn poll_read_frame(&mut self, cx: &mut Context<'_>) -> Poll<crate::Result<Option<Frame>>> {
         loop {
             if let Some(frame) = self.parse_frame()? {
-                return Ok(Some(frame));
+                return Poll::Ready(Ok(Some(frame)));
             }
 
             // 如果没有读取出frame，说明可能是buffer缓冲区数据不足 尝试从stream中读更多的数据到缓冲区内
-            if 0 == self.stream.read_buf(&mut self.buffer).await? {
+            if 0 == ready!(poll_read_buf(
+                Pin::new(&mut self.stream),
+                cx,
+                &mut self.buffer
+            ))? {
                 if self.buffer.is_empty() {
-                    return Ok(None);
+                    return Poll::Ready(Ok(None));
                 } else {
-                    return Err("connection per by reset".into());
+                    return Poll::Ready(Err("connection per by reset".into()));
                 }
             }
         }
     }
 
-    fn parse_frame(&mut self) -> crate::Result<Option<Frame>> {
-        use frame::Error::Incomplete;
-        // Cursor 用于记录现在读取到buffer中的哪个位置
-        let mut buf = Cursor::new(&self.buffer[..]);
-
-        match Frame::check(&mut buf) {
-            Ok(_) => {
-                let len = buf.position() as usize;
-
-                buf.set_position(0);
-
-                let frame = Frame::parse(&mut buf)?;
-
-                self.buffer.advance(len);
+    // 只从缓冲区中解析frame，不会去stream中读取新的数据
+    // 客户端使用pipeline一次发送多个命令时，这些命令往往已经全部在缓冲区中了，
+    // 可以借此一次性处理完，然后统一flush响应
+    pub fn read_buffered_frame(&mut self) -> crate::Result<Option<Frame>> {
+        self.parse_frame()
+    }
 
-                return Ok(Some(frame));
-            }
-            Err(Incomplete) => {
-                return Ok(None);
-            }
-            Err(e) => {
-                return Err(e.into());
+    // 等待对端关闭连接，阻塞的命令(例如BLPOP)用它来发现客户端已经断开
+    // 期间收到的数据会留在缓冲区中，等命令结束之后再解析。缓冲区中的数据达到CLOSED_READ_LIMIT之后
+    // 不再读取，剩下的数据留在socket中，此时不能再发现对端关闭，只能等命令超时或者被唤醒
+    pub async fn closed(&mut self) -> io::Result<()> {
+        while self.buffer.len() < CLOSED_READ_LIMIT {
+            if 0 == self.stream.read_buf(&mut self.buffer).await? {
+                return Ok(());
             }
         }
+        future::pending().await
     }
 
-    async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
-        match frame {
-            Frame::Array(v) => {
-                let len = v.len();
-
-                self.stream.write_u8(b'*').await?;
-                self.write_decimal(len as u64).await?;
+    fn parse_frame(&mut self) -> crate::Result<Option<Frame>> {
+        self.codec.decode(&mut self.buffer)
+    }
 
-                for item in v {
-                    self.write_value(item).await?;
-                }
-            }
-            _ => {
-                self.write_value(frame).await?;
-            }
-        }
+    // 修改解码frame时的限制，超出限制的frame会返回错误
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.codec.set_limits(limits);
+    }
 
-        self.stream.flush().await
+    // 当前连接使用的协议版本
+    pub fn protocol(&self) -> Protocol {
+        self.codec.protocol()
     }
 
-    async fn write_value(&mut self, frame: &Frame) -> io::Result<()> {
-        match frame {
-            Frame::Simple(v) => {
-                self.stream.write_u8(b'+').await?;
-                self.stream.write_all(v.as_bytes()).await?;
-                self.stream.write_all(b"\r\n").await?;
-            }
-            Frame::Error(v) => {
-                self.stream.write_u8(b'-').await?;
-                self.stream.write_all(v.as_bytes()).await?;
-                self.stream.write_all(b"\r\n").await?;
-            }
-            Frame::Integer(v) => {
-                self.stream.write_u8(b':').await?;
-                self.write_decimal(*v).await?;
-            }
-            Frame::Bulk(v) => {
-                self.stream.write_u8(b'$').await?;
-                self.write_decimal(v.len() as u64).await?;
-                self.stream.write_all(v).await?;
-                self.stream.write_all(b"\r\n").await?;
-            }
-            Frame::Array(_) => unreachable!(),
-            _ => {
-                unimplemented!()
-            }
-        }
-        Ok(())
+    // HELLO命令切换协议版本之后，后续的响应都会按照新的协议编码
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.codec.set_protocol(protocol);
     }
 
-    async fn write_decimal(&mut self, v: u64) -> io::Result<()> {
-        // 写入一个数字到stream中
-        let mut buf = [0u8; 20];
-        let mut buf = Cursor::new(&mut buf[..]);
+    // 将一个frame写入stream的缓冲区中，此时数据并不一定已经发送给对端
+    // 调用方需要在写完一批frame之后调用flush，这样多个响应只需要一次系统调用
+    pub async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        // 先按照当前的协议编码到write_buf中，再整体写入stream
+        self.write_buf.clear();
+        self.codec.encode(frame, &mut self.write_buf)?;
 
-        write!(&mut buf, "{}", v)?;
+        self.stream.write_all(&self.write_buf).await
+    }
 
-        self.stream.write_all(&buf.get_ref()[..]).await?;
-        self.stream.write_all(b"\r\n").await?;
-        Ok(())
+    // 将缓冲区中的数据全部写入到socket中
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush().await
     }
 }
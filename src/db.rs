@@ -1,12 +1,251 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fmt,
+    str::FromStr,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use bytes::Bytes;
-use tokio::sync::{broadcast, Notify};
-use tokio::time::{sleep_until, Instant};
+use bytes::{Bytes, BytesMut};
+use tokio::sync::{broadcast, oneshot, Notify};
+use tokio::time::{sleep, sleep_until, Instant};
+use tracing::debug;
+
+use crate::parse::{parse_f64, parse_i64};
+
+use self::{
+    dict::Dict,
+    glob::glob_match,
+    hash::Hash,
+    set::Set,
+    stream::Stream,
+    zset::{ZAddResult, ZSet},
+};
+
+pub use self::stream::{
+    AutoClaimOptions, AutoClaimed, PendingEntry, PendingRange, PendingSummary, StreamEntry,
+    StreamId, XAddId, XClaimOptions, XReadGroupOptions,
+};
+
+mod dict;
+mod dump;
+mod glob;
+mod hash;
+mod set;
+mod stream;
+mod zset;
+
+// 和redis的proto-max-bulk-len一致，字符串最大512MB
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+// UNLINK删除的值总大小超过这个阈值时，才会交给后台线程释放内存
+const LAZYFREE_THRESHOLD: usize = 64 * 1024;
+
+// 抽样策略下，后台任务每隔ACTIVE_EXPIRE_INTERVAL运行一次，每次抽查ACTIVE_EXPIRE_SAMPLES个key
+// 过期的比例超过25%时继续抽查，但是一次最多运行ACTIVE_EXPIRE_CYCLE_TIME，和redis的默认配置相同
+const ACTIVE_EXPIRE_INTERVAL: Duration = Duration::from_millis(100);
+const ACTIVE_EXPIRE_SAMPLES: usize = 20;
+const ACTIVE_EXPIRE_CYCLE_TIME: Duration = Duration::from_millis(25);
+
+// 和redis一样，不超过这个长度的字符串的编码是embstr
+const EMBSTR_SIZE_LIMIT: usize = 44;
+// 和redis的list-max-listpack-size默认值-2一样，元素的总长度不超过8KB时列表的编码是listpack
+const LIST_LISTPACK_SIZE: usize = 8 * 1024;
+
+// 和redis的LFU一样，新建的key的计数器初始值，避免刚写入的key马上就被视为访问频率最低
+const LFU_INIT_VAL: u8 = 5;
+// 计数器越大增长得越慢，和redis的lfu-log-factor默认值相同
+const LFU_LOG_FACTOR: f64 = 10.0;
+// 每经过这么长时间没有访问，计数器减1，和redis的lfu-decay-time默认值相同
+const LFU_DECAY_TIME: Duration = Duration::from_secs(60);
+
+const NOT_INTEGER: &str = "ERR value is not an integer or out of range";
+const TOO_LARGE: &str = "ERR string exceeds maximum allowed size (proto-max-bulk-len)";
+const WRONGTYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+const XGROUP_NO_KEY: &str = "ERR The XGROUP subcommand requires the key to exist. \
+    Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.";
+
+// SET命令的写入条件
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SetCondition {
+    #[default]
+    Always,
+    // NX
+    NotExists,
+    // XX
+    Exists,
+}
+
+// 写入key时对过期时间的处理
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Expiry {
+    // 不会过期，原来的过期时间会被清除，对应SET的默认行为以及GETEX的PERSIST
+    #[default]
+    Persist,
+    // 保留原来的过期时间，对应SET的KEEPTTL以及不带参数的GETEX
+    Keep,
+    // 一段时间之后过期，EX/PX
+    After(Duration),
+    // 在指定的unix时间过期，EXAT/PXAT
+    At(SystemTime),
+}
+
+// 列表的两端，LPUSH/RPUSH、LPOP/RPOP分别操作列表的头部和尾部
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListEnd {
+    Left,
+    Right,
+}
+
+// BLPOP、BRPOP、BLMOVE的结果，列表中已经有元素时立即弹出，否则需要等待
+#[derive(Debug)]
+pub enum BlockingPop {
+    // 弹出元素的key以及弹出的元素
+    Popped(String, Bytes),
+    Blocked(ListWaiter),
+}
+
+// 阻塞在列表上等待元素的客户端，列表中写入元素之后按照阻塞的先后顺序交给等待者
+// drop时会从Db的等待队列中移除，已经交给自己但是没有取走的元素会被放回列表
+#[derive(Debug)]
+pub struct ListWaiter {
+    shared: Arc<Shared>,
+    id: u64,
+    rx: oneshot::Receiver<(String, Bytes)>,
+    // 元素是从列表的哪一端弹出的，放回时也放回这一端
+    end: ListEnd,
+    // BLMOVE的元素已经写入了目标列表，不需要放回
+    moved: bool,
+}
+
+// XREAD、XREADGROUP的结果，stream中已经有新条目时立即返回，否则需要等待
+#[derive(Debug)]
+pub enum StreamRead {
+    // 每个有新条目的key以及读取到的条目，和keys的顺序一致
+    Entries(Vec<(String, Vec<StreamEntry>)>),
+    Blocked(StreamWaiter),
+}
+
+// 阻塞在stream上等待新条目的客户端，和ListWaiter不同，stream的条目不会被取走，
+// 所以只需要通知等待者有新条目写入了，由等待者重新读取
+// drop时会从Db的等待队列中移除
+#[derive(Debug)]
+pub struct StreamWaiter {
+    shared: Arc<Shared>,
+    id: u64,
+    rx: oneshot::Receiver<()>,
+}
+
+// 集合类型使用紧凑编码的阈值，和redis中同名配置的含义相同
+#[derive(Debug, Clone, Copy)]
+pub struct EncodingConfig {
+    // hash的field数量超过这个值之后从listpack转换成哈希表
+    pub hash_max_listpack_entries: usize,
+    // hash中任意一个field或者value的长度超过这个值之后从listpack转换成哈希表
+    pub hash_max_listpack_value: usize,
+    // 只包含整数的set的成员数量超过这个值之后从intset转换成哈希表
+    pub set_max_intset_entries: usize,
+}
+
+// SINTER、SUNION、SDIFF以及对应的STORE命令所做的集合运算，ZUNIONSTORE、ZINTERSTORE也使用它
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+// ZADD的条件，NX和XX不能同时使用，GT、LT、NX三者也只能使用一个
+// NX: 只添加新的成员，不更新已经存在的成员
+// XX: 只更新已经存在的成员，不添加新的成员
+// GT: 新的分数大于原来的分数时才更新，不影响添加新的成员
+// LT: 新的分数小于原来的分数时才更新，不影响添加新的成员
+// CH: 返回值除了新增的成员之外，还包括分数发生变化的成员
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ZAddOptions {
+    pub nx: bool,
+    pub xx: bool,
+    pub gt: bool,
+    pub lt: bool,
+    pub ch: bool,
+}
+
+// 分数范围的一端，-inf、+inf用Inclusive表示
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScoreBound {
+    Inclusive(f64),
+    Exclusive(f64),
+}
+
+// 字典序范围的一端，Min和Max分别对应"-"和"+"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexBound {
+    Min,
+    Max,
+    Inclusive(Bytes),
+    Exclusive(Bytes),
+}
+
+// ZRANGE的范围，按照排名、分数或者字典序
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZRange {
+    Rank(i64, i64),
+    Score(ScoreBound, ScoreBound),
+    Lex(LexBound, LexBound),
+}
+
+// ZUNIONSTORE、ZINTERSTORE合并同一个成员在多个输入中的分数的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Min,
+    Max,
+}
+
+// EXPIRE命令的条件，XX可以和GT或者LT同时使用
+// NX: key没有过期时间时才设置
+// XX: key已经有过期时间时才设置
+// GT: 新的过期时间晚于原来的过期时间时才设置
+// LT: 新的过期时间早于原来的过期时间时才设置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExpireCondition {
+    pub nx: bool,
+    pub xx: bool,
+    pub gt: bool,
+    pub lt: bool,
+}
+
+// 后台任务主动清除过期key的策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExpireStrategy {
+    // 所有带过期时间的key按照过期时间排序保存在BTreeSet中，后台任务在最早的过期时间醒来，
+    // 过期的key会被准时删除，但是每次设置过期时间都需要拷贝key并维护有序集合
+    #[default]
+    Ordered,
+    // 和redis的activeExpireCycle一样，周期性地随机抽查一部分带过期时间的key并删除其中过期的，
+    // 只需要在数组中记录key，设置过期时间的开销更小，代价是过期key占用的内存回收得没有那么及时
+    Sampled,
+}
+
+// RESTORE命令的可选参数，idle_time和freq用于恢复OBJECT IDLETIME、OBJECT FREQ的值
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RestoreOptions {
+    pub expire: Expiry,
+    // key已经存在时覆盖，否则返回BUSYKEY错误
+    pub replace: bool,
+    pub idle_time: Option<Duration>,
+    pub freq: Option<u8>,
+}
+
+// SET命令的可选参数
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SetOptions {
+    pub condition: SetCondition,
+    pub expire: Expiry,
+    // 是否需要返回key原来的值，原来的值不是字符串时返回WRONGTYPE错误并且不会写入
+    pub get: bool,
+}
 
 pub struct DbDropGuard {
     pub db: Db,
@@ -34,13 +273,33 @@ struct Shared {
 #[derive(Debug)]
 struct State {
     // key-value的数据结构，hashmap，管理用户设置的redis数据
-    entries: HashMap<String, Entry>,
+    entries: Dict<String, Entry>,
     // 管理通知者和订阅者
     pub_sub: HashMap<String, broadcast::Sender<Bytes>>,
     // 用于存储每个key值的time to live
     // background_task 会去遍历BTreeSet，找到过期的值
     // 有可能同一个时间会被创建多个过期时间，因此还需要通过一个唯一的key值来处理
+    // 只有ExpireStrategy::Ordered会使用
     expirations: BTreeSet<(Instant, String)>,
+    // ExpireStrategy::Sampled下所有带过期时间的key，用于随机抽样
+    // 每个key在数组中的下标记录在Entry::volatile_slot中，删除时可以用swap_remove在O(1)内完成
+    volatile: Vec<String>,
+    // 后台任务使用的清除策略，创建Db之后不会再改变
+    strategy: ExpireStrategy,
+    // 集合类型的编码阈值，创建Db之后不会再改变
+    encoding: EncodingConfig,
+    // 阻塞在列表上的等待者，key是等待者的id
+    waiters: HashMap<u64, Waiter>,
+    // 每个key上按照阻塞的先后顺序排列的等待者id
+    blocked: HashMap<String, VecDeque<u64>>,
+    // 阻塞在stream上的等待者，key是等待者的id，和列表的等待者共用next_waiter_id
+    stream_waiters: HashMap<u64, StreamReader>,
+    // 每个key上等待新条目的等待者id，新条目写入之后全部唤醒，所以不需要排序
+    stream_blocked: HashMap<String, Vec<u64>>,
+    // 下一个等待者的id
+    next_waiter_id: u64,
+    // SPOP、SRANDMEMBER随机选择成员时使用
+    rng: XorShift,
     // 当Db被drop的时候，shutdown的值会变成true，用于通知background_task退出
     shutdowm: bool,
 }
@@ -48,14 +307,74 @@ struct State {
 #[derive(Debug)]
 struct Entry {
     // 存储的值
-    data: Bytes,
+    value: Value,
     // 设置的过期时间
     expires_at: Option<Instant>,
+    // ExpireStrategy::Sampled下key在State::volatile中的下标，只在expires_at不为None时有意义
+    volatile_slot: usize,
+    // 最后一次访问的时间，OBJECT IDLETIME使用
+    accessed_at: Instant,
+    // 和redis的LFU一样的对数计数器，OBJECT FREQ使用
+    freq: u8,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    // 等待的所有key，被唤醒或者取消时需要从每个key的等待队列中移除
+    keys: Vec<String>,
+    // 从列表的哪一端弹出
+    end: ListEnd,
+    // BLMOVE弹出元素之后写入的列表以及写入的位置
+    destination: Option<(String, ListEnd)>,
+    // 弹出的key和元素通过oneshot交给等待者
+    tx: oneshot::Sender<(String, Bytes)>,
+}
+
+#[derive(Debug)]
+struct StreamReader {
+    // 等待的所有key，被唤醒或者取消时需要从每个key的等待列表中移除
+    keys: Vec<String>,
+    tx: oneshot::Sender<()>,
+}
+
+// key对应的值，每种类型只支持自己的命令，类型不匹配时返回WRONGTYPE错误
+#[derive(Debug, Clone)]
+enum Value {
+    String(Bytes),
+    List(VecDeque<Bytes>),
+    Hash(Hash),
+    Set(Set),
+    ZSet(ZSet),
+    Stream(Stream),
+}
+
+// ZUNIONSTORE、ZINTERSTORE的输入可以是有序集合，也可以是set，set中成员的分数视为1
+enum ZInput<'a> {
+    Set(&'a Set),
+    ZSet(&'a ZSet),
+}
+
+impl Default for DbDropGuard {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DbDropGuard {
     pub fn new() -> Self {
-        DbDropGuard { db: Db::new() }
+        Self::with_strategy(ExpireStrategy::default())
+    }
+
+    // 使用指定的策略主动清除过期的key
+    pub fn with_strategy(strategy: ExpireStrategy) -> Self {
+        Self::with_config(strategy, EncodingConfig::default())
+    }
+
+    // 同时指定过期key的清除策略以及集合类型的编码阈值
+    pub fn with_config(strategy: ExpireStrategy, encoding: EncodingConfig) -> Self {
+        DbDropGuard {
+            db: Db::new(strategy, encoding),
+        }
     }
 
     pub fn db(&self) -> Db {
@@ -70,178 +389,2891 @@ impl Drop for DbDropGuard {
 }
 
 impl Db {
-    fn new() -> Db {
+    fn new(strategy: ExpireStrategy, encoding: EncodingConfig) -> Db {
         let shared: Arc<Shared> = Arc::new(Shared {
             state: Mutex::new(State {
-                entries: HashMap::new(),
+                entries: Dict::new(),
                 pub_sub: HashMap::new(),
                 expirations: BTreeSet::new(),
+                volatile: Vec::new(),
+                strategy,
+                encoding,
+                waiters: HashMap::new(),
+                blocked: HashMap::new(),
+                stream_waiters: HashMap::new(),
+                stream_blocked: HashMap::new(),
+                next_waiter_id: 0,
+                rng: XorShift::new(),
                 shutdowm: false,
             }),
             bacground_task: Notify::new(),
         });
 
         // 开启后台清楚过期的background_task
-        tokio::spawn(purge_expired_tasks(Arc::clone(&shared)));
+        match strategy {
+            ExpireStrategy::Ordered => {
+                tokio::spawn(purge_expired_tasks(Arc::clone(&shared)));
+            }
+            ExpireStrategy::Sampled => {
+                tokio::spawn(active_expire_tasks(Arc::clone(&shared)));
+            }
+        }
 
         Db { shared }
     }
 
-    fn get(&self, key: &str) -> Option<Bytes> {
+    // key的数量，和redis的DBSIZE一样，已经过期但还没有被删除的key也会被计算在内
+    pub fn dbsize(&self) -> usize {
+        self.shared.state.lock().unwrap().entries.len()
+    }
+
+    // 所有匹配glob模式的key，已经过期的key不会返回
+    pub fn keys(&self, pattern: &[u8]) -> Vec<String> {
         let state = self.shared.state.lock().unwrap();
-        state.entries.get(key).map(|item| item.data.clone())
+        let now = Instant::now();
+
+        state
+            .entries
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .filter(|(key, _)| glob_match(pattern, key.as_bytes()))
+            .map(|(key, _)| key.clone())
+            .collect()
     }
 
-    fn set(&self, key: String, value: Bytes, expire: Option<Duration>) {
-        //通过Mutex获取state
+    // 从cursor开始遍历所有的key，返回下一次的游标以及这一次遍历到的key，游标为0表示遍历结束
+    // 游标在两次调用之间即使发生扩容、缩容也依然有效，遍历期间一直存在的key至少会被返回一次
+    // pattern、key_type不为None时只返回匹配glob模式、类型相同的key，遍历到的过期key会被删除
+    pub fn scan(
+        &self,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        key_type: Option<&str>,
+        count: usize,
+    ) -> (u64, Vec<String>) {
         let mut state = self.shared.state.lock().unwrap();
+        let now = Instant::now();
 
-        // 任务是否需要被通知 是在set的过程中计算出来的
-
-        let mut notify: bool = false;
+        let mut keys = vec![];
+        let mut expired = vec![];
+        let cursor = state.entries.scan(cursor, count, |key, entry| {
+            if entry.is_expired(now) {
+                expired.push(key.clone());
+            } else if pattern.is_none_or(|pattern| glob_match(pattern, key.as_bytes()))
+                && key_type.is_none_or(|key_type| key_type == entry.value.type_name())
+            {
+                keys.push(key.clone());
+            }
+        });
 
-        // 过期时间为调用设置时的时间 加上用户设置的duration
-        let expires_at: Option<Instant> = expire.map(|duration| {
-            let when = Instant::now() + duration;
+        for key in expired {
+            state.expire_if_needed(&key);
+        }
 
-            // 只有当下一个过期时间大于当前时间，才需要通知background_task去清楚
-            notify = state
-                .next_expiration()
-                .map(|expiration| expiration > when)
-                .unwrap_or(true);
+        (cursor, keys)
+    }
 
-            when
-        });
+    // key对应的值的类型，和TYPE命令的返回值相同，key不存在时返回None
+    pub fn key_type(&self, key: &str) -> Option<&'static str> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.get(key).map(|entry| entry.value.type_name())
+    }
 
-        // HaspMap如果insert的key之前有值，会更新这个key对应的value，并将之前的value返回
-        let prev = state.entries.insert(
-            key.clone(),
-            Entry {
-                data: value,
-                expires_at,
-            },
-        );
+    // 随机返回一个key，选中的key已经过期时删除之后重新选择，没有key时返回None
+    pub fn random_key(&self) -> Option<String> {
+        let mut state = self.shared.state.lock().unwrap();
+        let state = &mut *state;
 
-        if let Some(prev) = prev {
-            // 如果之前hashMap存储的值有expirea_at的话，需要将expirations内对应的值也清除掉
-            if let Some(when) = prev.expires_at {
-                state.expirations.remove(&(when, key.clone()));
+        loop {
+            let (key, entry) = state.entries.random(&mut state.rng)?;
+            if !entry.is_expired(Instant::now()) {
+                return Some(key.clone());
             }
+
+            // 每次循环都会删除一个key，最终一定会结束
+            let key = key.clone();
+            state.expire_if_needed(&key);
         }
+    }
 
-        // 如果set的时候传递了过期时间的话，需要在expireation的BTreeSet中设置相应的key和过期时间
-        if let Some(when) = expires_at {
-            state.expirations.insert((when, key.clone()));
+    // RENAME、RENAMENX，值和过期时间一起转移到newkey，newkey原来的值会被覆盖
+    // key不存在时返回错误，nx为true并且newkey已经存在时不做修改，返回false
+    pub fn rename(&self, key: &str, newkey: &str, nx: bool) -> crate::Result<bool> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        // 不需要移动的时候只检查key是否存在
+        if key == newkey || (nx && state.contains_key(newkey)) {
+            if !state.contains_key(key) {
+                return Err("ERR no such key".into());
+            }
+            return Ok(!nx);
         }
 
-        // 在设置完haspMap以及Btreeset之后将互斥锁释放掉
+        // 刚好过期的key也会被remove删除并返回None，同样视为不存在
+        let entry = state.remove(key).ok_or("ERR no such key")?;
+        let notify = state.insert_entry(newkey.to_string(), entry);
+        state.signal_key_ready(newkey);
+
         drop(state);
 
         if notify {
-            // 如果需要notify，即过期时间已经大于现在的时间，就通知后台线程去清理过期的key
-            self.shared.bacground_task.notify_one()
+            self.shared.bacground_task.notify_one();
         }
+
+        Ok(true)
     }
 
-    // 订阅一个值，返回一个boardcast的receiver，通过这个recivier可以获取到这个值变化
-    fn subscribe(&self, key: String) -> broadcast::Receiver<Bytes> {
-        use std::collections::hash_map::Entry;
-        // todo
+    // 把source的值和过期时间复制到destination，source不存在，或者destination已经存在并且replace为false时返回false
+    pub fn copy(&self, source: &str, destination: &str, replace: bool) -> crate::Result<bool> {
+        if source == destination {
+            return Err("ERR source and destination objects are the same".into());
+        }
+
         let mut state = self.shared.state.lock().unwrap();
-        match state.pub_sub.entry(key) {
-            Entry::Occupied(v) => v.get().subscribe(),
-            Entry::Vacant(v) => {
-                // 如果没有需要新建一个boardcast
-                let (tx, rx) = broadcast::channel(1024);
-                v.insert(tx);
-                rx
+
+        // 复制出来的是一个新的key，访问时间和访问频率从初始值开始计算
+        let entry = match state.get(source) {
+            Some(source) => {
+                let mut entry = Entry::new(source.value.clone());
+                entry.expires_at = source.expires_at;
+                entry
             }
+            None => return Ok(false),
+        };
+
+        if !replace && state.contains_key(destination) {
+            return Ok(false);
+        }
+
+        let notify = state.insert_entry(destination.to_string(), entry);
+        state.signal_key_ready(destination);
+
+        drop(state);
+
+        if notify {
+            self.shared.bacground_task.notify_one();
         }
+
+        Ok(true)
     }
 
-    // 发布消息 ，让所有订阅者进行接收，哪些值改动了
-    // 返回订阅者的数量
-    fn publish(&self, key: &str, value: Bytes) -> usize {
-        let state = self.shared.state.lock().unwrap();
-        state
-            .pub_sub
-            .get(key)
-            .map(|tx| tx.send(value).unwrap_or(0))
-            .unwrap_or(0)
+    // 将key的值序列化，key不存在时返回None，结果只能由RESTORE使用
+    pub fn dump(&self, key: &str) -> Option<Bytes> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.get(key).map(|entry| dump::dump(&entry.value))
     }
 
-    // 当db被drop时，需要通知后台清除所有的key
-    fn shut_down_purge(&self) {
+    // 用DUMP的结果重新创建key，过期时间已经过去时不会创建，带REPLACE时原来的key也会被删除
+    pub fn restore(&self, key: &str, payload: &[u8], options: RestoreOptions) -> crate::Result<()> {
         let mut state = self.shared.state.lock().unwrap();
 
-        state.shutdowm = true;
+        if !options.replace && state.contains_key(key) {
+            return Err("BUSYKEY Target key name already exists.".into());
+        }
+
+        let value = dump::restore(payload, &state.encoding)?;
+
+        let now = Instant::now();
+        let expires_at = options.expire.deadline(now);
+        if expires_at.is_some_and(|when| when <= now) {
+            state.remove(key);
+            return Ok(());
+        }
+
+        let mut entry = Entry::new(value);
+        entry.expires_at = expires_at;
+        if let Some(idle_time) = options.idle_time {
+            entry.accessed_at = now.checked_sub(idle_time).unwrap_or(now);
+        }
+        if let Some(freq) = options.freq {
+            entry.freq = freq;
+        }
+
+        let notify = state.insert_entry(key.to_string(), entry);
+        state.signal_key_ready(key);
 
         drop(state);
 
-        self.shared.bacground_task.notify_one();
+        if notify {
+            self.shared.bacground_task.notify_one();
+        }
+
+        Ok(())
     }
-}
 
-impl Shared {
-    // 清除所有过期的key
-    fn purge_expired_keys(&self) -> Option<Instant> {
-        let mut state = self.state.lock().unwrap();
+    // OBJECT ENCODING，key不存在时返回None，和OBJECT的其他子命令一样不会更新访问时间
+    pub fn object_encoding(&self, key: &str) -> Option<&'static str> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.peek(key).map(|entry| entry.value.encoding())
+    }
 
-        // 如果shutdown为true，证明db已经关闭
-        if state.shutdowm {
-            return None;
+    // OBJECT IDLETIME，距离最后一次访问的时间
+    pub fn object_idle_time(&self, key: &str) -> Option<Duration> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.peek(key).map(|entry| entry.idle_time(Instant::now()))
+    }
+
+    // OBJECT FREQ，衰减之后的访问频率计数器
+    pub fn object_freq(&self, key: &str) -> Option<u8> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.peek(key).map(|entry| entry.freq(Instant::now()))
+    }
+
+    // key不存在时返回None，key对应的值不是字符串时返回WRONGTYPE错误
+    pub fn get(&self, key: &str) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_string(key)?.cloned())
+    }
+
+    // 按照options中的条件写入key-value，条件的判断和写入在同一次加锁中完成
+    // 返回是否写入成功以及key原来的值，只有options.get为true时才会返回原来的值
+    // 写入时不关心原来的值是什么类型，都会被字符串覆盖
+    pub fn set(
+        &self,
+        key: String,
+        value: Bytes,
+        options: SetOptions,
+    ) -> crate::Result<(bool, Option<Bytes>)> {
+        //通过Mutex获取state
+        let mut state = self.shared.state.lock().unwrap();
+
+        let prev = state.get(&key);
+        let exists = prev.is_some();
+        let prev_expires_at = prev.and_then(|entry| entry.expires_at);
+        let previous = match prev {
+            Some(entry) if options.get => Some(entry.value.as_string()?.clone()),
+            _ => None,
+        };
+
+        // NX只在key不存在时写入，XX只在key存在时写入
+        let allowed = match options.condition {
+            SetCondition::Always => true,
+            SetCondition::NotExists => !exists,
+            SetCondition::Exists => exists,
+        };
+        if !allowed {
+            return Ok((false, previous));
         }
 
         let now = Instant::now();
+        let expires_at = match options.expire {
+            Expiry::Keep => prev_expires_at,
+            expire => expire.deadline(now),
+        };
 
-        // lock（）返回的是MutexGuard并不是对state的可变引用，但我们后续的while中需要使用state的可变引用
-        // 使用*操作符，解引用出互斥锁内的数据，然后通过&mut创建其内部数据的可变引用
-        let state = &mut *state;
+        // EXAT/PXAT指定的时间已经过去的话，相当于写入之后立即过期
+        if matches!(expires_at, Some(when) if when <= now) {
+            state.remove(&key);
+            return Ok((true, previous));
+        }
 
-        // 迭代循环expireations，将所有过期的key全部清除掉
-        while let Some(&(when, ref key)) = state.expirations.iter().next() {
-            // 比较b tree树中的时间和当前时间
-            if when > now {
-                return Some(when);
-            }
+        state.insert(key.clone(), Value::String(value));
 
-            // key过期，remove
-            state.entries.remove(key);
-            state.expirations.remove(&(when, key.clone()));
+        // 如果set的时候传递了过期时间的话，需要在expireation的BTreeSet中设置相应的key和过期时间
+        let notify = state.set_expiration(&key, expires_at);
+
+        // 在设置完haspMap以及Btreeset之后将互斥锁释放掉
+        drop(state);
+
+        if notify {
+            // 如果需要notify，即过期时间已经大于现在的时间，就通知后台线程去清理过期的key
+            self.shared.bacground_task.notify_one()
         }
 
-        None
+        Ok((true, previous))
     }
 
-    fn is_shutdown(&self) -> bool {
-        self.state.lock().unwrap().shutdowm
-    }
-}
+    // 读取key对应的值之后将key删除，值不是字符串时不会删除
+    pub fn get_del(&self, key: &str) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
 
-impl State {
-    fn next_expiration(&self) -> Option<Instant> {
-        self.expirations
-            .iter()
-            .next()
-            .map(|expiration| expiration.0)
+        let data = state.get_string(key)?.cloned();
+        if data.is_some() {
+            state.remove(key);
+        }
+
+        Ok(data)
     }
-}
 
-// 后台持续运行的任务，用于清除过期的key
-async fn purge_expired_tasks(shared: Arc<Shared>) {
-    while !shared.is_shutdown() {
-        // 如果purge_expired_keys返回了时间，说明暂时还没有过期的值
-        if let Some(when) = shared.purge_expired_keys() {
-            tokio::select! {
-                _ = sleep_until(when) => {}
-                _ = shared.bacground_task.notified() => {}
+    // 读取key对应的值，同时修改它的过期时间，Expiry::Keep表示不修改
+    pub fn get_ex(&self, key: &str, expire: Expiry) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let data = match state.get_string(key)? {
+            Some(data) => data.clone(),
+            None => return Ok(None),
+        };
+
+        if expire == Expiry::Keep {
+            return Ok(Some(data));
+        }
+
+        let now = Instant::now();
+        let notify = match expire.deadline(now) {
+            Some(when) if when <= now => {
+                state.remove(key);
+                false
             }
-            // 如果没有返回时间，说明没有即将过期的key了
-        } else {
-            // 等待其他线程的通知
-            shared.bacground_task.notified().await;
+            expires_at => state.set_expiration(key, expires_at),
+        };
+
+        drop(state);
+
+        if notify {
+            self.shared.bacground_task.notify_one()
         }
+
+        Ok(Some(data))
     }
 
-    dbg!("Purege background task shut down");
+    // 修改已经存在的key的过期时间，返回是否修改成功
+    // 过期时间已经过去的话直接删除key，同样视为修改成功
+    pub fn expire(&self, key: &str, expiry: Expiry, condition: ExpireCondition) -> bool {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let current = match state.get(key) {
+            Some(entry) => entry.expires_at,
+            None => return false,
+        };
+
+        let now = Instant::now();
+        let when = match expiry.deadline(now) {
+            Some(when) => when,
+            None => return false,
+        };
+
+        // 没有过期时间的key视为永不过期，比任何过期时间都晚
+        let allowed = match current {
+            None => !condition.xx && !condition.gt,
+            Some(_) if condition.nx => false,
+            Some(current) if condition.gt => when > current,
+            Some(current) if condition.lt => when < current,
+            Some(_) => true,
+        };
+        if !allowed {
+            return false;
+        }
+
+        if when <= now {
+            state.remove(key);
+            return true;
+        }
+
+        let notify = state.set_expiration(key, Some(when));
+
+        drop(state);
+
+        // 最早的过期时间提前了，后台任务需要重新计算等待的时间
+        if notify {
+            self.shared.bacground_task.notify_one()
+        }
+
+        true
+    }
+
+    // 清除key的过期时间，key存在并且原来有过期时间时返回true
+    pub fn persist(&self, key: &str) -> bool {
+        let mut state = self.shared.state.lock().unwrap();
+
+        match state.get(key) {
+            Some(entry) if entry.expires_at.is_some() => {
+                state.set_expiration(key, None);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // key剩余的存活时间，key不存在时返回None，没有过期时间时返回Some(None)
+    pub fn ttl(&self, key: &str) -> Option<Option<Duration>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let entry = state.get(key)?;
+        let now = Instant::now();
+
+        Some(
+            entry
+                .expires_at
+                .map(|when| when.saturating_duration_since(now)),
+        )
+    }
+
+    // 在同一次加锁中读取多个key，不存在或者不是字符串的key对应None
+    pub fn mget(&self, keys: &[String]) -> Vec<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        keys.iter()
+            .map(|key| state.get_string(key).ok().flatten().cloned())
+            .collect()
+    }
+
+    // 在同一次加锁中写入多个key-value，原来的过期时间会被清除
+    pub fn mset(&self, pairs: Vec<(String, Bytes)>) {
+        let mut state = self.shared.state.lock().unwrap();
+        for (key, value) in pairs {
+            state.insert(key, Value::String(value));
+        }
+    }
+
+    // 只有所有的key都不存在时才写入，返回是否写入
+    pub fn msetnx(&self, pairs: Vec<(String, Bytes)>) -> bool {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if pairs.iter().any(|(key, _)| state.contains_key(key)) {
+            return false;
+        }
+
+        for (key, value) in pairs {
+            state.insert(key, Value::String(value));
+        }
+        true
+    }
+
+    // 删除多个key，返回实际删除的数量
+    pub fn del(&self, keys: &[String]) -> usize {
+        let mut state = self.shared.state.lock().unwrap();
+        keys.iter()
+            .filter(|key| state.remove(key).is_some())
+            .count()
+    }
+
+    // 和del一样删除多个key，但是较大的值会在锁外交给后台线程释放，避免长时间占用锁
+    pub fn unlink(&self, keys: &[String]) -> usize {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let removed: Vec<Entry> = keys.iter().filter_map(|key| state.remove(key)).collect();
+
+        drop(state);
+
+        let count = removed.len();
+
+        let size: usize = removed.iter().map(|entry| entry.value.mem_usage()).sum();
+        if size > LAZYFREE_THRESHOLD {
+            tokio::task::spawn_blocking(move || drop(removed));
+        }
+
+        count
+    }
+
+    // 返回存在的key的数量，重复的key会被重复计算
+    pub fn exists(&self, keys: &[String]) -> usize {
+        let mut state = self.shared.state.lock().unwrap();
+        keys.iter().filter(|key| state.contains_key(key)).count()
+    }
+
+    // 将key对应的整数加上delta，key不存在时视为0，原有的过期时间保持不变
+    pub fn incr_by(&self, key: &str, delta: i64) -> crate::Result<i64> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let current = match state.get_string(key)? {
+            Some(data) => parse_i64(data).ok_or(NOT_INTEGER)?,
+            None => 0,
+        };
+
+        let value = current
+            .checked_add(delta)
+            .ok_or("ERR increment or decrement would overflow")?;
+
+        state.set_data(key, Bytes::from(value.to_string()));
+
+        Ok(value)
+    }
+
+    // 将key对应的浮点数加上delta，返回计算后的字符串形式
+    pub fn incr_by_float(&self, key: &str, delta: f64) -> crate::Result<Bytes> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let current = match state.get_string(key)? {
+            Some(data) => parse_f64(data).ok_or("ERR value is not a valid float")?,
+            None => 0.0,
+        };
+
+        if !(current + delta).is_finite() {
+            return Err("ERR increment would produce NaN or Infinity".into());
+        }
+
+        let value = Bytes::from(add_float(current, delta));
+        state.set_data(key, value.clone());
+
+        Ok(value)
+    }
+
+    // 在key对应的值后面追加数据，返回追加后的长度
+    pub fn append(&self, key: &str, value: Bytes) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let data = match state.get_string(key)? {
+            Some(current) => {
+                check_string_len(current.len() + value.len())?;
+
+                let mut data = BytesMut::with_capacity(current.len() + value.len());
+                data.extend_from_slice(current);
+                data.extend_from_slice(&value);
+                data.freeze()
+            }
+            None => value,
+        };
+
+        let len = data.len();
+        state.set_data(key, data);
+
+        Ok(len)
+    }
+
+    // key对应的值的长度，key不存在时为0
+    pub fn strlen(&self, key: &str) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_string(key)?.map_or(0, |data| data.len()))
+    }
+
+    // 返回[start, end]闭区间内的子串，负数表示从末尾开始计算的位置
+    pub fn get_range(&self, key: &str, start: i64, end: i64) -> crate::Result<Bytes> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let data = match state.get_string(key)? {
+            Some(data) => data,
+            None => return Ok(Bytes::new()),
+        };
+
+        let len = data.len() as i64;
+        let start = if start < 0 {
+            (len + start).max(0)
+        } else {
+            start
+        };
+        let end = if end < 0 {
+            (len + end).max(0)
+        } else {
+            end.min(len - 1)
+        };
+
+        if len == 0 || start > end {
+            return Ok(Bytes::new());
+        }
+
+        Ok(data.slice(start as usize..=end as usize))
+    }
+
+    // 从offset开始用value覆盖原来的数据，不足的部分用0填充，返回修改后的长度
+    pub fn set_range(&self, key: &str, offset: usize, value: Bytes) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let current = state.get_string(key)?.cloned();
+
+        // value为空时不会修改数据，也不会创建不存在的key
+        if value.is_empty() {
+            return Ok(current.map_or(0, |data| data.len()));
+        }
+
+        let len = offset.checked_add(value.len()).ok_or(TOO_LARGE)?;
+        check_string_len(len)?;
+
+        let mut data = BytesMut::from(&current.unwrap_or_default()[..]);
+        if data.len() < len {
+            data.resize(len, 0);
+        }
+        data[offset..len].copy_from_slice(&value);
+
+        let len = data.len();
+        state.set_data(key, data.freeze());
+
+        Ok(len)
+    }
+
+    // 将values依次插入到列表的一端，key不存在时新建列表，返回插入之后列表的长度
+    // 阻塞在这个key上的等待者会在返回之前拿到元素，所以返回的长度包含了被等待者取走的元素
+    pub fn push(&self, key: &str, values: Vec<Bytes>, end: ListEnd) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let list = state.get_or_create_list(key)?;
+        for value in values {
+            match end {
+                ListEnd::Left => list.push_front(value),
+                ListEnd::Right => list.push_back(value),
+            }
+        }
+        let len = list.len();
+
+        state.serve_blocked(key);
+
+        Ok(len)
+    }
+
+    // BLPOP、BRPOP、BLMOVE使用，按照keys的顺序从第一个不为空的列表中弹出元素
+    // destination不为None时弹出的元素会写入destination对应的列表
+    // 所有的列表都为空时登记一个等待者，返回BlockingPop::Blocked
+    pub fn blocking_pop(
+        &self,
+        keys: &[String],
+        end: ListEnd,
+        destination: Option<(String, ListEnd)>,
+    ) -> crate::Result<BlockingPop> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        for key in keys {
+            if state.get_list(key)?.is_none() {
+                continue;
+            }
+
+            // 目标列表的类型不对时不会弹出元素
+            if let Some((destination, _)) = &destination {
+                state.get_list(destination)?;
+            }
+
+            let value = state.move_element(key, end, destination.as_ref());
+
+            // 写入目标列表之后，阻塞在目标列表上的等待者也可以被唤醒了
+            if let Some((destination, _)) = &destination {
+                state.serve_blocked(destination);
+            }
+
+            return Ok(BlockingPop::Popped(key.clone(), value));
+        }
+
+        let id = state.next_waiter_id;
+        state.next_waiter_id += 1;
+
+        for key in keys {
+            state.blocked.entry(key.clone()).or_default().push_back(id);
+        }
+
+        let moved = destination.is_some();
+
+        let (tx, rx) = oneshot::channel();
+        state.waiters.insert(
+            id,
+            Waiter {
+                keys: keys.to_vec(),
+                end,
+                destination,
+                tx,
+            },
+        );
+
+        Ok(BlockingPop::Blocked(ListWaiter {
+            shared: Arc::clone(&self.shared),
+            id,
+            rx,
+            end,
+            moved,
+        }))
+    }
+
+    // 从列表的一端依次弹出最多count个元素，key不存在时返回None
+    pub fn pop(&self, key: &str, end: ListEnd, count: usize) -> crate::Result<Option<Vec<Bytes>>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let list = match state.get_list_mut(key)? {
+            Some(list) => list,
+            None => return Ok(None),
+        };
+
+        let count = count.min(list.len());
+        let values = match end {
+            ListEnd::Left => list.drain(..count).collect(),
+            ListEnd::Right => list.drain(list.len() - count..).rev().collect(),
+        };
+
+        state.remove_if_empty(key);
+
+        Ok(Some(values))
+    }
+
+    // 返回[start, stop]闭区间内的元素，负数表示从末尾开始计算的位置
+    pub fn lrange(&self, key: &str, start: i64, stop: i64) -> crate::Result<Vec<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let list = match state.get_list(key)? {
+            Some(list) => list,
+            None => return Ok(vec![]),
+        };
+
+        let values = match list_range(list.len(), start, stop) {
+            Some((start, stop)) => list.range(start..=stop).cloned().collect(),
+            None => vec![],
+        };
+
+        Ok(values)
+    }
+
+    // 列表的长度，key不存在时为0
+    pub fn llen(&self, key: &str) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_list(key)?.map_or(0, |list| list.len()))
+    }
+
+    // 返回index位置的元素，负数表示从末尾开始计算的位置，超出范围时返回None
+    pub fn lindex(&self, key: &str, index: i64) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let value = state
+            .get_list(key)?
+            .and_then(|list| list_index(list.len(), index).map(|index| list[index].clone()));
+
+        Ok(value)
+    }
+
+    // 修改index位置的元素，key不存在或者超出范围时返回错误
+    pub fn lset(&self, key: &str, index: i64, value: Bytes) -> crate::Result<()> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let list = state.get_list_mut(key)?.ok_or("ERR no such key")?;
+        let index = list_index(list.len(), index).ok_or("ERR index out of range")?;
+        list[index] = value;
+
+        Ok(())
+    }
+
+    // 删除等于value的元素，返回删除的数量
+    // count大于0时从头部开始删除最多count个，小于0时从尾部开始删除最多-count个，等于0时全部删除
+    pub fn lrem(&self, key: &str, count: i64, value: &[u8]) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let list = match state.get_list_mut(key)? {
+            Some(list) => list,
+            None => return Ok(0),
+        };
+
+        // 从尾部删除时，先跳过头部多出来的那部分相等的元素，这样只需要遍历一次列表
+        let matches = list.iter().filter(|item| **item == value).count();
+        let limit = match count {
+            0 => matches,
+            count => matches.min(count.unsigned_abs().try_into().unwrap_or(usize::MAX)),
+        };
+        let mut skip = if count < 0 { matches - limit } else { 0 };
+        let mut removed = 0;
+
+        list.retain(|item| {
+            if removed == limit || *item != value {
+                return true;
+            }
+            if skip > 0 {
+                skip -= 1;
+                return true;
+            }
+            removed += 1;
+            false
+        });
+
+        state.remove_if_empty(key);
+
+        Ok(removed)
+    }
+
+    // 只保留[start, stop]闭区间内的元素，范围为空时key会被删除
+    pub fn ltrim(&self, key: &str, start: i64, stop: i64) -> crate::Result<()> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let list = match state.get_list_mut(key)? {
+            Some(list) => list,
+            None => return Ok(()),
+        };
+
+        match list_range(list.len(), start, stop) {
+            Some((start, stop)) => {
+                list.truncate(stop + 1);
+                list.drain(..start);
+            }
+            None => list.clear(),
+        }
+
+        state.remove_if_empty(key);
+
+        Ok(())
+    }
+
+    // 在第一个等于pivot的元素之前(before为true)或者之后插入value
+    // 和redis的LINSERT一样，返回插入之后列表的长度，key不存在时返回0，找不到pivot时返回-1
+    pub fn linsert(
+        &self,
+        key: &str,
+        before: bool,
+        pivot: &[u8],
+        value: Bytes,
+    ) -> crate::Result<i64> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let list = match state.get_list_mut(key)? {
+            Some(list) => list,
+            None => return Ok(0),
+        };
+
+        let index = match list.iter().position(|item| *item == pivot) {
+            Some(index) if before => index,
+            Some(index) => index + 1,
+            None => return Ok(-1),
+        };
+        list.insert(index, value);
+
+        Ok(list.len() as i64)
+    }
+
+    // 写入多个field-value，key不存在时新建hash，返回新增的field的数量
+    pub fn hset(&self, key: &str, pairs: Vec<(Bytes, Bytes)>) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let encoding = state.encoding;
+        let hash = state.get_or_create_hash(key)?;
+
+        let added = pairs
+            .into_iter()
+            .map(|(field, value)| hash.insert(field, value, &encoding))
+            .filter(|&added| added)
+            .count();
+
+        Ok(added)
+    }
+
+    pub fn hget(&self, key: &str, field: &[u8]) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state
+            .get_hash(key)?
+            .and_then(|hash| hash.get(field).cloned()))
+    }
+
+    // 按照fields的顺序返回对应的值，不存在的field对应None
+    pub fn hmget(&self, key: &str, fields: &[Bytes]) -> crate::Result<Vec<Option<Bytes>>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let hash = state.get_hash(key)?;
+        let values = fields
+            .iter()
+            .map(|field| hash.and_then(|hash| hash.get(field).cloned()))
+            .collect();
+
+        Ok(values)
+    }
+
+    // 删除多个field，返回实际删除的数量，所有的field都被删除之后key也会被删除
+    pub fn hdel(&self, key: &str, fields: &[Bytes]) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let hash = match state.get_hash_mut(key)? {
+            Some(hash) => hash,
+            None => return Ok(0),
+        };
+
+        let removed = fields.iter().filter(|field| hash.remove(field)).count();
+
+        state.remove_if_empty(key);
+
+        Ok(removed)
+    }
+
+    // 返回所有的field-value，key不存在时返回空数组
+    pub fn hgetall(&self, key: &str) -> crate::Result<Vec<(Bytes, Bytes)>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let pairs = state.get_hash(key)?.map_or_else(Vec::new, |hash| {
+            hash.iter()
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect()
+        });
+
+        Ok(pairs)
+    }
+
+    pub fn hkeys(&self, key: &str) -> crate::Result<Vec<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let fields = state.get_hash(key)?.map_or_else(Vec::new, |hash| {
+            hash.iter().map(|(field, _)| field.clone()).collect()
+        });
+
+        Ok(fields)
+    }
+
+    pub fn hvals(&self, key: &str) -> crate::Result<Vec<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let values = state.get_hash(key)?.map_or_else(Vec::new, |hash| {
+            hash.iter().map(|(_, value)| value.clone()).collect()
+        });
+
+        Ok(values)
+    }
+
+    // 将field对应的整数加上delta，field不存在时视为0，返回计算后的值
+    pub fn hincr_by(&self, key: &str, field: Bytes, delta: i64) -> crate::Result<i64> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let encoding = state.encoding;
+        let hash = state.get_or_create_hash(key)?;
+
+        let current = match hash.get(&field) {
+            Some(value) => parse_i64(value).ok_or("ERR hash value is not an integer")?,
+            None => 0,
+        };
+
+        let value = match current.checked_add(delta) {
+            Some(value) => value,
+            None => {
+                // key原来不存在的话，需要删除刚刚创建的空hash
+                state.remove_if_empty(key);
+                return Err("ERR increment or decrement would overflow".into());
+            }
+        };
+
+        hash.insert(field, Bytes::from(value.to_string()), &encoding);
+
+        Ok(value)
+    }
+
+    pub fn hexists(&self, key: &str, field: &[u8]) -> crate::Result<bool> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state
+            .get_hash(key)?
+            .is_some_and(|hash| hash.contains(field)))
+    }
+
+    // field的数量，key不存在时为0
+    pub fn hlen(&self, key: &str) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_hash(key)?.map_or(0, |hash| hash.len()))
+    }
+
+    // 从cursor开始遍历hash，返回下一次的游标以及这一次遍历到的field-value
+    // count只是大致的数量，pattern不为None时只返回field匹配pattern的元素
+    pub fn hscan(
+        &self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+    ) -> crate::Result<(u64, Vec<(Bytes, Bytes)>)> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let hash = match state.get_hash(key)? {
+            Some(hash) => hash,
+            None => return Ok((0, vec![])),
+        };
+
+        let mut pairs = vec![];
+        let cursor = hash.scan(cursor, count, |field, value| {
+            if pattern.is_none_or(|pattern| glob_match(pattern, field)) {
+                pairs.push((field.clone(), value.clone()));
+            }
+        });
+
+        Ok((cursor, pairs))
+    }
+
+    // 和HSCAN一样遍历set，intset编码时一次返回所有的成员
+    pub fn sscan(
+        &self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+    ) -> crate::Result<(u64, Vec<Bytes>)> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let set = match state.get_set(key)? {
+            Some(set) => set,
+            None => return Ok((0, vec![])),
+        };
+
+        let mut members = vec![];
+        let cursor = set.scan(cursor, count, |member| {
+            if pattern.is_none_or(|pattern| glob_match(pattern, member)) {
+                members.push(member.clone());
+            }
+        });
+
+        Ok((cursor, members))
+    }
+
+    // 返回新增的成员数量，已经存在的成员不计算在内
+    pub fn sadd(&self, key: &str, members: Vec<Bytes>) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let encoding = state.encoding;
+        let set = state.get_or_create_set(key)?;
+
+        let added = members
+            .into_iter()
+            .map(|member| set.insert(member, &encoding))
+            .filter(|&added| added)
+            .count();
+
+        Ok(added)
+    }
+
+    // 返回实际删除的成员数量
+    pub fn srem(&self, key: &str, members: &[Bytes]) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let set = match state.get_set_mut(key)? {
+            Some(set) => set,
+            None => return Ok(0),
+        };
+
+        let removed = members.iter().filter(|member| set.remove(member)).count();
+
+        state.remove_if_empty(key);
+
+        Ok(removed)
+    }
+
+    // 返回所有的成员，key不存在时返回空数组
+    pub fn smembers(&self, key: &str) -> crate::Result<Vec<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state
+            .get_set(key)?
+            .map_or_else(Vec::new, |set| set.iter().collect()))
+    }
+
+    pub fn sismember(&self, key: &str, member: &[u8]) -> crate::Result<bool> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_set(key)?.is_some_and(|set| set.contains(member)))
+    }
+
+    pub fn scard(&self, key: &str) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_set(key)?.map_or(0, |set| set.len()))
+    }
+
+    // SINTER、SUNION、SDIFF，不存在的key视为空set
+    pub fn set_op(&self, op: SetOp, keys: &[String]) -> crate::Result<Vec<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.set_op(op, keys)
+    }
+
+    // SINTERSTORE、SUNIONSTORE、SDIFFSTORE，返回结果中的成员数量
+    // 计算和写入在同一次加锁中完成，其他连接看不到中间状态。destination原来的值和过期时间都会被覆盖，
+    // 和redis一样，结果为空时删除destination
+    pub fn set_op_store(
+        &self,
+        op: SetOp,
+        destination: &str,
+        keys: &[String],
+    ) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let members = state.set_op(op, keys)?;
+        let len = members.len();
+
+        if members.is_empty() {
+            state.remove(destination);
+        } else {
+            let mut set = Set::new();
+            for member in members {
+                set.insert(member, &state.encoding);
+            }
+            state.insert(destination.to_string(), Value::Set(set));
+        }
+
+        Ok(len)
+    }
+
+    // 随机删除并返回最多count个成员，key不存在时返回空数组
+    pub fn spop(&self, key: &str, count: usize) -> crate::Result<Vec<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let (set, rng) = match state.get_set_and_rng(key)? {
+            Some(found) => found,
+            None => return Ok(vec![]),
+        };
+
+        let members = sample_members(set, count, rng);
+        for member in &members {
+            set.remove(member);
+        }
+
+        state.remove_if_empty(key);
+
+        Ok(members)
+    }
+
+    // count不小于0时随机返回最多count个不同的成员，小于0时返回-count个成员，同一个成员可能出现多次
+    pub fn srandmember(&self, key: &str, count: i64) -> crate::Result<Vec<Bytes>> {
+        // 和redis一样count的范围是[-i64::MAX, i64::MAX]
+        if count == i64::MIN {
+            return Err("ERR value is out of range".into());
+        }
+
+        let mut state = self.shared.state.lock().unwrap();
+
+        let (set, rng) = match state.get_set_and_rng(key)? {
+            Some(found) => found,
+            None => return Ok(vec![]),
+        };
+
+        if let Ok(count) = usize::try_from(count) {
+            return Ok(sample_members(set, count, rng));
+        }
+
+        // count可能很大，不按照count预先分配内存
+        let mut members = vec![];
+        for _ in 0..count.unsigned_abs() {
+            members.push(set.random(rng).expect("the set is not empty"));
+        }
+
+        Ok(members)
+    }
+
+    // 返回新增的成员数量，options.ch为true时还包括分数发生变化的成员
+    pub fn zadd(
+        &self,
+        key: &str,
+        pairs: Vec<(f64, Bytes)>,
+        options: ZAddOptions,
+    ) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let zset = state.get_or_create_zset(key)?;
+        let results = pairs
+            .into_iter()
+            .map(|(score, member)| zset.add(member, score, &options, false))
+            .collect::<crate::Result<Vec<_>>>();
+
+        // XX时可能一个成员都没有写入
+        state.remove_if_empty(key);
+
+        let count = results?
+            .into_iter()
+            .filter(|result| match result {
+                ZAddResult::Added(_) => true,
+                ZAddResult::Updated(_) => options.ch,
+                _ => false,
+            })
+            .count();
+
+        Ok(count)
+    }
+
+    // ZINCRBY以及ZADD的INCR选项，返回成员新的分数，因为NX、XX、GT、LT的条件没有写入时返回None
+    pub fn zincr_by(
+        &self,
+        key: &str,
+        member: Bytes,
+        increment: f64,
+        options: ZAddOptions,
+    ) -> crate::Result<Option<f64>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let result = state
+            .get_or_create_zset(key)?
+            .add(member, increment, &options, true);
+
+        state.remove_if_empty(key);
+
+        match result? {
+            ZAddResult::Added(score)
+            | ZAddResult::Updated(score)
+            | ZAddResult::Unchanged(score) => Ok(Some(score)),
+            ZAddResult::Skipped => Ok(None),
+        }
+    }
+
+    // 返回实际删除的成员数量
+    pub fn zrem(&self, key: &str, members: &[Bytes]) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let zset = match state.get_zset_mut(key)? {
+            Some(zset) => zset,
+            None => return Ok(0),
+        };
+
+        let removed = members.iter().filter(|member| zset.remove(member)).count();
+
+        state.remove_if_empty(key);
+
+        Ok(removed)
+    }
+
+    pub fn zscore(&self, key: &str, member: &[u8]) -> crate::Result<Option<f64>> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_zset(key)?.and_then(|zset| zset.score(member)))
+    }
+
+    pub fn zcard(&self, key: &str) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_zset(key)?.map_or(0, |zset| zset.len()))
+    }
+
+    // 和HSCAN一样遍历有序集合，返回的成员没有按照分数排序
+    pub fn zscan(
+        &self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<&[u8]>,
+        count: usize,
+    ) -> crate::Result<(u64, Vec<(Bytes, f64)>)> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let zset = match state.get_zset(key)? {
+            Some(zset) => zset,
+            None => return Ok((0, vec![])),
+        };
+
+        let mut members = vec![];
+        let cursor = zset.scan(cursor, count, |member, score| {
+            if pattern.is_none_or(|pattern| glob_match(pattern, member)) {
+                members.push((member.clone(), score));
+            }
+        });
+
+        Ok((cursor, members))
+    }
+
+    // 成员的排名(从0开始)以及分数，rev为true时分数最高的成员排名为0
+    pub fn zrank(
+        &self,
+        key: &str,
+        member: &[u8],
+        rev: bool,
+    ) -> crate::Result<Option<(usize, f64)>> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_zset(key)?.and_then(|zset| zset.rank(member, rev)))
+    }
+
+    // 返回范围内的成员以及分数，rev为true时按照分数从高到低返回
+    // limit是(offset, count)，只对按照分数或者字典序的范围有效，offset为负数时返回空数组，count为负数时不限制数量
+    pub fn zrange(
+        &self,
+        key: &str,
+        range: ZRange,
+        rev: bool,
+        limit: Option<(i64, i64)>,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let zset = match state.get_zset(key)? {
+            Some(zset) => zset,
+            None => return Ok(vec![]),
+        };
+
+        let (offset, count) = match limit {
+            Some((offset, count)) => match usize::try_from(offset) {
+                Ok(offset) => (offset, usize::try_from(count).ok()),
+                Err(_) => return Ok(vec![]),
+            },
+            None => (0, None),
+        };
+
+        let members = match range {
+            ZRange::Rank(start, stop) => match list_range(zset.len(), start, stop) {
+                Some((start, stop)) => zset.range_by_rank(start, stop, rev),
+                None => vec![],
+            },
+            ZRange::Score(min, max) => zset.range_by_score(min, max, rev, offset, count),
+            ZRange::Lex(min, max) => zset.range_by_lex(&min, &max, rev, offset, count),
+        };
+
+        Ok(members)
+    }
+
+    // 分数在[min, max]之间的成员数量
+    pub fn zcount(&self, key: &str, min: ScoreBound, max: ScoreBound) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_zset(key)?.map_or(0, |zset| zset.count(min, max)))
+    }
+
+    // 删除并返回分数最低的count个成员，rev为true时删除分数最高的，key不存在时返回空数组
+    pub fn zpop(&self, key: &str, count: usize, rev: bool) -> crate::Result<Vec<(Bytes, f64)>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let popped = match state.get_zset_mut(key)? {
+            Some(zset) => zset.pop(count, rev),
+            None => return Ok(vec![]),
+        };
+
+        state.remove_if_empty(key);
+
+        Ok(popped)
+    }
+
+    // ZUNIONSTORE、ZINTERSTORE，weights和keys一一对应，返回结果中的成员数量
+    // 计算和写入在同一次加锁中完成，destination原来的值和过期时间都会被覆盖，结果为空时删除destination
+    pub fn zset_op_store(
+        &self,
+        op: SetOp,
+        destination: &str,
+        keys: &[String],
+        weights: &[f64],
+        aggregate: Aggregate,
+    ) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let members = state.zset_op(op, keys, weights, aggregate)?;
+        let len = members.len();
+
+        if members.is_empty() {
+            state.remove(destination);
+        } else {
+            let mut zset = ZSet::new();
+            for (member, score) in members {
+                zset.insert(member, score);
+            }
+            state.insert(destination.to_string(), Value::ZSet(zset));
+        }
+
+        Ok(len)
+    }
+
+    // 向stream追加一个条目，key不存在时新建stream，返回新条目的ID
+    // nomkstream为true并且key不存在时不会创建stream，返回None
+    // maxlen不为None时写入之后删除最早的条目，直到条目数量不超过maxlen
+    pub fn xadd(
+        &self,
+        key: &str,
+        id: XAddId,
+        fields: Vec<(Bytes, Bytes)>,
+        nomkstream: bool,
+        maxlen: Option<usize>,
+    ) -> crate::Result<Option<StreamId>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        // 先检查ID，ID不合法时不能创建空的stream
+        let id = match state.get_stream(key)? {
+            Some(stream) => stream.next_id(id)?,
+            None if nomkstream => return Ok(None),
+            None => Stream::new().next_id(id)?,
+        };
+
+        let stream = state.get_or_create_stream(key)?;
+        stream.insert(id, fields);
+        if let Some(maxlen) = maxlen {
+            stream.trim(maxlen);
+        }
+
+        state.serve_stream_blocked(key);
+
+        Ok(Some(id))
+    }
+
+    // stream中的条目数量，key不存在时返回0
+    pub fn xlen(&self, key: &str) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_stream(key)?.map_or(0, |stream| stream.len()))
+    }
+
+    // ID在[start, end]之间的最多count个条目，rev为true时从end开始倒序返回
+    pub fn xrange(
+        &self,
+        key: &str,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+        rev: bool,
+    ) -> crate::Result<Vec<StreamEntry>> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        Ok(state
+            .get_stream(key)?
+            .map_or_else(Vec::new, |stream| stream.range(start, end, count, rev)))
+    }
+
+    // XREAD，读取每个stream中ID大于ids中对应ID的条目，ids中的None表示$，即只读取之后写入的条目
+    // None会被替换成stream当前的last_id，阻塞的客户端被唤醒之后用替换后的ids重新读取
+    // 所有的stream都没有新条目并且block为true时登记一个等待者
+    pub fn xread(
+        &self,
+        keys: &[String],
+        ids: &mut [Option<StreamId>],
+        count: Option<usize>,
+        block: bool,
+    ) -> crate::Result<StreamRead> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let mut result = vec![];
+        for (key, id) in keys.iter().zip(ids.iter_mut()) {
+            let stream = state.get_stream(key)?;
+            let after = *id.get_or_insert(stream.map_or(StreamId::MIN, |stream| stream.last_id()));
+
+            let entries = stream.map_or_else(Vec::new, |stream| stream.read_after(after, count));
+            if !entries.is_empty() {
+                result.push((key.clone(), entries));
+            }
+        }
+
+        if !result.is_empty() || !block {
+            return Ok(StreamRead::Entries(result));
+        }
+
+        Ok(StreamRead::Blocked(
+            state.block_stream_reader(&self.shared, keys),
+        ))
+    }
+
+    // XREADGROUP，ids中的None表示>，读取从来没有投递给组内消费者的条目，并加入consumer的待确认列表
+    // 指定ID时读取consumer待确认列表中ID大于它的条目，这种情况下即使没有条目也会返回key
+    // 所有的ID都是>并且没有新条目，同时block为true时登记一个等待者
+    pub fn xread_group(
+        &self,
+        group: &str,
+        consumer: &str,
+        keys: &[String],
+        ids: &[Option<StreamId>],
+        options: &XReadGroupOptions,
+    ) -> crate::Result<StreamRead> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        // 先检查所有的key，任意一个key或者组不存在时都不会读取
+        for key in keys {
+            match state.get_stream(key)? {
+                Some(stream) if stream.has_group(group) => {}
+                _ => {
+                    return Err(format!(
+                        "NOGROUP No such key '{}' or consumer group '{}' in XREADGROUP with GROUP option",
+                        key, group
+                    )
+                    .into())
+                }
+            }
+        }
+
+        let mut result = vec![];
+        for (key, id) in keys.iter().zip(ids) {
+            let stream = state.get_group_stream(key, group)?;
+
+            let entries = match id {
+                None => stream.read_group_new(group, consumer, options.count, options.noack),
+                Some(after) => stream.read_group_history(group, consumer, *after, options.count),
+            }
+            .expect("the group exists");
+
+            if id.is_some() || !entries.is_empty() {
+                result.push((key.clone(), entries));
+            }
+        }
+
+        if !result.is_empty() || !options.block {
+            return Ok(StreamRead::Entries(result));
+        }
+
+        Ok(StreamRead::Blocked(
+            state.block_stream_reader(&self.shared, keys),
+        ))
+    }
+
+    // XGROUP CREATE，last_delivered为None时表示$，只投递之后写入的条目
+    // key不存在并且mkstream为true时新建一个空的stream
+    pub fn xgroup_create(
+        &self,
+        key: &str,
+        group: &str,
+        last_delivered: Option<StreamId>,
+        mkstream: bool,
+    ) -> crate::Result<()> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let stream = match state.get_stream_mut(key)? {
+            Some(stream) => stream,
+            None if mkstream => state.get_or_create_stream(key)?,
+            None => return Err(XGROUP_NO_KEY.into()),
+        };
+
+        let last_delivered = last_delivered.unwrap_or(stream.last_id());
+        if !stream.create_group(group, last_delivered) {
+            return Err("BUSYGROUP Consumer Group name already exists".into());
+        }
+
+        Ok(())
+    }
+
+    // XGROUP DESTROY，组不存在时返回false
+    pub fn xgroup_destroy(&self, key: &str, group: &str) -> crate::Result<bool> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state.get_xgroup_stream(key)?.destroy_group(group))
+    }
+
+    // XGROUP SETID，last_delivered为None时表示$
+    pub fn xgroup_set_id(
+        &self,
+        key: &str,
+        group: &str,
+        last_delivered: Option<StreamId>,
+    ) -> crate::Result<()> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let stream = state.get_xgroup_stream(key)?;
+        let last_delivered = last_delivered.unwrap_or(stream.last_id());
+        if !stream.set_group_id(group, last_delivered) {
+            return Err(no_such_group(key, group));
+        }
+
+        Ok(())
+    }
+
+    // XGROUP CREATECONSUMER，消费者已经存在时返回false
+    pub fn xgroup_create_consumer(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> crate::Result<bool> {
+        let mut state = self.shared.state.lock().unwrap();
+        state
+            .get_xgroup_stream(key)?
+            .create_consumer(group, consumer)
+            .ok_or_else(|| no_such_group(key, group))
+    }
+
+    // XGROUP DELCONSUMER，返回消费者被删除时还没有确认的条目数量
+    pub fn xgroup_delete_consumer(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        state
+            .get_xgroup_stream(key)?
+            .delete_consumer(group, consumer)
+            .ok_or_else(|| no_such_group(key, group))
+    }
+
+    // 确认条目，返回确认成功的数量，key或者组不存在时返回0
+    pub fn xack(&self, key: &str, group: &str, ids: &[StreamId]) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state
+            .get_stream_mut(key)?
+            .map_or(0, |stream| stream.ack(group, ids)))
+    }
+
+    // XPENDING key group，待确认条目的汇总信息
+    pub fn xpending_summary(&self, key: &str, group: &str) -> crate::Result<PendingSummary> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state
+            .get_group_stream(key, group)?
+            .pending_summary(group)
+            .expect("the group exists"))
+    }
+
+    // XPENDING key group [IDLE min-idle-time] start end count [consumer]
+    pub fn xpending(
+        &self,
+        key: &str,
+        group: &str,
+        range: &PendingRange,
+    ) -> crate::Result<Vec<PendingEntry>> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state
+            .get_group_stream(key, group)?
+            .pending(group, range)
+            .expect("the group exists"))
+    }
+
+    // 把空闲了至少min_idle毫秒的待确认条目转移给consumer，返回转移成功的条目
+    pub fn xclaim(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle: u64,
+        ids: &[StreamId],
+        options: &XClaimOptions,
+    ) -> crate::Result<Vec<StreamEntry>> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state
+            .get_group_stream(key, group)?
+            .claim(group, consumer, min_idle, ids, options)
+            .expect("the group exists"))
+    }
+
+    // 从start开始扫描待确认列表，把空闲了至少min_idle毫秒的条目转移给consumer，最多转移count个
+    pub fn xautoclaim(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        options: &AutoClaimOptions,
+    ) -> crate::Result<AutoClaimed> {
+        let mut state = self.shared.state.lock().unwrap();
+        Ok(state
+            .get_group_stream(key, group)?
+            .auto_claim(group, consumer, options)
+            .expect("the group exists"))
+    }
+
+    // 订阅一个值，返回一个boardcast的receiver，通过这个recivier可以获取到这个值变化
+    pub fn subscribe(&self, key: String) -> broadcast::Receiver<Bytes> {
+        use std::collections::hash_map::Entry;
+        // todo
+        let mut state = self.shared.state.lock().unwrap();
+        match state.pub_sub.entry(key) {
+            Entry::Occupied(v) => v.get().subscribe(),
+            Entry::Vacant(v) => {
+                // 如果没有需要新建一个boardcast
+                let (tx, rx) = broadcast::channel(1024);
+                v.insert(tx);
+                rx
+            }
+        }
+    }
+
+    // 发布消息 ，让所有订阅者进行接收，哪些值改动了
+    // 返回订阅者的数量
+    pub fn publish(&self, key: &str, value: Bytes) -> usize {
+        let state = self.shared.state.lock().unwrap();
+        state
+            .pub_sub
+            .get(key)
+            .map(|tx| tx.send(value).unwrap_or(0))
+            .unwrap_or(0)
+    }
+
+    // 当db被drop时，需要通知后台清除所有的key
+    fn shut_down_purge(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        state.shutdowm = true;
+
+        // 服务器关闭时还没有退出的连接仍然持有Db，这里直接清空数据，不必等到最后一个Db被drop
+        // pub_sub中的sender被drop之后，所有订阅者的消息流也会随之结束
+        state.entries.clear();
+        state.expirations.clear();
+        state.volatile.clear();
+        state.pub_sub.clear();
+        // 等待者的sender被drop之后，阻塞的客户端会立即返回
+        state.waiters.clear();
+        state.blocked.clear();
+        state.stream_waiters.clear();
+        state.stream_blocked.clear();
+
+        drop(state);
+
+        self.shared.bacground_task.notify_one();
+    }
+}
+
+impl Shared {
+    // 清除所有过期的key
+    fn purge_expired_keys(&self) -> Option<Instant> {
+        let mut state = self.state.lock().unwrap();
+
+        // 如果shutdown为true，证明db已经关闭
+        if state.shutdowm {
+            return None;
+        }
+
+        let now = Instant::now();
+
+        // lock（）返回的是MutexGuard并不是对state的可变引用，但我们后续的while中需要使用state的可变引用
+        // 使用*操作符，解引用出互斥锁内的数据，然后通过&mut创建其内部数据的可变引用
+        let state = &mut *state;
+
+        // 迭代循环expireations，将所有过期的key全部清除掉
+        while let Some(&(when, ref key)) = state.expirations.iter().next() {
+            // 比较b tree树中的时间和当前时间
+            if when > now {
+                return Some(when);
+            }
+
+            // key过期，remove
+            state.entries.remove(key);
+            state.expirations.remove(&(when, key.clone()));
+        }
+
+        None
+    }
+
+    // 随机抽查一批带过期时间的key，删除其中已经过期的
+    // 过期的比例超过25%时返回true，说明可能还有大量过期的key，调用方应该继续抽查
+    fn sample_expired_keys(&self, rng: &mut XorShift) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        if state.shutdowm {
+            return false;
+        }
+
+        let now = Instant::now();
+        let state = &mut *state;
+
+        let samples = ACTIVE_EXPIRE_SAMPLES.min(state.volatile.len());
+        let mut expired = 0;
+
+        for _ in 0..samples {
+            if state.volatile.is_empty() {
+                break;
+            }
+
+            let slot = (rng.next() % state.volatile.len() as u64) as usize;
+            let key = &state.volatile[slot];
+
+            // 正常情况下volatile中的key都存在并且设置了过期时间，记录的下标也一致
+            // 万一出现了不一致的记录，直接丢弃，不能让后台任务panic导致整个Db不可用
+            let is_expired = match state.entries.get(key) {
+                Some(entry) if entry.volatile_slot == slot && entry.expires_at.is_some() => {
+                    entry.expires_at.is_some_and(|when| when <= now)
+                }
+                _ => {
+                    state.remove_volatile_slot(slot);
+                    continue;
+                }
+            };
+
+            if is_expired {
+                let key = key.clone();
+                state.remove(&key);
+                expired += 1;
+            }
+        }
+
+        expired * 4 > samples
+    }
+
+    fn is_shutdown(&self) -> bool {
+        self.state.lock().unwrap().shutdowm
+    }
+}
+
+impl State {
+    // 所有读取key的地方都需要先经过这里，已经过期但还没有被后台任务清除的key会被立即删除，视为不存在
+    // 这样过期时间是精确的，不依赖后台任务被调度的时机
+    fn get(&mut self, key: &str) -> Option<&Entry> {
+        self.get_mut(key).map(|entry| &*entry)
+    }
+
+    // 和get一样，同时和redis的lookupKey一样更新key的访问时间和访问频率
+    fn get_mut(&mut self, key: &str) -> Option<&mut Entry> {
+        self.expire_if_needed(key);
+        let entry = self.entries.get_mut(key)?;
+        entry.touch(&mut self.rng);
+        Some(entry)
+    }
+
+    // key不存在时新建一个不会过期的条目，f返回新建的值
+    fn get_or_insert_with(&mut self, key: &str, f: impl FnOnce() -> Value) -> &mut Entry {
+        self.expire_if_needed(key);
+        let entry = self
+            .entries
+            .get_or_insert_with(key.to_string(), || Entry::new(f()));
+        entry.touch(&mut self.rng);
+        entry
+    }
+
+    // 和get一样会删除已经过期的key，但是不会更新访问时间和访问频率
+    fn peek(&mut self, key: &str) -> Option<&Entry> {
+        self.expire_if_needed(key);
+        self.entries.get(key)
+    }
+
+    fn contains_key(&mut self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    // 读取字符串类型的值，key不存在时返回None
+    fn get_string(&mut self, key: &str) -> crate::Result<Option<&Bytes>> {
+        self.get(key)
+            .map(|entry| entry.value.as_string())
+            .transpose()
+    }
+
+    fn get_list(&mut self, key: &str) -> crate::Result<Option<&VecDeque<Bytes>>> {
+        self.get(key).map(|entry| entry.value.as_list()).transpose()
+    }
+
+    fn get_list_mut(&mut self, key: &str) -> crate::Result<Option<&mut VecDeque<Bytes>>> {
+        self.get_mut(key)
+            .map(|entry| entry.value.as_list_mut())
+            .transpose()
+    }
+
+    // key不存在时新建一个不会过期的空列表，调用方需要保证写入之后列表不为空
+    fn get_or_create_list(&mut self, key: &str) -> crate::Result<&mut VecDeque<Bytes>> {
+        self.get_or_insert_with(key, || Value::List(VecDeque::new()))
+            .value
+            .as_list_mut()
+    }
+
+    fn get_hash(&mut self, key: &str) -> crate::Result<Option<&Hash>> {
+        self.get(key).map(|entry| entry.value.as_hash()).transpose()
+    }
+
+    fn get_hash_mut(&mut self, key: &str) -> crate::Result<Option<&mut Hash>> {
+        self.get_mut(key)
+            .map(|entry| entry.value.as_hash_mut())
+            .transpose()
+    }
+
+    // key不存在时新建一个不会过期的空hash，调用方需要保证写入之后hash不为空
+    fn get_or_create_hash(&mut self, key: &str) -> crate::Result<&mut Hash> {
+        self.get_or_insert_with(key, || Value::Hash(Hash::new()))
+            .value
+            .as_hash_mut()
+    }
+
+    fn get_set(&mut self, key: &str) -> crate::Result<Option<&Set>> {
+        self.get(key).map(|entry| entry.value.as_set()).transpose()
+    }
+
+    // SPOP、SRANDMEMBER在访问set的同时需要使用随机数生成器，两者都是State的字段，所以一起借用
+    fn get_set_and_rng(&mut self, key: &str) -> crate::Result<Option<(&mut Set, &mut XorShift)>> {
+        self.expire_if_needed(key);
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                entry.touch(&mut self.rng);
+                Ok(Some((entry.value.as_set_mut()?, &mut self.rng)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn get_set_mut(&mut self, key: &str) -> crate::Result<Option<&mut Set>> {
+        self.get_mut(key)
+            .map(|entry| entry.value.as_set_mut())
+            .transpose()
+    }
+
+    // key不存在时新建一个不会过期的空set，调用方需要保证写入之后set不为空
+    fn get_or_create_set(&mut self, key: &str) -> crate::Result<&mut Set> {
+        self.get_or_insert_with(key, || Value::Set(Set::new()))
+            .value
+            .as_set_mut()
+    }
+
+    // 计算多个set的交集、并集或者差集，不存在的key视为空set，任何一个key不是set时返回WRONGTYPE
+    fn set_op(&mut self, op: SetOp, keys: &[String]) -> crate::Result<Vec<Bytes>> {
+        // 先清除过期的key并检查类型，之后才能同时借用多个set
+        for key in keys {
+            self.get_set(key)?;
+        }
+
+        let sets: Vec<Option<&Set>> = keys
+            .iter()
+            .map(|key| {
+                self.entries
+                    .get(key)
+                    .and_then(|entry| entry.value.as_set().ok())
+            })
+            .collect();
+
+        let members = match op {
+            // 有一个set为空时交集也为空，否则遍历最小的set，检查它的成员是否在其他所有set中
+            SetOp::Inter => match sets.into_iter().collect::<Option<Vec<_>>>() {
+                Some(mut sets) => {
+                    sets.sort_by_key(|set| set.len());
+                    match sets.split_first() {
+                        Some((smallest, rest)) => smallest
+                            .iter()
+                            .filter(|member| rest.iter().all(|set| set.contains(member)))
+                            .collect(),
+                        None => vec![],
+                    }
+                }
+                None => vec![],
+            },
+            SetOp::Union => {
+                let mut seen = HashSet::new();
+                sets.into_iter()
+                    .flatten()
+                    .flat_map(|set| set.iter())
+                    .filter(|member| seen.insert(member.clone()))
+                    .collect()
+            }
+            // 第一个set中不属于其他任何一个set的成员
+            SetOp::Diff => match sets.split_first() {
+                Some((Some(first), rest)) => first
+                    .iter()
+                    .filter(|member| !rest.iter().flatten().any(|set| set.contains(member)))
+                    .collect(),
+                _ => vec![],
+            },
+        };
+
+        Ok(members)
+    }
+
+    fn get_zset(&mut self, key: &str) -> crate::Result<Option<&ZSet>> {
+        self.get(key).map(|entry| entry.value.as_zset()).transpose()
+    }
+
+    fn get_zset_mut(&mut self, key: &str) -> crate::Result<Option<&mut ZSet>> {
+        self.get_mut(key)
+            .map(|entry| entry.value.as_zset_mut())
+            .transpose()
+    }
+
+    fn get_stream(&mut self, key: &str) -> crate::Result<Option<&Stream>> {
+        self.get(key)
+            .map(|entry| entry.value.as_stream())
+            .transpose()
+    }
+
+    fn get_stream_mut(&mut self, key: &str) -> crate::Result<Option<&mut Stream>> {
+        self.get_mut(key)
+            .map(|entry| entry.value.as_stream_mut())
+            .transpose()
+    }
+
+    // key不存在时新建一个不会过期的空stream，空的stream不会被删除
+    fn get_or_create_stream(&mut self, key: &str) -> crate::Result<&mut Stream> {
+        self.get_or_insert_with(key, || Value::Stream(Stream::new()))
+            .value
+            .as_stream_mut()
+    }
+
+    // 用于XREADGROUP、XPENDING等需要消费者组已经存在的命令，key或者组不存在时返回NOGROUP错误
+    fn get_group_stream(&mut self, key: &str, group: &str) -> crate::Result<&mut Stream> {
+        match self.get_stream_mut(key)? {
+            Some(stream) if stream.has_group(group) => Ok(stream),
+            _ => Err(format!(
+                "NOGROUP No such key '{}' or consumer group '{}'",
+                key, group
+            )
+            .into()),
+        }
+    }
+
+    // 用于XGROUP的子命令，key不存在时返回错误，不会自动创建
+    fn get_xgroup_stream(&mut self, key: &str) -> crate::Result<&mut Stream> {
+        self.get_stream_mut(key)?
+            .ok_or_else(|| XGROUP_NO_KEY.into())
+    }
+
+    // 登记一个等待keys中任意一个stream写入新条目的等待者
+    fn block_stream_reader(&mut self, shared: &Arc<Shared>, keys: &[String]) -> StreamWaiter {
+        let id = self.next_waiter_id;
+        self.next_waiter_id += 1;
+
+        for key in keys {
+            self.stream_blocked.entry(key.clone()).or_default().push(id);
+        }
+
+        let (tx, rx) = oneshot::channel();
+        self.stream_waiters.insert(
+            id,
+            StreamReader {
+                keys: keys.to_vec(),
+                tx,
+            },
+        );
+
+        StreamWaiter {
+            shared: Arc::clone(shared),
+            id,
+            rx,
+        }
+    }
+
+    // stream中写入了新条目之后唤醒这个key上所有的等待者
+    fn serve_stream_blocked(&mut self, key: &str) {
+        let ids = match self.stream_blocked.remove(key) {
+            Some(ids) => ids,
+            None => return,
+        };
+
+        for id in ids {
+            if let Some(reader) = self.remove_stream_reader(id) {
+                let _ = reader.tx.send(());
+            }
+        }
+    }
+
+    // 将等待者从所有key的等待列表中移除，已经被唤醒或者移除过的话返回None
+    fn remove_stream_reader(&mut self, id: u64) -> Option<StreamReader> {
+        let reader = self.stream_waiters.remove(&id)?;
+
+        for key in &reader.keys {
+            if let Some(ids) = self.stream_blocked.get_mut(key) {
+                ids.retain(|&other| other != id);
+                if ids.is_empty() {
+                    self.stream_blocked.remove(key);
+                }
+            }
+        }
+
+        Some(reader)
+    }
+
+    // key不存在时新建一个不会过期的空有序集合，调用方需要在写入之后调用remove_if_empty
+    fn get_or_create_zset(&mut self, key: &str) -> crate::Result<&mut ZSet> {
+        self.get_or_insert_with(key, || Value::ZSet(ZSet::new()))
+            .value
+            .as_zset_mut()
+    }
+
+    // 计算多个有序集合的并集、交集或者差集，weights和keys一一对应，每个输入的分数先乘以对应的权重
+    // 不存在的key视为空集合，key既不是有序集合也不是set时返回WRONGTYPE
+    fn zset_op(
+        &mut self,
+        op: SetOp,
+        keys: &[String],
+        weights: &[f64],
+        aggregate: Aggregate,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        // 先清除过期的key并检查类型，之后才能同时借用多个输入
+        for key in keys {
+            if let Some(entry) = self.get(key) {
+                if !matches!(entry.value, Value::Set(_) | Value::ZSet(_)) {
+                    return Err(WRONGTYPE.into());
+                }
+            }
+        }
+
+        let inputs: Vec<Option<(ZInput, f64)>> = keys
+            .iter()
+            .zip(weights)
+            .map(|(key, &weight)| {
+                let input = match &self.entries.get(key)?.value {
+                    Value::Set(set) => ZInput::Set(set),
+                    Value::ZSet(zset) => ZInput::ZSet(zset),
+                    _ => return None,
+                };
+                Some((input, weight))
+            })
+            .collect();
+
+        let members = match op {
+            SetOp::Union => {
+                let mut scores: HashMap<Bytes, f64> = HashMap::new();
+                for (input, weight) in inputs.iter().flatten() {
+                    for (member, score) in input.iter() {
+                        let score = weighted(score, *weight);
+                        scores
+                            .entry(member)
+                            .and_modify(|acc| *acc = aggregate.apply(*acc, score))
+                            .or_insert(score);
+                    }
+                }
+                scores.into_iter().collect()
+            }
+            // 有一个输入为空时交集也为空，否则遍历最小的输入，检查它的成员是否在其他所有输入中
+            SetOp::Inter => match inputs.into_iter().collect::<Option<Vec<_>>>() {
+                Some(mut inputs) => {
+                    inputs.sort_by_key(|(input, _)| input.len());
+                    match inputs.split_first() {
+                        Some(((smallest, weight), rest)) => smallest
+                            .iter()
+                            .filter_map(|(member, score)| {
+                                let mut acc = weighted(score, *weight);
+                                for (input, weight) in rest {
+                                    let score = weighted(input.score(&member)?, *weight);
+                                    acc = aggregate.apply(acc, score);
+                                }
+                                Some((member, acc))
+                            })
+                            .collect(),
+                        None => vec![],
+                    }
+                }
+                None => vec![],
+            },
+            // 第一个输入中不属于其他任何一个输入的成员，分数只来自第一个输入
+            SetOp::Diff => match inputs.split_first() {
+                Some((Some((first, weight)), rest)) => first
+                    .iter()
+                    .filter(|(member, _)| {
+                        !rest
+                            .iter()
+                            .flatten()
+                            .any(|(input, _)| input.score(member).is_some())
+                    })
+                    .map(|(member, score)| (member, weighted(score, *weight)))
+                    .collect(),
+                _ => vec![],
+            },
+        };
+
+        Ok(members)
+    }
+
+    // 从列表的一端弹出一个元素，写入destination对应的列表，调用方需要保证列表不为空
+    fn move_element(
+        &mut self,
+        key: &str,
+        end: ListEnd,
+        destination: Option<&(String, ListEnd)>,
+    ) -> Bytes {
+        let list = self
+            .get_list_mut(key)
+            .ok()
+            .flatten()
+            .expect("the list is not empty");
+        let value = match end {
+            ListEnd::Left => list.pop_front(),
+            ListEnd::Right => list.pop_back(),
+        }
+        .expect("the list is not empty");
+
+        self.remove_if_empty(key);
+
+        if let Some((destination, end)) = destination {
+            if let Ok(list) = self.get_or_create_list(destination) {
+                match end {
+                    ListEnd::Left => list.push_front(value.clone()),
+                    ListEnd::Right => list.push_back(value.clone()),
+                }
+            }
+        }
+
+        value
+    }
+
+    // 列表中写入了元素之后，按照阻塞的先后顺序把元素交给这个key上的等待者，直到列表被取空
+    // BLMOVE的等待者会把元素写入另一个列表，那个列表上的等待者同样需要处理
+    fn serve_blocked(&mut self, key: &str) {
+        let mut ready = vec![key.to_string()];
+
+        while let Some(key) = ready.pop() {
+            // 目标列表类型不对的等待者会被跳过，和redis一样继续阻塞
+            let mut skipped = 0;
+
+            while let Ok(Some(_)) = self.get_list(&key) {
+                let id = match self.blocked.get(&key).and_then(|queue| queue.get(skipped)) {
+                    Some(&id) => id,
+                    None => break,
+                };
+
+                let destination = self.waiters[&id].destination.clone();
+                if let Some((destination, _)) = &destination {
+                    if self.get_list(destination).is_err() {
+                        skipped += 1;
+                        continue;
+                    }
+                }
+
+                let waiter = self.remove_waiter(id).expect("the waiter is registered");
+                let value = self.move_element(&key, waiter.end, destination.as_ref());
+
+                if let Some((destination, _)) = destination {
+                    ready.push(destination);
+                }
+
+                // 等待者被drop之前一定会先从队列中移除，所以这里的发送不会失败
+                let _ = waiter.tx.send((key.clone(), value));
+            }
+        }
+    }
+
+    // 将等待者从所有key的等待队列中移除，已经被唤醒或者移除过的话返回None
+    fn remove_waiter(&mut self, id: u64) -> Option<Waiter> {
+        let waiter = self.waiters.remove(&id)?;
+
+        for key in &waiter.keys {
+            if let Some(queue) = self.blocked.get_mut(key) {
+                queue.retain(|&other| other != id);
+                if queue.is_empty() {
+                    self.blocked.remove(key);
+                }
+            }
+        }
+
+        Some(waiter)
+    }
+
+    // 和redis一样不保留空的列表、hash、set、有序集合，最后一个元素被删除之后key也随之删除
+    fn remove_if_empty(&mut self, key: &str) {
+        let empty = self
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.value.is_empty());
+
+        if empty {
+            self.remove(key);
+        }
+    }
+
+    fn expire_if_needed(&mut self, key: &str) {
+        let expired = self
+            .entries
+            .get(key)
+            .and_then(|entry| entry.expires_at)
+            .is_some_and(|when| when <= Instant::now());
+
+        if expired {
+            self.remove(key);
+        }
+    }
+
+    // 写入一个不会过期的key-value，原来的过期时间会被清除
+    fn insert(&mut self, key: String, value: Value) {
+        // HaspMap如果insert的key之前有值，会更新这个key对应的value，并将之前的value返回
+        let prev = self.entries.insert(key.clone(), Entry::new(value));
+
+        // 如果之前hashMap存储的值有expirea_at的话，需要将expirations内对应的值也清除掉
+        if let Some(prev) = prev {
+            if let Some(when) = prev.expires_at {
+                self.untrack_expiration(&key, when, prev.volatile_slot);
+            }
+        }
+    }
+
+    // 写入一个完整的条目并保留它的过期时间，key原来的值会被覆盖
+    // 返回值和set_expiration相同，为true时调用方需要通知后台任务
+    fn insert_entry(&mut self, key: String, mut entry: Entry) -> bool {
+        let expires_at = entry.expires_at.take();
+
+        self.remove(&key);
+        self.entries.insert(key.clone(), entry);
+
+        self.set_expiration(&key, expires_at)
+    }
+
+    // RENAME、COPY、RESTORE写入了新的值之后，阻塞在这个key上的客户端和写入了新元素时一样需要被唤醒
+    // 没有客户端阻塞时直接返回，避免读取key更新了访问时间，RESTORE的IDLETIME就会失效
+    fn signal_key_ready(&mut self, key: &str) {
+        if self.blocked.contains_key(key) {
+            self.serve_blocked(key);
+        }
+
+        if self.stream_blocked.contains_key(key) {
+            self.serve_stream_blocked(key);
+        }
+    }
+
+    // 删除key以及它的过期时间，已经过期的key同样会被删除，但是视为不存在而返回None
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        if let Some(when) = entry.expires_at {
+            self.untrack_expiration(key, when, entry.volatile_slot);
+
+            if when <= Instant::now() {
+                return None;
+            }
+        }
+        Some(entry)
+    }
+
+    // 修改key的过期时间，None表示不再过期
+    // 新的过期时间早于原来最早的过期时间时返回true，调用方需要通知后台任务重新计算等待的时间
+    fn set_expiration(&mut self, key: &str, expires_at: Option<Instant>) -> bool {
+        let entry = match self.entries.get_mut(key) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        let slot = entry.volatile_slot;
+        if let Some(prev) = std::mem::replace(&mut entry.expires_at, expires_at) {
+            self.untrack_expiration(key, prev, slot);
+        }
+
+        match expires_at {
+            Some(when) => self.track_expiration(key, when),
+            None => false,
+        }
+    }
+
+    // 按照清除策略记录key的过期时间，key必须已经在entries中
+    // 新的过期时间早于原来最早的过期时间时返回true
+    fn track_expiration(&mut self, key: &str, when: Instant) -> bool {
+        match self.strategy {
+            ExpireStrategy::Ordered => {
+                // 只有当下一个过期时间大于当前时间，才需要通知background_task去清楚
+                let notify = self
+                    .next_expiration()
+                    .map(|expiration| expiration > when)
+                    .unwrap_or(true);
+
+                self.expirations.insert((when, key.to_string()));
+
+                notify
+            }
+            // 抽样的后台任务按照固定的周期运行，不需要通知
+            ExpireStrategy::Sampled => {
+                if let Some(entry) = self.entries.get_mut(key) {
+                    entry.volatile_slot = self.volatile.len();
+                }
+                self.volatile.push(key.to_string());
+
+                false
+            }
+        }
+    }
+
+    // 不再记录key的过期时间，slot是key在volatile中的下标
+    fn untrack_expiration(&mut self, key: &str, when: Instant, slot: usize) {
+        match self.strategy {
+            ExpireStrategy::Ordered => {
+                self.expirations.remove(&(when, key.to_string()));
+            }
+            ExpireStrategy::Sampled => self.remove_volatile_slot(slot),
+        }
+    }
+
+    fn remove_volatile_slot(&mut self, slot: usize) {
+        self.volatile.swap_remove(slot);
+
+        // 原来数组中的最后一个key被移动到了slot的位置，需要更新它记录的下标
+        if let Some(moved) = self.volatile.get(slot) {
+            if let Some(entry) = self.entries.get_mut(moved) {
+                entry.volatile_slot = slot;
+            }
+        }
+    }
+
+    // 修改key对应的字符串，保留原有的过期时间，key不存在时新建一个不会过期的条目
+    fn set_data(&mut self, key: &str, data: Bytes) {
+        match self.entries.get_mut(key) {
+            Some(entry) => entry.value = Value::String(data),
+            None => {
+                self.entries
+                    .insert(key.to_string(), Entry::new(Value::String(data)));
+            }
+        }
+    }
+
+    fn next_expiration(&self) -> Option<Instant> {
+        self.expirations
+            .iter()
+            .next()
+            .map(|expiration| expiration.0)
+    }
+}
+
+impl ListWaiter {
+    // 等待列表中的元素被交给自己，返回弹出元素的key以及元素，Db关闭时返回None
+    pub async fn recv(&mut self) -> Option<(String, Bytes)> {
+        (&mut self.rx).await.ok()
+    }
+
+    // 不再等待，例如超时的时候
+    // 取消之前可能刚好已经被唤醒，此时返回已经弹出的元素，不能让元素丢失
+    pub fn cancel(mut self) -> Option<(String, Bytes)> {
+        self.shared.state.lock().unwrap().remove_waiter(self.id);
+        self.rx.try_recv().ok()
+    }
+}
+
+impl Drop for ListWaiter {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.remove_waiter(self.id);
+
+        // 客户端断开或者服务器关闭之前可能刚好已经被唤醒，元素已经从列表中弹出，需要放回原来的位置
+        // 放回之后可能还有其他客户端在等待这个key
+        if let Ok((key, value)) = self.rx.try_recv() {
+            if self.moved {
+                return;
+            }
+
+            if let Ok(list) = state.get_or_create_list(&key) {
+                match self.end {
+                    ListEnd::Left => list.push_front(value),
+                    ListEnd::Right => list.push_back(value),
+                }
+                state.serve_blocked(&key);
+            }
+        }
+    }
+}
+
+impl StreamWaiter {
+    // 等待有新条目写入，Db关闭时返回false
+    pub async fn recv(&mut self) -> bool {
+        (&mut self.rx).await.is_ok()
+    }
+}
+
+impl Drop for StreamWaiter {
+    fn drop(&mut self) {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .remove_stream_reader(self.id);
+    }
+}
+
+impl Entry {
+    fn new(value: Value) -> Entry {
+        Entry {
+            value,
+            expires_at: None,
+            volatile_slot: 0,
+            accessed_at: Instant::now(),
+            freq: LFU_INIT_VAL,
+        }
+    }
+
+    // 记录一次访问，计数器先按照距离上一次访问的时间衰减，再以1 / ((freq - LFU_INIT_VAL) * LFU_LOG_FACTOR + 1)的概率加1
+    fn touch(&mut self, rng: &mut XorShift) {
+        let now = Instant::now();
+        let freq = self.freq(now);
+
+        let base = freq.saturating_sub(LFU_INIT_VAL) as f64;
+        let increase = freq < u8::MAX && rng.next_f64() < 1.0 / (base * LFU_LOG_FACTOR + 1.0);
+
+        self.freq = if increase { freq + 1 } else { freq };
+        self.accessed_at = now;
+    }
+
+    // 衰减之后的访问频率计数器
+    fn freq(&self, now: Instant) -> u8 {
+        let periods =
+            now.saturating_duration_since(self.accessed_at).as_secs() / LFU_DECAY_TIME.as_secs();
+        self.freq.saturating_sub(periods.min(u8::MAX as u64) as u8)
+    }
+
+    fn idle_time(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.accessed_at)
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|when| when <= now)
+    }
+}
+
+impl Value {
+    // OBJECT ENCODING返回的编码名，列表和字符串只是按照redis的规则推算，实际的存储方式并没有区别
+    fn encoding(&self) -> &'static str {
+        match self {
+            Value::String(data)
+                if parse_i64(data).is_some_and(|v| v.to_string().as_bytes() == &data[..]) =>
+            {
+                "int"
+            }
+            Value::String(data) if data.len() <= EMBSTR_SIZE_LIMIT => "embstr",
+            Value::String(_) => "raw",
+            Value::List(list)
+                if list.iter().map(|item| item.len()).sum::<usize>() <= LIST_LISTPACK_SIZE =>
+            {
+                "listpack"
+            }
+            Value::List(_) => "quicklist",
+            Value::Hash(hash) => hash.encoding(),
+            Value::Set(set) => set.encoding(),
+            Value::ZSet(_) => "skiplist",
+            Value::Stream(_) => "stream",
+        }
+    }
+
+    // TYPE命令返回的类型名
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::ZSet(_) => "zset",
+            Value::Stream(_) => "stream",
+        }
+    }
+
+    fn as_string(&self) -> crate::Result<&Bytes> {
+        match self {
+            Value::String(data) => Ok(data),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    fn as_list(&self) -> crate::Result<&VecDeque<Bytes>> {
+        match self {
+            Value::List(list) => Ok(list),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    fn as_list_mut(&mut self) -> crate::Result<&mut VecDeque<Bytes>> {
+        match self {
+            Value::List(list) => Ok(list),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    fn as_hash(&self) -> crate::Result<&Hash> {
+        match self {
+            Value::Hash(hash) => Ok(hash),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    fn as_hash_mut(&mut self) -> crate::Result<&mut Hash> {
+        match self {
+            Value::Hash(hash) => Ok(hash),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    fn as_set(&self) -> crate::Result<&Set> {
+        match self {
+            Value::Set(set) => Ok(set),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    fn as_set_mut(&mut self) -> crate::Result<&mut Set> {
+        match self {
+            Value::Set(set) => Ok(set),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    fn as_zset(&self) -> crate::Result<&ZSet> {
+        match self {
+            Value::ZSet(zset) => Ok(zset),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    fn as_zset_mut(&mut self) -> crate::Result<&mut ZSet> {
+        match self {
+            Value::ZSet(zset) => Ok(zset),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    fn as_stream(&self) -> crate::Result<&Stream> {
+        match self {
+            Value::Stream(stream) => Ok(stream),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    fn as_stream_mut(&mut self) -> crate::Result<&mut Stream> {
+        match self {
+            Value::Stream(stream) => Ok(stream),
+            _ => Err(WRONGTYPE.into()),
+        }
+    }
+
+    // 集合类型中已经没有元素，字符串不会被视为空
+    // 和redis一样，stream的条目全部被裁剪掉之后仍然保留，消费者组的信息不能丢失
+    fn is_empty(&self) -> bool {
+        match self {
+            Value::String(_) | Value::Stream(_) => false,
+            Value::List(list) => list.is_empty(),
+            Value::Hash(hash) => hash.len() == 0,
+            Value::Set(set) => set.len() == 0,
+            Value::ZSet(zset) => zset.len() == 0,
+        }
+    }
+
+    // 值占用的内存的粗略估计，UNLINK用它判断是否需要交给后台线程释放
+    fn mem_usage(&self) -> usize {
+        match self {
+            Value::String(data) => data.len(),
+            Value::List(list) => list
+                .iter()
+                .map(|item| item.len() + std::mem::size_of::<Bytes>())
+                .sum(),
+            Value::Hash(hash) => hash.mem_usage(),
+            Value::Set(set) => set.mem_usage(),
+            Value::ZSet(zset) => zset.mem_usage(),
+            Value::Stream(stream) => stream.mem_usage(),
+        }
+    }
+}
+
+impl ZInput<'_> {
+    fn len(&self) -> usize {
+        match self {
+            ZInput::Set(set) => set.len(),
+            ZInput::ZSet(zset) => zset.len(),
+        }
+    }
+
+    fn score(&self, member: &[u8]) -> Option<f64> {
+        match self {
+            ZInput::Set(set) => set.contains(member).then_some(1.0),
+            ZInput::ZSet(zset) => zset.score(member),
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (Bytes, f64)> + '_> {
+        match self {
+            ZInput::Set(set) => Box::new(set.iter().map(|member| (member, 1.0))),
+            ZInput::ZSet(zset) => {
+                Box::new(zset.iter().map(|(member, score)| (member.clone(), score)))
+            }
+        }
+    }
+}
+
+impl Aggregate {
+    // 和redis一样，inf加上-inf得到的nan视为0
+    fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            Aggregate::Sum => {
+                let sum = a + b;
+                if sum.is_nan() {
+                    0.0
+                } else {
+                    sum
+                }
+            }
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+        }
+    }
+}
+
+impl Default for EncodingConfig {
+    fn default() -> Self {
+        EncodingConfig {
+            hash_max_listpack_entries: 128,
+            hash_max_listpack_value: 64,
+            set_max_intset_entries: 512,
+        }
+    }
+}
+
+impl FromStr for ExpireStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "ordered" => Ok(ExpireStrategy::Ordered),
+            "sampled" => Ok(ExpireStrategy::Sampled),
+            _ => Err(format!(
+                "unknown expire strategy '{}', expected ordered or sampled",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ExpireStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpireStrategy::Ordered => "ordered".fmt(f),
+            ExpireStrategy::Sampled => "sampled".fmt(f),
+        }
+    }
+}
+
+impl Expiry {
+    // 计算出具体的过期时间点，None表示不会过期，Keep需要由调用方根据原来的过期时间处理
+    fn deadline(&self, now: Instant) -> Option<Instant> {
+        match *self {
+            Expiry::Persist | Expiry::Keep => None,
+            Expiry::After(duration) => Some(now + duration),
+            // unix时间转换成tokio的Instant，已经过去的时间会得到now
+            Expiry::At(at) => {
+                let duration = at.duration_since(SystemTime::now()).unwrap_or_default();
+                Some(now + duration)
+            }
+        }
+    }
+}
+
+// 将LRANGE、LTRIM的start stop转换成列表中[start, stop]闭区间的下标，范围为空时返回None
+// 负数表示从末尾开始计算的位置，超出列表的部分会被截断
+fn list_range(len: usize, start: i64, stop: i64) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+
+    if start > stop || start >= len {
+        return None;
+    }
+
+    Some((start as usize, stop as usize))
+}
+
+// 将LINDEX、LSET的index转换成列表中的下标，超出范围时返回None
+fn list_index(len: usize, index: i64) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+// INCRBYFLOAT的结果，redis使用long double计算，然后按照"%.17Lf"格式化成小数点后17位，
+// 再去掉末尾的0，不使用科学计数法。0.1加0.2在redis中得到的是0.3，而f64直接相加是0.30000000000000004，
+// 这里把两个数按照各自最短的十进制表示精确相加，再舍入到小数点后17位，
+// 同时和long double的精度一样最多保留19位有效数字，大部分情况下结果和redis一致，
+// 只有long double无法精确表示的值在最后几位上可能不同
+fn add_float(a: f64, b: f64) -> String {
+    const DIGITS: u32 = 17;
+    const FRACTION_DIGITS: i32 = 17;
+    const MAX_DIGITS: u32 = 19;
+
+    // 转换成mantissa * 10^exp的形式，mantissa固定为17位，exp之外再返回最高位的指数用于比较大小
+    fn decimal(v: f64) -> (i128, i32, i32) {
+        let s = format!("{:e}", v);
+        let (mantissa, exp) = s.split_once('e').expect("LowerExp always has an exponent");
+        let exp: i32 = exp.parse().expect("LowerExp exponent is an integer");
+        let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+        let pad = DIGITS - digits.len() as u32;
+        let mut m = digits.parse::<i128>().expect("at most 17 digits") * 10i128.pow(pad);
+        if v < 0.0 {
+            m = -m;
+        }
+        (m, exp - DIGITS as i32 + 1, exp)
+    }
+
+    let (ma, ea, ta) = decimal(a);
+    let (mb, eb, tb) = decimal(b);
+
+    // 最高位相差超过20位时较小的数不会影响保留下来的19位有效数字
+    let (mut m, mut e) = match (ma, mb) {
+        (0, _) => (mb, eb),
+        (_, 0) => (ma, ea),
+        _ if ta - tb > 20 => (ma, ea),
+        _ if tb - ta > 20 => (mb, eb),
+        _ if ea >= eb => (ma * 10i128.pow((ea - eb) as u32) + mb, eb),
+        _ => (ma + mb * 10i128.pow((eb - ea) as u32), ea),
+    };
+
+    // 四舍五入到小数点后17位，并且不超过19位有效数字
+    let len = m.unsigned_abs().to_string().len() as u32;
+    let drop = len
+        .saturating_sub(MAX_DIGITS)
+        .max((-e - FRACTION_DIGITS).max(0) as u32);
+    if drop > len {
+        // 比0.5 * 10^-17还要小
+        m = 0;
+    } else if drop > 0 {
+        let scale = 10i128.pow(drop);
+        let rem = m % scale;
+        m /= scale;
+        if rem.abs() * 2 >= scale {
+            m += rem.signum();
+        }
+        e += drop as i32;
+    }
+
+    if m == 0 {
+        return "0".to_string();
+    }
+
+    let digits = m.unsigned_abs().to_string();
+    let mut out = if m < 0 {
+        "-".to_string()
+    } else {
+        String::new()
+    };
+    if e >= 0 {
+        out.push_str(&digits);
+        out.extend(std::iter::repeat_n('0', e as usize));
+        return out;
+    }
+
+    let point = digits.len() as i32 + e;
+    if point > 0 {
+        out.push_str(&digits[..point as usize]);
+        out.push('.');
+        out.push_str(&digits[point as usize..]);
+    } else {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', -point as usize));
+        out.push_str(&digits);
+    }
+
+    let trimmed = out.trim_end_matches('0').trim_end_matches('.').len();
+    out.truncate(trimmed);
+    out
+}
+
+// APPEND、SETRANGE之后的字符串长度不能超过MAX_STRING_LEN
+fn check_string_len(len: usize) -> crate::Result<()> {
+    if len > MAX_STRING_LEN {
+        return Err(TOO_LARGE.into());
+    }
+    Ok(())
+}
+
+fn no_such_group(key: &str, group: &str) -> crate::Error {
+    format!(
+        "NOGROUP No such consumer group '{}' for key name '{}'",
+        group, key
+    )
+    .into()
+}
+
+// 输入的分数乘以权重，和redis一样，0乘以inf得到的nan视为0
+fn weighted(score: f64, weight: f64) -> f64 {
+    let score = score * weight;
+    if score.is_nan() {
+        0.0
+    } else {
+        score
+    }
+}
+
+// 从set中随机选择最多count个不同的成员
+// 和redis一样，count接近set的大小时复制所有成员之后打乱顺序，否则反复随机选择直到选够count个不同的成员
+fn sample_members(set: &Set, count: usize, rng: &mut XorShift) -> Vec<Bytes> {
+    if count >= set.len() {
+        return set.iter().collect();
+    }
+
+    if count * 3 > set.len() {
+        let mut members: Vec<Bytes> = set.iter().collect();
+        // 只需要打乱前count个位置
+        for i in 0..count {
+            let j = i + (rng.next() % (members.len() - i) as u64) as usize;
+            members.swap(i, j);
+        }
+        members.truncate(count);
+        return members;
+    }
+
+    let mut picked = HashSet::with_capacity(count);
+    let mut members = Vec::with_capacity(count);
+    while members.len() < count {
+        let member = set.random(rng).expect("the set is not empty");
+        if picked.insert(member.clone()) {
+            members.push(member);
+        }
+    }
+    members
+}
+
+// 后台持续运行的任务，用于清除过期的key
+async fn purge_expired_tasks(shared: Arc<Shared>) {
+    while !shared.is_shutdown() {
+        // 如果purge_expired_keys返回了时间，说明暂时还没有过期的值
+        if let Some(when) = shared.purge_expired_keys() {
+            tokio::select! {
+                _ = sleep_until(when) => {}
+                _ = shared.bacground_task.notified() => {}
+            }
+            // 如果没有返回时间，说明没有即将过期的key了
+        } else {
+            // 等待其他线程的通知
+            shared.bacground_task.notified().await;
+        }
+    }
+
+    debug!("Purge background task shut down");
+}
+
+// ExpireStrategy::Sampled使用的后台任务
+// 每个周期抽查一批key，过期的比例较高时继续抽查，直到比例降下来或者用完这个周期的时间
+async fn active_expire_tasks(shared: Arc<Shared>) {
+    let mut rng = XorShift::new();
+
+    while !shared.is_shutdown() {
+        let start = std::time::Instant::now();
+
+        // 每次抽查之间会释放锁，其他连接不会被长时间阻塞
+        while shared.sample_expired_keys(&mut rng) && start.elapsed() < ACTIVE_EXPIRE_CYCLE_TIME {}
+
+        tokio::select! {
+            _ = sleep(ACTIVE_EXPIRE_INTERVAL) => {}
+            _ = shared.bacground_task.notified() => {}
+        }
+    }
+
+    debug!("Active expire background task shut down");
+}
+
+// 抽样用的伪随机数生成器(xorshift64)，不需要密码学强度，也就不必为此引入rand
+#[derive(Debug, Clone)]
+struct XorShift(u64);
+
+impl XorShift {
+    fn new() -> XorShift {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+
+        // 种子不能为0，否则之后生成的数都是0
+        XorShift(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    // [0, 1)之间的浮点数，取高53位作为尾数
+    fn next_f64(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
 }
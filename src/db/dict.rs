@@ -0,0 +1,217 @@
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    mem,
+};
+
+use super::XorShift;
+
+// 哈希表最少的桶数量
+const INITIAL_SIZE: usize = 4;
+
+// 和redis的dict一样使用链地址法的哈希表，桶的数量总是2的幂，元素所在的桶由哈希值的低位决定
+// 不直接使用HashMap是因为SCAN需要一个扩容、缩容之后依然有效的游标，这依赖于桶和哈希值低位之间的对应关系
+#[derive(Debug, Clone)]
+pub(crate) struct Dict<K, V> {
+    buckets: Vec<Vec<(K, V)>>,
+    len: usize,
+    hasher: RandomState,
+}
+
+impl<K: Hash + Eq, V> Dict<K, V> {
+    pub(crate) fn new() -> Dict<K, V> {
+        Dict {
+            buckets: Vec::new(),
+            len: 0,
+            hasher: RandomState::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(crate) fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.is_empty() {
+            return None;
+        }
+
+        self.buckets[self.bucket(key)]
+            .iter()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, v)| v)
+    }
+
+    pub(crate) fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.is_empty() {
+            return None;
+        }
+
+        let bucket = self.bucket(key);
+        self.buckets[bucket]
+            .iter_mut()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, v)| v)
+    }
+
+    // 写入key-value，key已经存在时返回原来的值
+    pub(crate) fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(prev) = self.get_mut(&key) {
+            return Some(mem::replace(prev, value));
+        }
+
+        self.grow_if_needed();
+
+        let bucket = self.bucket(&key);
+        self.buckets[bucket].push((key, value));
+        self.len += 1;
+
+        None
+    }
+
+    // key不存在时写入f返回的值，返回key对应的值的可变引用
+    pub(crate) fn get_or_insert_with(&mut self, key: K, f: impl FnOnce() -> V) -> &mut V {
+        if !self.is_empty() {
+            let bucket = self.bucket(&key);
+            if let Some(pos) = self.buckets[bucket].iter().position(|(k, _)| *k == key) {
+                return &mut self.buckets[bucket][pos].1;
+            }
+        }
+
+        self.grow_if_needed();
+
+        let bucket = self.bucket(&key);
+        self.buckets[bucket].push((key, f()));
+        self.len += 1;
+
+        &mut self.buckets[bucket].last_mut().expect("just pushed").1
+    }
+
+    pub(crate) fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.is_empty() {
+            return None;
+        }
+
+        let bucket = self.bucket(key);
+        let pos = self.buckets[bucket]
+            .iter()
+            .position(|(k, _)| k.borrow() == key)?;
+        let (_, value) = self.buckets[bucket].swap_remove(pos);
+        self.len -= 1;
+
+        self.shrink_if_needed();
+
+        Some(value)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.buckets = Vec::new();
+        self.len = 0;
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.buckets.iter().flatten().map(|(k, v)| (k, v))
+    }
+
+    // 和redis的dictGetRandomKey一样，先随机选择一个非空的桶，再从桶中随机选择一个元素
+    // 缩容保证了至少1/8的桶不为空，不会循环太多次。和redis一样，所在的链越短的元素被选中的概率越高，
+    // 这里只需要大致随机，不保证严格均匀
+    pub(crate) fn random(&self, rng: &mut XorShift) -> Option<(&K, &V)> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mask = self.buckets.len() as u64 - 1;
+        loop {
+            let bucket = &self.buckets[(rng.next() & mask) as usize];
+            if !bucket.is_empty() {
+                let (k, v) = &bucket[(rng.next() % bucket.len() as u64) as usize];
+                return Some((k, v));
+            }
+        }
+    }
+
+    // 从cursor对应的桶开始逐个访问桶，直到访问了至少count个元素，返回下一次的游标，0表示遍历结束
+    // 和redis的dictScan一样，游标按照二进制位反转之后加一的顺序前进，所以两次调用之间即使哈希表扩容或者缩容，
+    // 已经访问过的桶对应的元素也不会再被访问，一直存在的元素至少会被返回一次，代价是缩容时可能会返回重复的元素
+    pub(crate) fn scan(&self, mut cursor: u64, count: usize, mut f: impl FnMut(&K, &V)) -> u64 {
+        if self.buckets.is_empty() {
+            return 0;
+        }
+
+        let mask = (self.buckets.len() - 1) as u64;
+        let mut visited = 0;
+
+        // 和redis一样，连续遇到空桶时最多访问count * 10个桶，避免一次调用耗时太长
+        for _ in 0..count.saturating_mul(10).max(1) {
+            let bucket = &self.buckets[(cursor & mask) as usize];
+            for (k, v) in bucket {
+                f(k, v);
+            }
+            visited += bucket.len();
+
+            // 把掩码之外的高位置1，反转之后加一再反转回来，相当于在高位上进位
+            cursor |= !mask;
+            cursor = cursor.reverse_bits().wrapping_add(1).reverse_bits();
+
+            if cursor == 0 || visited >= count {
+                break;
+            }
+        }
+
+        cursor
+    }
+
+    fn bucket<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.hasher.hash_one(key) as usize & (self.buckets.len() - 1)
+    }
+
+    // 元素的数量达到桶的数量时扩容一倍
+    fn grow_if_needed(&mut self) {
+        if self.buckets.is_empty() {
+            self.resize(INITIAL_SIZE);
+        } else if self.len >= self.buckets.len() {
+            self.resize(self.buckets.len() * 2);
+        }
+    }
+
+    // 和redis一样，元素的数量不到桶的数量的1/8时缩容，避免大量删除之后浪费内存
+    fn shrink_if_needed(&mut self) {
+        if self.len == 0 {
+            self.buckets = Vec::new();
+        } else if self.buckets.len() > INITIAL_SIZE && self.len * 8 < self.buckets.len() {
+            self.resize(self.len.max(INITIAL_SIZE).next_power_of_two());
+        }
+    }
+
+    fn resize(&mut self, size: usize) {
+        let buckets = (0..size).map(|_| Vec::new()).collect();
+        let old = mem::replace(&mut self.buckets, buckets);
+
+        for (k, v) in old.into_iter().flatten() {
+            let bucket = self.bucket(&k);
+            self.buckets[bucket].push((k, v));
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::VecDeque;
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use super::{hash::Hash, set::Set, stream::Stream, zset::ZSet, EncodingConfig, Value};
+
+// DUMP的格式：1字节的类型，之后是类型对应的内容，最后是2字节的版本号和8字节的CRC64校验和
+// 所有的整数都使用小端序，字符串和集合都以8字节的长度开头
+// 格式只保证同一个版本之间兼容，RESTORE遇到不认识的版本号时拒绝恢复
+const DUMP_VERSION: u16 = 1;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_HASH: u8 = 2;
+const TYPE_SET: u8 = 3;
+const TYPE_ZSET: u8 = 4;
+const TYPE_STREAM: u8 = 5;
+
+// 和redis相同的CRC-64/Jones，输入输出都按位反转，初始值为0
+// 这里是按位反转之后的多项式，"123456789"的校验和是0xe9c6d914c4b8d9ca
+const CRC64_POLY: u64 = 0x95ac_9329_ac4b_c9b5;
+
+const CRC64_TABLE: [u64; 256] = {
+    let mut table = [0u64; 256];
+
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC64_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+const BAD_PAYLOAD: &str = "ERR DUMP payload version or checksum are wrong";
+const BAD_FORMAT: &str = "ERR Bad data format";
+
+// 序列化时使用的缓冲区
+pub(crate) struct Writer {
+    buf: BytesMut,
+}
+
+// 反序列化时使用，数据不足时返回None，不会panic
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+// 将值序列化成DUMP的格式
+pub(crate) fn dump(value: &Value) -> Bytes {
+    let mut w = Writer {
+        buf: BytesMut::new(),
+    };
+
+    match value {
+        Value::String(data) => {
+            w.u8(TYPE_STRING);
+            w.bytes(data);
+        }
+        Value::List(list) => {
+            w.u8(TYPE_LIST);
+            w.len(list.len());
+            for item in list {
+                w.bytes(item);
+            }
+        }
+        Value::Hash(hash) => {
+            w.u8(TYPE_HASH);
+            w.len(hash.len());
+            for (field, value) in hash.iter() {
+                w.bytes(field);
+                w.bytes(value);
+            }
+        }
+        Value::Set(set) => {
+            w.u8(TYPE_SET);
+            w.len(set.len());
+            for member in set.iter() {
+                w.bytes(&member);
+            }
+        }
+        Value::ZSet(zset) => {
+            w.u8(TYPE_ZSET);
+            w.len(zset.len());
+            for (member, score) in zset.iter() {
+                w.bytes(member);
+                w.f64(score);
+            }
+        }
+        Value::Stream(stream) => {
+            w.u8(TYPE_STREAM);
+            stream.dump(&mut w);
+        }
+    }
+
+    w.buf.put_u16_le(DUMP_VERSION);
+    let checksum = crc64(&w.buf);
+    w.buf.put_u64_le(checksum);
+
+    w.buf.freeze()
+}
+
+// 从DUMP的结果恢复出值，集合类型的编码按照当前的配置重新决定
+pub(crate) fn restore(payload: &[u8], config: &EncodingConfig) -> crate::Result<Value> {
+    let (body, checksum) = payload.split_last_chunk::<8>().ok_or(BAD_PAYLOAD)?;
+    if crc64(body) != u64::from_le_bytes(*checksum) {
+        return Err(BAD_PAYLOAD.into());
+    }
+
+    let (body, version) = body.split_last_chunk::<2>().ok_or(BAD_PAYLOAD)?;
+    if u16::from_le_bytes(*version) != DUMP_VERSION {
+        return Err(BAD_PAYLOAD.into());
+    }
+
+    let mut r = Reader { buf: body };
+    let value = read_value(&mut r, config).ok_or(BAD_FORMAT)?;
+
+    // 内容之后不能有多余的数据
+    if !r.buf.is_empty() {
+        return Err(BAD_FORMAT.into());
+    }
+
+    Ok(value)
+}
+
+// 除了stream以外，DUMP出来的集合类型都不会是空的
+fn read_value(r: &mut Reader, config: &EncodingConfig) -> Option<Value> {
+    let value = match r.u8()? {
+        TYPE_STRING => Value::String(r.bytes()?),
+        TYPE_LIST => {
+            let mut list = VecDeque::new();
+            for _ in 0..r.len()? {
+                list.push_back(r.bytes()?);
+            }
+            Value::List(list)
+        }
+        TYPE_HASH => {
+            let mut hash = Hash::new();
+            for _ in 0..r.len()? {
+                hash.insert(r.bytes()?, r.bytes()?, config);
+            }
+            Value::Hash(hash)
+        }
+        TYPE_SET => {
+            let mut set = Set::new();
+            for _ in 0..r.len()? {
+                set.insert(r.bytes()?, config);
+            }
+            Value::Set(set)
+        }
+        TYPE_ZSET => {
+            let mut zset = ZSet::new();
+            for _ in 0..r.len()? {
+                let member = r.bytes()?;
+                let score = r.f64()?;
+                if score.is_nan() {
+                    return None;
+                }
+                zset.insert(member, score);
+            }
+            Value::ZSet(zset)
+        }
+        TYPE_STREAM => return Stream::restore(r).map(Value::Stream),
+        _ => return None,
+    };
+
+    if value.is_empty() {
+        return None;
+    }
+
+    Some(value)
+}
+
+fn crc64(data: &[u8]) -> u64 {
+    data.iter().fold(0, |crc, &b| {
+        CRC64_TABLE[((crc ^ b as u64) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+impl Writer {
+    pub(crate) fn u8(&mut self, v: u8) {
+        self.buf.put_u8(v);
+    }
+
+    pub(crate) fn u64(&mut self, v: u64) {
+        self.buf.put_u64_le(v);
+    }
+
+    pub(crate) fn f64(&mut self, v: f64) {
+        self.buf.put_f64_le(v);
+    }
+
+    pub(crate) fn len(&mut self, len: usize) {
+        self.u64(len as u64);
+    }
+
+    pub(crate) fn bytes(&mut self, data: &[u8]) {
+        self.len(data.len());
+        self.buf.put_slice(data);
+    }
+}
+
+impl Reader<'_> {
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        let (&v, rest) = self.buf.split_first()?;
+        self.buf = rest;
+        Some(v)
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        let (v, rest) = self.buf.split_first_chunk::<8>()?;
+        self.buf = rest;
+        Some(u64::from_le_bytes(*v))
+    }
+
+    pub(crate) fn f64(&mut self) -> Option<f64> {
+        self.u64().map(f64::from_bits)
+    }
+
+    // 长度来自不可信的数据，调用方不能用它预先分配内存
+    pub(crate) fn len(&mut self) -> Option<usize> {
+        usize::try_from(self.u64()?).ok()
+    }
+
+    pub(crate) fn bytes(&mut self) -> Option<Bytes> {
+        let len = self.len()?;
+        if len > self.buf.len() {
+            return None;
+        }
+
+        let (data, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Some(Bytes::copy_from_slice(data))
+    }
+
+    pub(crate) fn string(&mut self) -> Option<String> {
+        String::from_utf8(self.bytes()?.to_vec()).ok()
+    }
+}
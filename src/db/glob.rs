@@ -0,0 +1,71 @@
+// 和redis的stringmatchlen规则相同的glob匹配
+// *匹配任意多个字符，?匹配一个字符，[abc]、[^abc]、[a-z]匹配一个字符集合中的字符，\用于转义
+pub(crate) fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // 最近一次遇到的*的位置，以及*之后开始匹配的字符串位置
+    // 后面匹配失败时回溯到这里，让*多匹配一个字符
+    let mut star: Option<(usize, usize)> = None;
+
+    while s < string.len() {
+        if let Some(&c) = pattern.get(p) {
+            let next = match c {
+                b'*' => {
+                    star = Some((p, s));
+                    p += 1;
+                    continue;
+                }
+                b'?' => Some(p + 1),
+                b'[' => match_class(pattern, p + 1, string[s]),
+                b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == string[s]).then_some(p + 2),
+                c => (c == string[s]).then_some(p + 1),
+            };
+
+            if let Some(next) = next {
+                p = next;
+                s += 1;
+                continue;
+            }
+        }
+
+        match star {
+            Some((star_p, star_s)) => {
+                p = star_p + 1;
+                s = star_s + 1;
+                star = Some((star_p, star_s + 1));
+            }
+            None => return false,
+        }
+    }
+
+    // 字符串已经匹配完，剩下的模式只能是*
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+// p指向'['之后的位置，匹配成功时返回']'之后的位置
+fn match_class(pattern: &[u8], mut p: usize, c: u8) -> Option<usize> {
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+
+    let mut matched = false;
+    while p < pattern.len() && pattern[p] != b']' {
+        if pattern[p] == b'\\' && p + 1 < pattern.len() {
+            matched |= pattern[p + 1] == c;
+            p += 2;
+        } else if p + 2 < pattern.len() && pattern[p + 1] == b'-' {
+            let (start, end) = (
+                pattern[p].min(pattern[p + 2]),
+                pattern[p].max(pattern[p + 2]),
+            );
+            matched |= (start..=end).contains(&c);
+            p += 3;
+        } else {
+            matched |= pattern[p] == c;
+            p += 1;
+        }
+    }
+
+    // 和redis一样，缺少']'时字符集合一直延续到模式的结尾
+    (matched != negate).then_some((p + 1).min(pattern.len()))
+}
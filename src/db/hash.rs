@@ -0,0 +1,126 @@
+use std::mem;
+
+use bytes::Bytes;
+
+use super::{dict::Dict, EncodingConfig};
+
+// hash类型的值有两种编码
+#[derive(Debug, Clone)]
+pub(crate) enum Hash {
+    // 和redis的listpack一样，field较少时按照写入的顺序保存在数组中，查找时线性扫描，占用的内存更少
+    Listpack(Vec<(Bytes, Bytes)>),
+    // field的数量或者长度超过EncodingConfig中的阈值之后转换成哈希表，之后不会再转换回来
+    Table(Dict<Bytes, Bytes>),
+}
+
+impl Hash {
+    pub(crate) fn new() -> Hash {
+        Hash::Listpack(Vec::new())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Hash::Listpack(entries) => entries.len(),
+            Hash::Table(dict) => dict.len(),
+        }
+    }
+
+    pub(crate) fn get(&self, field: &[u8]) -> Option<&Bytes> {
+        match self {
+            Hash::Listpack(entries) => entries
+                .iter()
+                .find(|(f, _)| f == field)
+                .map(|(_, value)| value),
+            Hash::Table(dict) => dict.get(field),
+        }
+    }
+
+    pub(crate) fn contains(&self, field: &[u8]) -> bool {
+        self.get(field).is_some()
+    }
+
+    // 写入field-value，field原来不存在时返回true
+    pub(crate) fn insert(&mut self, field: Bytes, value: Bytes, config: &EncodingConfig) -> bool {
+        let too_long = field.len() > config.hash_max_listpack_value
+            || value.len() > config.hash_max_listpack_value;
+
+        let added = match self {
+            Hash::Listpack(entries) => match entries.iter_mut().find(|(f, _)| *f == field) {
+                Some((_, prev)) => {
+                    *prev = value;
+                    false
+                }
+                None => {
+                    entries.push((field, value));
+                    true
+                }
+            },
+            Hash::Table(dict) => dict.insert(field, value).is_none(),
+        };
+
+        if too_long || self.len() > config.hash_max_listpack_entries {
+            self.convert_to_table();
+        }
+
+        added
+    }
+
+    // 删除field，field存在时返回true
+    pub(crate) fn remove(&mut self, field: &[u8]) -> bool {
+        match self {
+            Hash::Listpack(entries) => match entries.iter().position(|(f, _)| f == field) {
+                Some(pos) => {
+                    entries.remove(pos);
+                    true
+                }
+                None => false,
+            },
+            Hash::Table(dict) => dict.remove(field).is_some(),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> Box<dyn Iterator<Item = (&Bytes, &Bytes)> + '_> {
+        match self {
+            Hash::Listpack(entries) => Box::new(entries.iter().map(|(f, v)| (f, v))),
+            Hash::Table(dict) => Box::new(dict.iter()),
+        }
+    }
+
+    // 和redis一样，listpack编码时一次返回所有的field，游标总是0
+    pub(crate) fn scan(&self, cursor: u64, count: usize, mut f: impl FnMut(&Bytes, &Bytes)) -> u64 {
+        match self {
+            Hash::Listpack(entries) => {
+                for (field, value) in entries {
+                    f(field, value);
+                }
+                0
+            }
+            Hash::Table(dict) => dict.scan(cursor, count, f),
+        }
+    }
+
+    // OBJECT ENCODING返回的编码名
+    pub(crate) fn encoding(&self) -> &'static str {
+        match self {
+            Hash::Listpack(_) => "listpack",
+            Hash::Table(_) => "hashtable",
+        }
+    }
+
+    // 占用内存的粗略估计
+    pub(crate) fn mem_usage(&self) -> usize {
+        self.iter()
+            .map(|(field, value)| field.len() + value.len() + 2 * mem::size_of::<Bytes>())
+            .sum()
+    }
+
+    fn convert_to_table(&mut self) {
+        if let Hash::Listpack(entries) = self {
+            let mut dict = Dict::new();
+            for (field, value) in mem::take(entries) {
+                dict.insert(field, value);
+            }
+            *self = Hash::Table(dict);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::mem;
+
+use bytes::Bytes;
+
+use super::{dict::Dict, EncodingConfig, XorShift};
+use crate::parse::parse_i64;
+
+// set类型的值有两种编码
+#[derive(Debug, Clone)]
+pub(crate) enum Set {
+    // 和redis的intset一样，所有成员都是整数并且数量较少时保存在有序数组中，查找时二分查找，占用的内存更少
+    IntSet(Vec<i64>),
+    // 写入了非整数的成员或者成员数量超过EncodingConfig中的阈值之后转换成哈希表，之后不会再转换回来
+    Table(Dict<Bytes, ()>),
+}
+
+impl Set {
+    pub(crate) fn new() -> Set {
+        Set::IntSet(Vec::new())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Set::IntSet(ints) => ints.len(),
+            Set::Table(dict) => dict.len(),
+        }
+    }
+
+    pub(crate) fn contains(&self, member: &[u8]) -> bool {
+        match self {
+            Set::IntSet(ints) => as_int(member).is_some_and(|v| ints.binary_search(&v).is_ok()),
+            Set::Table(dict) => dict.get(member).is_some(),
+        }
+    }
+
+    // 写入成员，成员原来不存在时返回true
+    pub(crate) fn insert(&mut self, member: Bytes, config: &EncodingConfig) -> bool {
+        if let Set::IntSet(ints) = self {
+            match as_int(&member) {
+                Some(v) => match ints.binary_search(&v) {
+                    Ok(_) => return false,
+                    Err(pos) if ints.len() < config.set_max_intset_entries => {
+                        ints.insert(pos, v);
+                        return true;
+                    }
+                    Err(_) => self.convert_to_table(),
+                },
+                None => self.convert_to_table(),
+            }
+        }
+
+        match self {
+            Set::Table(dict) => dict.insert(member, ()).is_none(),
+            Set::IntSet(_) => unreachable!("the set has been converted to a table"),
+        }
+    }
+
+    // 删除成员，成员存在时返回true
+    pub(crate) fn remove(&mut self, member: &[u8]) -> bool {
+        match self {
+            Set::IntSet(ints) => match as_int(member).map(|v| ints.binary_search(&v)) {
+                Some(Ok(pos)) => {
+                    ints.remove(pos);
+                    true
+                }
+                _ => false,
+            },
+            Set::Table(dict) => dict.remove(member).is_some(),
+        }
+    }
+
+    // intset中的整数在遍历时才转换成字符串
+    pub(crate) fn iter(&self) -> Box<dyn Iterator<Item = Bytes> + '_> {
+        match self {
+            Set::IntSet(ints) => Box::new(ints.iter().map(|v| Bytes::from(v.to_string()))),
+            Set::Table(dict) => Box::new(dict.iter().map(|(member, _)| member.clone())),
+        }
+    }
+
+    // 和redis一样，intset编码时一次返回所有的成员，游标总是0
+    pub(crate) fn scan(&self, cursor: u64, count: usize, mut f: impl FnMut(&Bytes)) -> u64 {
+        match self {
+            Set::IntSet(ints) => {
+                for v in ints {
+                    f(&Bytes::from(v.to_string()));
+                }
+                0
+            }
+            Set::Table(dict) => dict.scan(cursor, count, |member, _| f(member)),
+        }
+    }
+
+    // 随机返回一个成员，set为空时返回None
+    pub(crate) fn random(&self, rng: &mut XorShift) -> Option<Bytes> {
+        match self {
+            Set::IntSet(ints) if ints.is_empty() => None,
+            Set::IntSet(ints) => {
+                let v = ints[(rng.next() % ints.len() as u64) as usize];
+                Some(Bytes::from(v.to_string()))
+            }
+            Set::Table(dict) => dict.random(rng).map(|(member, _)| member.clone()),
+        }
+    }
+
+    // OBJECT ENCODING返回的编码名
+    pub(crate) fn encoding(&self) -> &'static str {
+        match self {
+            Set::IntSet(_) => "intset",
+            Set::Table(_) => "hashtable",
+        }
+    }
+
+    // 占用内存的粗略估计
+    pub(crate) fn mem_usage(&self) -> usize {
+        match self {
+            Set::IntSet(ints) => ints.len() * mem::size_of::<i64>(),
+            Set::Table(dict) => dict
+                .iter()
+                .map(|(member, _)| member.len() + mem::size_of::<Bytes>())
+                .sum(),
+        }
+    }
+
+    fn convert_to_table(&mut self) {
+        if let Set::IntSet(ints) = self {
+            let mut dict = Dict::new();
+            for v in mem::take(ints) {
+                dict.insert(Bytes::from(v.to_string()), ());
+            }
+            *self = Set::Table(dict);
+        }
+    }
+}
+
+// 和redis的string2ll一样，只有转换回字符串之后和原来完全相同的整数才能保存在intset中
+// 像"007"、"-0"这样的成员如果按照整数保存，读出来的时候就变成了另外一个字符串
+fn as_int(member: &[u8]) -> Option<i64> {
+    parse_i64(member).filter(|v| v.to_string().as_bytes() == member)
+}
@@ -0,0 +1,774 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+
+use super::dump::{Reader, Writer};
+
+// 和redis一样的ID：毫秒时间戳-序号，先比较时间戳再比较序号
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+// XADD指定ID的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XAddId {
+    // *，时间戳和序号都自动生成
+    Auto,
+    // ms-*，只自动生成序号
+    AutoSeq(u64),
+    Explicit(StreamId),
+}
+
+// XRANGE、XREAD等返回的条目，fields为None表示条目已经被删除，只会出现在XREADGROUP读取历史消息的时候
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamEntry {
+    pub id: StreamId,
+    pub fields: Option<Vec<(Bytes, Bytes)>>,
+}
+
+// XPENDING不带范围时返回的汇总信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PendingSummary {
+    pub count: usize,
+    // 待确认的最小和最大ID，没有待确认的条目时为None
+    pub bounds: Option<(StreamId, StreamId)>,
+    // 每个消费者待确认的条目数量，按照消费者的名字排序
+    pub consumers: Vec<(String, usize)>,
+}
+
+// XPENDING带范围时返回的每一个待确认的条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEntry {
+    pub id: StreamId,
+    pub consumer: String,
+    // 距离上一次投递的毫秒数
+    pub idle: u64,
+    pub delivery_count: u64,
+}
+
+// XPENDING key group [IDLE min-idle-time] start end count [consumer]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingRange {
+    pub min_idle: Option<u64>,
+    pub start: StreamId,
+    pub end: StreamId,
+    pub count: usize,
+    pub consumer: Option<String>,
+}
+
+// XCLAIM的可选参数
+// IDLE、TIME: 把投递时间设置为指定的时间，而不是当前时间
+// RETRYCOUNT: 把投递次数设置为指定的值，而不是加一
+// FORCE: 条目不在待确认列表中时也创建，只要条目还在stream中
+// JUSTID: 只返回ID，不增加投递次数
+// LASTID: 同时推进消费者组的last_delivered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct XClaimOptions {
+    pub idle: Option<u64>,
+    pub time: Option<u64>,
+    pub retry_count: Option<u64>,
+    pub force: bool,
+    pub justid: bool,
+    pub last_id: Option<StreamId>,
+}
+
+// XREADGROUP的可选参数
+// NOACK: 读取到的条目不加入待确认列表，相当于读取之后立即确认
+// BLOCK: 没有新条目时阻塞等待
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct XReadGroupOptions {
+    pub count: Option<usize>,
+    pub noack: bool,
+    pub block: bool,
+}
+
+// XAUTOCLAIM key group consumer min-idle-time start [COUNT count] [JUSTID]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoClaimOptions {
+    pub min_idle: u64,
+    pub start: StreamId,
+    pub count: usize,
+    pub justid: bool,
+}
+
+// XAUTOCLAIM的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutoClaimed {
+    // 下一次扫描的起点，0-0表示已经扫描完所有待确认的条目
+    pub cursor: StreamId,
+    pub claimed: Vec<StreamEntry>,
+    // 已经从stream中删除的条目，同时从待确认列表中删除
+    pub deleted: Vec<StreamId>,
+}
+
+// 只追加的stream，条目按照ID排序
+#[derive(Debug, Clone)]
+pub(crate) struct Stream {
+    entries: BTreeMap<StreamId, Vec<(Bytes, Bytes)>>,
+    // 最后一次写入的ID，条目被裁剪掉之后也不会变小，新的ID必须比它大
+    last_id: StreamId,
+    groups: HashMap<String, Group>,
+}
+
+// 消费者组，记录已经投递给组内消费者但是还没有确认的条目(PEL)
+#[derive(Debug, Clone)]
+struct Group {
+    // 最后一个投递给组内消费者的ID，XREADGROUP的>从这里之后开始读取
+    last_delivered: StreamId,
+    pending: BTreeMap<StreamId, Pending>,
+    consumers: HashMap<String, Consumer>,
+}
+
+#[derive(Debug, Clone)]
+struct Pending {
+    consumer: String,
+    // 最后一次投递的unix时间，毫秒
+    delivered_at: u64,
+    delivery_count: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Consumer {
+    // 最后一次读取或者认领的unix时间，毫秒
+    seen_at: u64,
+    // 投递给这个消费者但是还没有确认的ID
+    pending: BTreeSet<StreamId>,
+}
+
+const ID_TOO_SMALL: &str =
+    "ERR The ID specified in XADD is equal or smaller than the target stream top item";
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    // 紧跟在后面的ID，已经是最大的ID时返回None
+    pub fn next(self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_add(1)?, 0)),
+        }
+    }
+
+    // 紧挨着的前一个ID，已经是最小的ID时返回None
+    pub fn prev(self) -> Option<StreamId> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_sub(1)?, u64::MAX)),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+impl Stream {
+    pub(crate) fn new() -> Stream {
+        Stream {
+            entries: BTreeMap::new(),
+            last_id: StreamId::MIN,
+            groups: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    // 按照XADD的规则生成新条目的ID，新的ID必须比last_id大
+    pub(crate) fn next_id(&self, id: XAddId) -> crate::Result<StreamId> {
+        let last = self.last_id;
+
+        match id {
+            XAddId::Auto => {
+                // 时钟回拨时沿用last_id的时间戳，只增加序号
+                let ms = now_ms().max(last.ms);
+                if ms > last.ms {
+                    Ok(StreamId::new(ms, 0))
+                } else {
+                    last.next().ok_or_else(|| {
+                        "ERR The stream has exhausted the last possible ID, unable to add more items"
+                            .into()
+                    })
+                }
+            }
+            XAddId::AutoSeq(ms) if ms > last.ms => Ok(StreamId::new(ms, 0)),
+            XAddId::AutoSeq(ms) if ms == last.ms && last.seq < u64::MAX => {
+                Ok(StreamId::new(ms, last.seq + 1))
+            }
+            XAddId::AutoSeq(_) => Err(ID_TOO_SMALL.into()),
+            XAddId::Explicit(StreamId::MIN) => {
+                Err("ERR The ID specified in XADD must be greater than 0-0".into())
+            }
+            XAddId::Explicit(id) if id <= last => Err(ID_TOO_SMALL.into()),
+            XAddId::Explicit(id) => Ok(id),
+        }
+    }
+
+    // 追加条目，调用方需要保证id比last_id大
+    pub(crate) fn insert(&mut self, id: StreamId, fields: Vec<(Bytes, Bytes)>) {
+        self.entries.insert(id, fields);
+        self.last_id = id;
+    }
+
+    // 删除最早的条目，直到条目数量不超过maxlen，返回删除的数量
+    // 已经投递给消费者组的条目即使被删除，也会留在待确认列表中，直到被确认
+    pub(crate) fn trim(&mut self, maxlen: usize) -> usize {
+        let mut trimmed = 0;
+        while self.entries.len() > maxlen {
+            self.entries.pop_first();
+            trimmed += 1;
+        }
+        trimmed
+    }
+
+    // ID在[start, end]之间的条目，rev为true时从end开始倒序返回
+    pub(crate) fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+        rev: bool,
+    ) -> Vec<StreamEntry> {
+        if start > end {
+            return vec![];
+        }
+
+        let range = self.entries.range(start..=end);
+        let count = count.unwrap_or(usize::MAX);
+
+        if rev {
+            range.rev().take(count).map(to_entry).collect()
+        } else {
+            range.take(count).map(to_entry).collect()
+        }
+    }
+
+    // ID大于after的条目
+    pub(crate) fn read_after(&self, after: StreamId, count: Option<usize>) -> Vec<StreamEntry> {
+        match after.next() {
+            Some(start) => self.range(start, StreamId::MAX, count, false),
+            None => vec![],
+        }
+    }
+
+    pub(crate) fn has_group(&self, group: &str) -> bool {
+        self.groups.contains_key(group)
+    }
+
+    // 创建消费者组，从last_delivered之后开始投递，组已经存在时返回false
+    pub(crate) fn create_group(&mut self, group: &str, last_delivered: StreamId) -> bool {
+        if self.has_group(group) {
+            return false;
+        }
+
+        self.groups.insert(
+            group.to_string(),
+            Group {
+                last_delivered,
+                pending: BTreeMap::new(),
+                consumers: HashMap::new(),
+            },
+        );
+        true
+    }
+
+    pub(crate) fn destroy_group(&mut self, group: &str) -> bool {
+        self.groups.remove(group).is_some()
+    }
+
+    // 修改消费者组的last_delivered，组不存在时返回false
+    pub(crate) fn set_group_id(&mut self, group: &str, last_delivered: StreamId) -> bool {
+        match self.groups.get_mut(group) {
+            Some(group) => {
+                group.last_delivered = last_delivered;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 创建消费者，组不存在时返回None，消费者已经存在时返回Some(false)
+    pub(crate) fn create_consumer(&mut self, group: &str, consumer: &str) -> Option<bool> {
+        let group = self.groups.get_mut(group)?;
+        if group.consumers.contains_key(consumer) {
+            return Some(false);
+        }
+
+        group.consumer(consumer, now_ms());
+        Some(true)
+    }
+
+    // 删除消费者以及它所有待确认的条目，返回删除的待确认条目数量，组不存在时返回None
+    pub(crate) fn delete_consumer(&mut self, group: &str, consumer: &str) -> Option<usize> {
+        let group = self.groups.get_mut(group)?;
+
+        let removed = match group.consumers.remove(consumer) {
+            Some(removed) => removed,
+            None => return Some(0),
+        };
+
+        for id in &removed.pending {
+            group.pending.remove(id);
+        }
+
+        Some(removed.pending.len())
+    }
+
+    // XREADGROUP的>，读取从来没有投递给组内消费者的条目，组不存在时返回None
+    // 读取到的条目加入待确认列表，noack为true时相当于读取之后立即确认
+    pub(crate) fn read_group_new(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        count: Option<usize>,
+        noack: bool,
+    ) -> Option<Vec<StreamEntry>> {
+        let group = self.groups.get_mut(group)?;
+        let now = now_ms();
+        group.consumer(consumer, now);
+
+        let entries = match group.last_delivered.next() {
+            Some(start) => self
+                .entries
+                .range(start..)
+                .take(count.unwrap_or(usize::MAX))
+                .map(to_entry)
+                .collect(),
+            None => vec![],
+        };
+
+        if let Some(last) = entries.last() {
+            group.last_delivered = last.id;
+        }
+
+        if !noack {
+            for entry in &entries {
+                group.assign(entry.id, consumer, now, 1);
+            }
+        }
+
+        Some(entries)
+    }
+
+    // XREADGROUP指定ID时，读取已经投递给这个消费者但是还没有确认的条目，同时更新投递时间和次数
+    pub(crate) fn read_group_history(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        after: StreamId,
+        count: Option<usize>,
+    ) -> Option<Vec<StreamEntry>> {
+        let group = self.groups.get_mut(group)?;
+        let now = now_ms();
+
+        let start = match after.next() {
+            Some(start) => start,
+            None => return Some(vec![]),
+        };
+
+        let ids: Vec<StreamId> = group
+            .consumer(consumer, now)
+            .pending
+            .range(start..)
+            .take(count.unwrap_or(usize::MAX))
+            .copied()
+            .collect();
+
+        let entries = ids
+            .into_iter()
+            .map(|id| {
+                let pending = group.pending.get_mut(&id).expect("the id is pending");
+                pending.delivered_at = now;
+                pending.delivery_count += 1;
+
+                StreamEntry {
+                    id,
+                    fields: self.entries.get(&id).cloned(),
+                }
+            })
+            .collect();
+
+        Some(entries)
+    }
+
+    // 确认条目，从待确认列表中删除，返回确认的数量，组不存在时返回0
+    pub(crate) fn ack(&mut self, group: &str, ids: &[StreamId]) -> usize {
+        match self.groups.get_mut(group) {
+            Some(group) => ids.iter().filter(|id| group.ack(id)).count(),
+            None => 0,
+        }
+    }
+
+    pub(crate) fn pending_summary(&self, group: &str) -> Option<PendingSummary> {
+        let group = self.groups.get(group)?;
+
+        let bounds = group
+            .pending
+            .first_key_value()
+            .zip(group.pending.last_key_value())
+            .map(|((min, _), (max, _))| (*min, *max));
+
+        let mut consumers: Vec<(String, usize)> = group
+            .consumers
+            .iter()
+            .filter(|(_, consumer)| !consumer.pending.is_empty())
+            .map(|(name, consumer)| (name.clone(), consumer.pending.len()))
+            .collect();
+        consumers.sort();
+
+        Some(PendingSummary {
+            count: group.pending.len(),
+            bounds,
+            consumers,
+        })
+    }
+
+    pub(crate) fn pending(&self, group: &str, range: &PendingRange) -> Option<Vec<PendingEntry>> {
+        let group = self.groups.get(group)?;
+
+        if range.start > range.end {
+            return Some(vec![]);
+        }
+
+        let now = now_ms();
+        let entries = group
+            .pending
+            .range(range.start..=range.end)
+            .filter(|(_, pending)| {
+                range
+                    .consumer
+                    .as_ref()
+                    .is_none_or(|consumer| *consumer == pending.consumer)
+            })
+            .map(|(id, pending)| PendingEntry {
+                id: *id,
+                consumer: pending.consumer.clone(),
+                idle: now.saturating_sub(pending.delivered_at),
+                delivery_count: pending.delivery_count,
+            })
+            .filter(|entry| range.min_idle.is_none_or(|min_idle| entry.idle >= min_idle))
+            .take(range.count)
+            .collect();
+
+        Some(entries)
+    }
+
+    // 把空闲了至少min_idle毫秒的待确认条目转移给consumer，组不存在时返回None
+    // 已经从stream中删除的条目会同时从待确认列表中删除，不会返回
+    pub(crate) fn claim(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        min_idle: u64,
+        ids: &[StreamId],
+        options: &XClaimOptions,
+    ) -> Option<Vec<StreamEntry>> {
+        let group = self.groups.get_mut(group)?;
+        let now = now_ms();
+        group.consumer(consumer, now);
+
+        if let Some(last_id) = options.last_id {
+            group.last_delivered = group.last_delivered.max(last_id);
+        }
+
+        let delivered_at = match (options.time, options.idle) {
+            (Some(time), _) => time,
+            (None, Some(idle)) => now.saturating_sub(idle),
+            (None, None) => now,
+        };
+
+        let mut claimed = vec![];
+        for &id in ids {
+            let fields = self.entries.get(&id);
+
+            let delivery_count = match group.pending.get(&id) {
+                Some(pending) => {
+                    if now.saturating_sub(pending.delivered_at) < min_idle {
+                        continue;
+                    }
+                    if fields.is_none() {
+                        group.ack(&id);
+                        continue;
+                    }
+                    pending.delivery_count
+                }
+                // FORCE创建的条目没有空闲时间的限制
+                None if options.force && fields.is_some() => 0,
+                None => continue,
+            };
+
+            let delivery_count = match options.retry_count {
+                Some(retry_count) => retry_count,
+                None if options.justid => delivery_count.max(1),
+                None => delivery_count + 1,
+            };
+            group.assign(id, consumer, delivered_at, delivery_count);
+
+            claimed.push(StreamEntry {
+                id,
+                fields: fields.cloned(),
+            });
+        }
+
+        Some(claimed)
+    }
+
+    // 从start开始扫描待确认列表，把空闲了至少min_idle毫秒的条目转移给consumer，最多转移count个
+    // 和redis一样，一次最多检查count * 10个条目，避免待确认列表很长时耗时太久
+    pub(crate) fn auto_claim(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        options: &AutoClaimOptions,
+    ) -> Option<AutoClaimed> {
+        let group = self.groups.get_mut(group)?;
+        let now = now_ms();
+        group.consumer(consumer, now);
+
+        let AutoClaimOptions {
+            min_idle,
+            start,
+            count,
+            justid,
+        } = *options;
+
+        let mut attempts = count.saturating_mul(10);
+        let ids: Vec<StreamId> = group
+            .pending
+            .range(start..)
+            .map(|(id, _)| *id)
+            .take(attempts.saturating_add(1))
+            .collect();
+
+        let mut result = AutoClaimed::default();
+        for id in ids {
+            if attempts == 0 || result.claimed.len() == count {
+                result.cursor = id;
+                break;
+            }
+            attempts -= 1;
+
+            let pending = group.pending.get(&id).expect("the id is pending");
+            if now.saturating_sub(pending.delivered_at) < min_idle {
+                continue;
+            }
+
+            let fields = match self.entries.get(&id) {
+                Some(fields) => fields,
+                None => {
+                    group.ack(&id);
+                    result.deleted.push(id);
+                    continue;
+                }
+            };
+
+            let delivery_count = if justid {
+                pending.delivery_count
+            } else {
+                pending.delivery_count + 1
+            };
+            group.assign(id, consumer, now, delivery_count);
+
+            result.claimed.push(StreamEntry {
+                id,
+                fields: Some(fields.clone()),
+            });
+        }
+
+        Some(result)
+    }
+
+    // DUMP时序列化所有的条目以及消费者组
+    pub(crate) fn dump(&self, w: &mut Writer) {
+        write_id(w, self.last_id);
+
+        w.len(self.entries.len());
+        for (id, fields) in &self.entries {
+            write_id(w, *id);
+            w.len(fields.len());
+            for (field, value) in fields {
+                w.bytes(field);
+                w.bytes(value);
+            }
+        }
+
+        w.len(self.groups.len());
+        for (name, group) in &self.groups {
+            w.bytes(name.as_bytes());
+            write_id(w, group.last_delivered);
+
+            w.len(group.consumers.len());
+            for (name, consumer) in &group.consumers {
+                w.bytes(name.as_bytes());
+                w.u64(consumer.seen_at);
+            }
+
+            w.len(group.pending.len());
+            for (id, pending) in &group.pending {
+                write_id(w, *id);
+                w.bytes(pending.consumer.as_bytes());
+                w.u64(pending.delivered_at);
+                w.u64(pending.delivery_count);
+            }
+        }
+    }
+
+    // dump的逆过程，数据不合法时返回None，例如条目的ID没有递增或者大于last_id
+    pub(crate) fn restore(r: &mut Reader) -> Option<Stream> {
+        let mut stream = Stream::new();
+        let last_id = read_id(r)?;
+
+        for _ in 0..r.len()? {
+            let id = read_id(r)?;
+            if id > last_id
+                || stream
+                    .entries
+                    .last_key_value()
+                    .is_some_and(|(last, _)| *last >= id)
+            {
+                return None;
+            }
+
+            let mut fields = vec![];
+            for _ in 0..r.len()? {
+                fields.push((r.bytes()?, r.bytes()?));
+            }
+            stream.entries.insert(id, fields);
+        }
+        stream.last_id = last_id;
+
+        for _ in 0..r.len()? {
+            let name = r.string()?;
+            let mut group = Group {
+                last_delivered: read_id(r)?,
+                pending: BTreeMap::new(),
+                consumers: HashMap::new(),
+            };
+
+            for _ in 0..r.len()? {
+                let name = r.string()?;
+                let consumer = Consumer {
+                    seen_at: r.u64()?,
+                    pending: BTreeSet::new(),
+                };
+                group.consumers.insert(name, consumer);
+            }
+
+            for _ in 0..r.len()? {
+                let id = read_id(r)?;
+                let pending = Pending {
+                    consumer: r.string()?,
+                    delivered_at: r.u64()?,
+                    delivery_count: r.u64()?,
+                };
+
+                group
+                    .consumers
+                    .get_mut(&pending.consumer)?
+                    .pending
+                    .insert(id);
+                group.pending.insert(id, pending);
+            }
+
+            stream.groups.insert(name, group);
+        }
+
+        Some(stream)
+    }
+
+    // 占用内存的粗略估计
+    pub(crate) fn mem_usage(&self) -> usize {
+        self.entries
+            .values()
+            .flatten()
+            .map(|(field, value)| field.len() + value.len())
+            .sum()
+    }
+}
+
+impl Group {
+    // 获取消费者，不存在时创建，同时更新最后活跃的时间
+    fn consumer(&mut self, name: &str, now: u64) -> &mut Consumer {
+        let consumer = self.consumers.entry(name.to_string()).or_default();
+        consumer.seen_at = now;
+        consumer
+    }
+
+    // 把条目投递给consumer，原来属于其他消费者时转移过来
+    fn assign(&mut self, id: StreamId, consumer: &str, delivered_at: u64, delivery_count: u64) {
+        let prev = self.pending.insert(
+            id,
+            Pending {
+                consumer: consumer.to_string(),
+                delivered_at,
+                delivery_count,
+            },
+        );
+
+        if let Some(prev) = prev {
+            if let Some(owner) = self.consumers.get_mut(&prev.consumer) {
+                owner.pending.remove(&id);
+            }
+        }
+
+        self.consumer(consumer, delivered_at.max(now_ms()))
+            .pending
+            .insert(id);
+    }
+
+    fn ack(&mut self, id: &StreamId) -> bool {
+        match self.pending.remove(id) {
+            Some(pending) => {
+                if let Some(owner) = self.consumers.get_mut(&pending.consumer) {
+                    owner.pending.remove(id);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn write_id(w: &mut Writer, id: StreamId) {
+    w.u64(id.ms);
+    w.u64(id.seq);
+}
+
+fn read_id(r: &mut Reader) -> Option<StreamId> {
+    Some(StreamId::new(r.u64()?, r.u64()?))
+}
+
+fn to_entry((id, fields): (&StreamId, &Vec<(Bytes, Bytes)>)) -> StreamEntry {
+    StreamEntry {
+        id: *id,
+        fields: Some(fields.clone()),
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,624 @@
+use std::{cmp::Ordering, iter, mem};
+
+use bytes::Bytes;
+
+use super::{dict::Dict, LexBound, ScoreBound, XorShift, ZAddOptions};
+
+// 跳表最多的层数，和redis的ZSKIPLIST_MAXLEVEL相同
+const MAX_LEVEL: usize = 32;
+
+// 节点每多一层的概率，和redis的ZSKIPLIST_P相同
+const LEVEL_PROBABILITY: u64 = 4;
+
+// 跳表的头节点总是保存在nodes[0]
+const HEAD: usize = 0;
+
+// 有序集合，和redis的zset一样同时使用两种结构：
+// 哈希表保存成员到分数的映射，ZSCORE、ZADD判断成员是否存在时使用；
+// 跳表按照(分数, 成员)排序，用于按照排名或者分数、字典序的范围查找
+#[derive(Debug, Clone)]
+pub(crate) struct ZSet {
+    dict: Dict<Bytes, f64>,
+    list: SkipList,
+}
+
+// ZADD写入一个成员的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ZAddResult {
+    // 新增了成员
+    Added(f64),
+    // 已经存在的成员的分数发生了变化
+    Updated(f64),
+    // 已经存在的成员的分数没有变化
+    Unchanged(f64),
+    // 因为NX、XX、GT、LT的条件没有写入
+    Skipped,
+}
+
+impl ZSet {
+    pub(crate) fn new() -> ZSet {
+        ZSet {
+            dict: Dict::new(),
+            list: SkipList::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.dict.len()
+    }
+
+    pub(crate) fn score(&self, member: &[u8]) -> Option<f64> {
+        self.dict.get(member).copied()
+    }
+
+    // 写入成员的分数，成员原来不存在时返回true
+    pub(crate) fn insert(&mut self, member: Bytes, score: f64) -> bool {
+        match self.dict.get_mut(&member) {
+            Some(current) => {
+                if *current != score {
+                    self.list.remove(*current, &member);
+                    self.list.insert(score, member);
+                    *current = score;
+                }
+                false
+            }
+            None => {
+                self.list.insert(score, member.clone());
+                self.dict.insert(member, score);
+                true
+            }
+        }
+    }
+
+    // 按照ZADD的规则写入成员，incr为true时score是增量
+    pub(crate) fn add(
+        &mut self,
+        member: Bytes,
+        score: f64,
+        options: &ZAddOptions,
+        incr: bool,
+    ) -> crate::Result<ZAddResult> {
+        let current = match self.score(&member) {
+            Some(current) => current,
+            None if options.xx => return Ok(ZAddResult::Skipped),
+            None => {
+                self.insert(member, score);
+                return Ok(ZAddResult::Added(score));
+            }
+        };
+
+        if options.nx {
+            return Ok(ZAddResult::Skipped);
+        }
+
+        let score = if incr { current + score } else { score };
+        // inf加上-inf
+        if score.is_nan() {
+            return Err("ERR resulting score is not a number (NaN)".into());
+        }
+
+        if (options.gt && score <= current) || (options.lt && score >= current) {
+            return Ok(ZAddResult::Skipped);
+        }
+
+        if score == current {
+            return Ok(ZAddResult::Unchanged(score));
+        }
+
+        self.insert(member, score);
+
+        Ok(ZAddResult::Updated(score))
+    }
+
+    // 删除成员，成员存在时返回true
+    pub(crate) fn remove(&mut self, member: &[u8]) -> bool {
+        match self.dict.remove(member) {
+            Some(score) => {
+                self.list.remove(score, member);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 成员的排名(从0开始)和分数，rev为true时分数最高的成员排名为0
+    pub(crate) fn rank(&self, member: &[u8], rev: bool) -> Option<(usize, f64)> {
+        let score = self.score(member)?;
+        let rank = self
+            .list
+            .rank(score, member)
+            .expect("the member is in the skiplist");
+        let rank = if rev { self.len() - 1 - rank } else { rank };
+        Some((rank, score))
+    }
+
+    // 排名在[start, stop]之间的成员，调用方需要保证stop小于成员的数量
+    pub(crate) fn range_by_rank(&self, start: usize, stop: usize, rev: bool) -> Vec<(Bytes, f64)> {
+        let len = stop - start + 1;
+        let first = if rev {
+            self.list.by_rank(self.len() - 1 - start)
+        } else {
+            self.list.by_rank(start)
+        };
+        self.list.collect(first, rev, len)
+    }
+
+    // 分数在[min, max]之间的成员，rev为true时从分数最高的一端开始，跳过offset个成员之后最多返回count个
+    pub(crate) fn range_by_score(
+        &self,
+        min: ScoreBound,
+        max: ScoreBound,
+        rev: bool,
+        offset: usize,
+        count: Option<usize>,
+    ) -> Vec<(Bytes, f64)> {
+        self.range(
+            |score, _| above_min(min, score),
+            |score, _| below_max(max, score),
+            rev,
+            offset,
+            count,
+        )
+    }
+
+    // 字典序在[min, max]之间的成员，只有所有成员的分数都相同时结果才有意义
+    pub(crate) fn range_by_lex(
+        &self,
+        min: &LexBound,
+        max: &LexBound,
+        rev: bool,
+        offset: usize,
+        count: Option<usize>,
+    ) -> Vec<(Bytes, f64)> {
+        self.range(
+            |_, member| lex_above_min(min, member),
+            |_, member| lex_below_max(max, member),
+            rev,
+            offset,
+            count,
+        )
+    }
+
+    // 分数在[min, max]之间的成员数量，通过两端成员的排名计算，不需要遍历
+    pub(crate) fn count(&self, min: ScoreBound, max: ScoreBound) -> usize {
+        let first = self.list.first_where(|score, _| above_min(min, score));
+        let last = self.list.last_where(|score, _| below_max(max, score));
+
+        match (first, last) {
+            (Some(first), Some(last)) => {
+                let first = self.list.node_rank(first);
+                let last = self.list.node_rank(last);
+                (last + 1).saturating_sub(first)
+            }
+            _ => 0,
+        }
+    }
+
+    // 删除并返回分数最低的count个成员，rev为true时删除分数最高的
+    pub(crate) fn pop(&mut self, count: usize, rev: bool) -> Vec<(Bytes, f64)> {
+        let first = if rev {
+            self.list.tail
+        } else {
+            self.list.first()
+        };
+        let popped = self.list.collect(first, rev, count);
+
+        for (member, _) in &popped {
+            self.remove(member);
+        }
+
+        popped
+    }
+
+    // 按照分数从低到高遍历所有成员
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Bytes, f64)> {
+        self.list.iter(self.list.first(), false)
+    }
+
+    // 通过成员到分数的哈希表遍历，和HSCAN一样游标在扩容、缩容之后依然有效
+    pub(crate) fn scan(&self, cursor: u64, count: usize, mut f: impl FnMut(&Bytes, f64)) -> u64 {
+        self.dict
+            .scan(cursor, count, |member, score| f(member, *score))
+    }
+
+    // 占用内存的粗略估计，成员同时保存在哈希表和跳表中
+    pub(crate) fn mem_usage(&self) -> usize {
+        self.iter()
+            .map(|(member, _)| member.len() + 2 * (mem::size_of::<Bytes>() + mem::size_of::<f64>()))
+            .sum()
+    }
+
+    // in_min对于范围下界以下的成员返回false，in_max对于范围上界以上的成员返回false
+    fn range(
+        &self,
+        in_min: impl Fn(f64, &[u8]) -> bool,
+        in_max: impl Fn(f64, &[u8]) -> bool,
+        rev: bool,
+        offset: usize,
+        count: Option<usize>,
+    ) -> Vec<(Bytes, f64)> {
+        // 从范围的一端开始遍历，直到超出另一端
+        if rev {
+            let first = self.list.last_where(&in_max);
+            self.list.collect_while(first, rev, in_min, offset, count)
+        } else {
+            let first = self.list.first_where(&in_min);
+            self.list.collect_while(first, rev, in_max, offset, count)
+        }
+    }
+}
+
+fn above_min(min: ScoreBound, score: f64) -> bool {
+    match min {
+        ScoreBound::Inclusive(min) => score >= min,
+        ScoreBound::Exclusive(min) => score > min,
+    }
+}
+
+fn below_max(max: ScoreBound, score: f64) -> bool {
+    match max {
+        ScoreBound::Inclusive(max) => score <= max,
+        ScoreBound::Exclusive(max) => score < max,
+    }
+}
+
+fn lex_above_min(min: &LexBound, member: &[u8]) -> bool {
+    match min {
+        LexBound::Min => true,
+        LexBound::Max => false,
+        LexBound::Inclusive(min) => member >= &min[..],
+        LexBound::Exclusive(min) => member > &min[..],
+    }
+}
+
+fn lex_below_max(max: &LexBound, member: &[u8]) -> bool {
+    match max {
+        LexBound::Min => false,
+        LexBound::Max => true,
+        LexBound::Inclusive(max) => member <= &max[..],
+        LexBound::Exclusive(max) => member < &max[..],
+    }
+}
+
+// 和redis的zskiplist相同的跳表，每一层的指针同时记录跨越的节点数量(span)，所以可以在O(log n)内计算排名
+// 节点保存在数组中，用下标代替指针，删除的节点放入free中等待复用
+#[derive(Debug, Clone)]
+struct SkipList {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    // 最后一个节点，ZPOPMAX以及反向遍历从这里开始
+    tail: Option<usize>,
+    len: usize,
+    // 当前最高的层数
+    level: usize,
+    rng: XorShift,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    score: f64,
+    member: Bytes,
+    // 第0层的前一个节点，第一个节点的backward为None
+    backward: Option<usize>,
+    levels: Vec<Level>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    forward: Option<usize>,
+    // 从当前节点到forward之间跨越的节点数量，forward为None时是当前节点之后的节点数量
+    span: usize,
+}
+
+impl Node {
+    // 按照(分数, 成员)比较节点和给定的元素
+    fn cmp(&self, score: f64, member: &[u8]) -> Ordering {
+        self.score
+            .partial_cmp(&score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.member[..].cmp(member))
+    }
+}
+
+impl SkipList {
+    fn new() -> SkipList {
+        let head = Node {
+            score: 0.0,
+            member: Bytes::new(),
+            backward: None,
+            levels: vec![
+                Level {
+                    forward: None,
+                    span: 0
+                };
+                MAX_LEVEL
+            ],
+        };
+
+        SkipList {
+            nodes: vec![head],
+            free: Vec::new(),
+            tail: None,
+            len: 0,
+            level: 1,
+            rng: XorShift::new(),
+        }
+    }
+
+    fn first(&self) -> Option<usize> {
+        self.nodes[HEAD].levels[0].forward
+    }
+
+    fn forward(&self, node: usize, level: usize) -> Option<usize> {
+        self.nodes[node].levels[level].forward
+    }
+
+    fn span(&self, node: usize, level: usize) -> usize {
+        self.nodes[node].levels[level].span
+    }
+
+    // 每多一层的概率是1/4
+    fn random_level(&mut self) -> usize {
+        let mut level = 1;
+        while level < MAX_LEVEL && self.rng.next().is_multiple_of(LEVEL_PROBABILITY) {
+            level += 1;
+        }
+        level
+    }
+
+    // 插入新的元素，调用方需要保证成员不在跳表中
+    fn insert(&mut self, score: f64, member: Bytes) {
+        // update[i]是第i层中新节点的前一个节点，rank[i]是它的排名
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut rank = [0; MAX_LEVEL];
+
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            rank[i] = if i == self.level - 1 { 0 } else { rank[i + 1] };
+            while let Some(next) = self.forward(x, i) {
+                if self.nodes[next].cmp(score, &member) != Ordering::Less {
+                    break;
+                }
+                rank[i] += self.span(x, i);
+                x = next;
+            }
+            update[i] = x;
+        }
+
+        let level = self.random_level();
+        if level > self.level {
+            for i in self.level..level {
+                rank[i] = 0;
+                update[i] = HEAD;
+                self.nodes[HEAD].levels[i].span = self.len;
+            }
+            self.level = level;
+        }
+
+        let node = self.alloc(Node {
+            score,
+            member,
+            backward: (update[0] != HEAD).then_some(update[0]),
+            levels: vec![
+                Level {
+                    forward: None,
+                    span: 0
+                };
+                level
+            ],
+        });
+
+        for i in 0..level {
+            let prev = update[i];
+            let prev_span = self.span(prev, i);
+            self.nodes[node].levels[i] = Level {
+                forward: self.forward(prev, i),
+                span: prev_span - (rank[0] - rank[i]),
+            };
+            self.nodes[prev].levels[i] = Level {
+                forward: Some(node),
+                span: rank[0] - rank[i] + 1,
+            };
+        }
+
+        // 新节点没有达到的层，前一个节点跨越的节点数量多了一个
+        for (i, &prev) in update.iter().enumerate().take(self.level).skip(level) {
+            self.nodes[prev].levels[i].span += 1;
+        }
+
+        match self.forward(node, 0) {
+            Some(next) => self.nodes[next].backward = Some(node),
+            None => self.tail = Some(node),
+        }
+
+        self.len += 1;
+    }
+
+    // 删除元素，元素存在时返回true
+    fn remove(&mut self, score: f64, member: &[u8]) -> bool {
+        let mut update = [HEAD; MAX_LEVEL];
+
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if self.nodes[next].cmp(score, member) != Ordering::Less {
+                    break;
+                }
+                x = next;
+            }
+            update[i] = x;
+        }
+
+        let node = match self.forward(x, 0) {
+            Some(node) if self.nodes[node].cmp(score, member) == Ordering::Equal => node,
+            _ => return false,
+        };
+
+        for (i, &prev) in update.iter().enumerate().take(self.level) {
+            if self.forward(prev, i) == Some(node) {
+                let span = self.span(prev, i) + self.span(node, i) - 1;
+                self.nodes[prev].levels[i] = Level {
+                    forward: self.forward(node, i),
+                    span,
+                };
+            } else {
+                self.nodes[prev].levels[i].span -= 1;
+            }
+        }
+
+        let backward = self.nodes[node].backward;
+        match self.forward(node, 0) {
+            Some(next) => self.nodes[next].backward = backward,
+            None => self.tail = backward,
+        }
+
+        while self.level > 1 && self.forward(HEAD, self.level - 1).is_none() {
+            self.level -= 1;
+        }
+
+        self.release(node);
+        self.len -= 1;
+
+        true
+    }
+
+    // 元素的排名(从0开始)，元素不存在时返回None
+    fn rank(&self, score: f64, member: &[u8]) -> Option<usize> {
+        let mut rank = 0;
+
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if self.nodes[next].cmp(score, member) == Ordering::Greater {
+                    break;
+                }
+                rank += self.span(x, i);
+                x = next;
+            }
+
+            if x != HEAD && self.nodes[x].cmp(score, member) == Ordering::Equal {
+                return Some(rank - 1);
+            }
+        }
+
+        None
+    }
+
+    fn node_rank(&self, node: usize) -> usize {
+        let node = &self.nodes[node];
+        self.rank(node.score, &node.member)
+            .expect("the node is in the skiplist")
+    }
+
+    // 排名为rank(从0开始)的节点
+    fn by_rank(&self, rank: usize) -> Option<usize> {
+        let target = rank + 1;
+        let mut traversed = 0;
+
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if traversed + self.span(x, i) > target {
+                    break;
+                }
+                traversed += self.span(x, i);
+                x = next;
+            }
+
+            if traversed == target {
+                return Some(x);
+            }
+        }
+
+        None
+    }
+
+    // 第一个满足条件的节点，条件对于排在前面的一部分节点为false，之后的节点都为true
+    fn first_where(&self, f: impl Fn(f64, &[u8]) -> bool) -> Option<usize> {
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                let node = &self.nodes[next];
+                if f(node.score, &node.member) {
+                    break;
+                }
+                x = next;
+            }
+        }
+
+        self.forward(x, 0)
+    }
+
+    // 最后一个满足条件的节点，条件对于排在前面的一部分节点为true，之后的节点都为false
+    fn last_where(&self, f: impl Fn(f64, &[u8]) -> bool) -> Option<usize> {
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                let node = &self.nodes[next];
+                if !f(node.score, &node.member) {
+                    break;
+                }
+                x = next;
+            }
+        }
+
+        (x != HEAD).then_some(x)
+    }
+
+    // 从start开始遍历，rev为true时向分数低的一端遍历
+    fn iter(&self, start: Option<usize>, rev: bool) -> impl Iterator<Item = (&Bytes, f64)> {
+        iter::successors(start, move |&node| {
+            if rev {
+                self.nodes[node].backward
+            } else {
+                self.forward(node, 0)
+            }
+        })
+        .map(|node| (&self.nodes[node].member, self.nodes[node].score))
+    }
+
+    // 从start开始遍历，最多返回count个元素
+    fn collect(&self, start: Option<usize>, rev: bool, count: usize) -> Vec<(Bytes, f64)> {
+        self.iter(start, rev)
+            .take(count)
+            .map(|(member, score)| (member.clone(), score))
+            .collect()
+    }
+
+    // 从start开始遍历，直到遇到第一个不满足条件的元素，跳过offset个元素之后最多返回count个
+    fn collect_while(
+        &self,
+        start: Option<usize>,
+        rev: bool,
+        f: impl Fn(f64, &[u8]) -> bool,
+        offset: usize,
+        count: Option<usize>,
+    ) -> Vec<(Bytes, f64)> {
+        self.iter(start, rev)
+            .take_while(|(member, score)| f(*score, member))
+            .skip(offset)
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(member, score)| (member.clone(), score))
+            .collect()
+    }
+
+    fn alloc(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    // 释放节点占用的成员和层，下标之后再复用
+    fn release(&mut self, node: usize) {
+        self.nodes[node].member = Bytes::new();
+        self.nodes[node].levels = Vec::new();
+        self.free.push(node);
+    }
+}
@@ -1,6 +1,6 @@
-use std::io::Cursor;
+use std::{fmt, io::Cursor};
 
-use bytes::{Buf, Bytes};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 use std::string::FromUtf8Error;
 
@@ -17,7 +17,7 @@ pub enum Frame {
     // Integers（整数）：以冒号（:）开头，后跟整数值的字符串表示，以回车换行（\r\n）结束。
     // 整型（Integers）： 响应的首字节是 ":"
     // :1000\r\n
-    Integer(u64),
+    Integer(i64),
     // Bulk Strings（块字符串）：以美元符号（$）开头，后跟字符串长度的字符串表示，然后是实际字符串内容，以回车换行（\r\n）结束。
     // 多行字符串（Bulk Strings）： 响应的首字节是"$"
     // 美元符 "$" 后面跟着组成字符串的字节数(前缀长度)，并以 CRLF 结尾。
@@ -31,58 +31,188 @@ pub enum Frame {
     // 外加数组中每个 RESP 类型的元素
     // *2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n
     Array(Vec<Frame>),
+
+    // 以下是RESP3新增的类型，只有客户端通过 HELLO 3 切换协议之后才会原样发送
+    // RESP2的连接上会被转换成含义相近的RESP2类型，见Frame::encode
+
+    // Map：%后跟键值对的数量，然后依次是每个键和值
+    // %1\r\n+key\r\n:1\r\n
+    Map(Vec<(Frame, Frame)>),
+    // Set：和数组的格式一样，只是元素无序且不重复
+    // ~2\r\n+a\r\n+b\r\n
+    Set(Vec<Frame>),
+    // Double：浮点数，inf、-inf、nan也是合法的值
+    // ,1.23\r\n
+    Double(f64),
+    // Boolean：#t 或者 #f
+    // #t\r\n
+    Boolean(bool),
+    // Big Number：超出64位整数范围的大整数，按照十进制字符串保存
+    // (3492890328409238509324850943850943825024385\r\n
+    BigNumber(String),
+    // Verbatim String：和Bulk类似，但数据前有3个字节的格式说明，例如txt、mkd
+    // =15\r\ntxt:Some string\r\n
+    Verbatim {
+        format: String,
+        data: Bytes,
+    },
+    // Push：服务器主动推送给客户端的数据，例如pub/sub的消息，格式和数组一样
+    // >3\r\n$7\r\nmessage\r\n$2\r\nch\r\n$5\r\nhello\r\n
+    Push(Vec<Frame>),
+    // Attribute：附加在某个响应之前的键值对信息，紧跟着的frame才是真正的响应
+    // |1\r\n+ttl\r\n:3600\r\n$3\r\nbar\r\n
+    Attribute {
+        attributes: Vec<(Frame, Frame)>,
+        data: Box<Frame>,
+    },
 }
 
-#[derive(Debug)]
-pub enum Error {
-    Incomplete,
-    Other(crate::Error),
+// 判断一个字节是否是RESP协议中的类型前缀，客户端解码响应时不是类型前缀的按内联命令处理
+pub(crate) fn is_type_byte(b: u8) -> bool {
+    matches!(
+        b,
+        b'+' | b'-'
+            | b':'
+            | b'$'
+            | b'*'
+            | b'_'
+            | b','
+            | b'#'
+            | b'('
+            | b'='
+            | b'%'
+            | b'~'
+            | b'>'
+            | b'|'
+    )
 }
 
-impl Frame {
-    // 检查src能否被完整的解码
-    pub fn check(src: &mut Cursor<&[u8]>) -> Result<(), Error> {
-        match get_u8(src)? {
-            b'+' => {
-                get_line(src)?;
-                Ok(())
-            }
-            b'-' => {
-                get_line(src)?;
-                Ok(())
-            }
-            b':' => {
-                get_decimal(src)?;
-                Ok(())
-            }
-            // $5\r\nhello\r\n
-            b'$' => {
-                // redis协议中规定如果后面是‘-1\r\n’的话，认为是空的字符串，因此需要跳过这个情况
-                if b'-' == peek_u8(src)? {
-                    // skip -1\r\n
-                    skip(src, 4)
-                // 如果下一个字节不是'-'的话，说明是一个正常的二进制字符串，此时需要读取一个十进制的数据，该数值表示字符串的长度
-                // 然后跳过相应的长度，以便读取正常的字符串数据
-                } else {
-                    // 获取二进制字符串的长度
-                    let len = get_decimal(src)? as usize;
-                    // 需要加上\r\n
-                    skip(src, len + 2)
+// 连接使用的协议版本，默认是RESP2，客户端可以通过 HELLO 3 切换到RESP3
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Protocol {
+    #[default]
+    Resp2,
+    Resp3,
+}
+
+impl PartialEq<&str> for Frame {
+    fn eq(&self, other: &&str) -> bool {
+        match self {
+            Frame::Simple(s) => s.eq(other),
+            Frame::Bulk(s) => s.eq(other),
+            _ => false,
+        }
+    }
+}
+
+// 以可读的形式输出frame，主要用于日志和错误信息
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Frame::Simple(s) => s.fmt(f),
+            Frame::Error(msg) => write!(f, "error: {}", msg),
+            Frame::Integer(num) => num.fmt(f),
+            Frame::Bulk(bytes) => match std::str::from_utf8(bytes) {
+                Ok(s) => s.fmt(f),
+                Err(_) => write!(f, "{:?}", bytes),
+            },
+            Frame::Null => "(nil)".fmt(f),
+            Frame::Array(parts) | Frame::Set(parts) | Frame::Push(parts) => {
+                for (i, part) in parts.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    part.fmt(f)?;
                 }
+                Ok(())
             }
-            // *3\r\n$3\r\nfoo\r\n$3\r\nbar\r\n$3\r\nbaz\r\n
-            b'*' => {
-                let len = get_decimal(src)?;
-                for _ in 0..len {
-                    Frame::check(src)?;
+            Frame::Map(pairs) => {
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{} {}", key, value)?;
                 }
                 Ok(())
             }
-            actual => Err(format!("protocol error;invalid frame byte type,{}", actual).into()),
+            Frame::Double(num) => num.fmt(f),
+            Frame::Boolean(b) => b.fmt(f),
+            Frame::BigNumber(num) => num.fmt(f),
+            Frame::Verbatim { data, .. } => Frame::Bulk(data.clone()).fmt(f),
+            Frame::Attribute { data, .. } => data.fmt(f),
         }
     }
+}
+
+// 解码时的各种限制，防止错误或者恶意的客户端发送超大的数据耗尽服务器的内存
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    // 单个Bulk字符串的最大长度，默认512MB，和redis的proto-max-bulk-len一致
+    pub max_bulk_len: usize,
+    // 数组、Map等聚合类型的最大嵌套层数
+    pub max_depth: usize,
+    // 单个聚合类型中元素的最大数量
+    pub max_elements: usize,
+    // 内联命令以及RESP中+ - :等类型一行的最大长度，默认64KB，和redis的PROTO_INLINE_MAX_SIZE一致
+    pub max_inline_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_bulk_len: 512 * 1024 * 1024,
+            max_depth: 128,
+            max_elements: 1024 * 1024,
+            max_inline_len: 64 * 1024,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Incomplete,
+    Other(crate::Error),
+}
 
-    pub fn parse(src: &mut Cursor<&[u8]>) -> Result<Frame, Error> {
+impl Frame {
+    // 创建一个空的数组frame
+    pub(crate) fn array() -> Frame {
+        Frame::Array(vec![])
+    }
+
+    // 向数组frame中追加一个Bulk，如果self不是数组的话会panic
+    pub(crate) fn push_bulk(&mut self, bytes: Bytes) {
+        match self {
+            Frame::Array(vec) | Frame::Push(vec) => vec.push(Frame::Bulk(bytes)),
+            _ => panic!("not an array frame"),
+        }
+    }
+
+    // 向数组frame中追加一个Integer
+    pub(crate) fn push_int(&mut self, value: i64) {
+        match self {
+            Frame::Array(vec) | Frame::Push(vec) => vec.push(Frame::Integer(value)),
+            _ => panic!("not an array frame"),
+        }
+    }
+
+    // 向数组frame中追加一个Null
+    pub(crate) fn push_null(&mut self) {
+        match self {
+            Frame::Array(vec) | Frame::Push(vec) => vec.push(Frame::Null),
+            _ => panic!("not an array frame"),
+        }
+    }
+
+    // 检查src能否被完整的解码，同时检查长度、嵌套层数等是否超出了limits的限制
+    // 只有check通过之后才会调用parse，因此parse时不需要再考虑数据不完整的情况
+    pub fn check(src: &mut Cursor<&[u8]>, limits: &Limits) -> Result<(), Error> {
+        check_depth(src, limits, 0)
+    }
+
+    // 从src中解析出一个frame
+    // src是从连接的缓冲区中切分出来的Bytes，Bulk字符串直接引用其中的数据，不需要拷贝
+    pub fn parse(src: &mut Cursor<Bytes>) -> Result<Frame, Error> {
         match get_u8(src)? {
             // 简单字符串 直接读取字符串内容并返回
             b'+' => {
@@ -96,77 +226,448 @@ impl Frame {
                 let string = String::from_utf8(line)?;
                 Ok(Frame::Error(string))
             }
-            // 数值
+            // 数值，可以是负数 :-1\r\n
             b':' => {
-                let value = get_decimal(src)?;
+                let value = get_signed(src)?;
                 Ok(Frame::Integer(value))
             }
-            b'$' => {
-                // redis协议中规定如果是-1/r/n的话 说明是空字符串
-                if b'-' == peek_u8(src)? {
-                    // todo
-                    let line = get_line(src)?;
-                    if line != b"-1" {
-                        return Err("protocol error;Invalid frame format".into());
-                    }
+            // $6\r\nfoobar\r\n
+            // redis协议中规定如果是$-1\r\n的话 说明是空字符串
+            b'$' => match get_length(src)? {
+                Some(len) => Ok(Frame::Bulk(get_bytes(src, len)?)),
+                None => Ok(Frame::Null),
+            },
+            // *2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n
+            // *-1\r\n 表示空数组，同样当作Null处理
+            b'*' => match get_length(src)? {
+                Some(len) => Ok(Frame::Array(parse_vec(src, len)?)),
+                None => Ok(Frame::Null),
+            },
+            b'~' => {
+                let len = get_required_length(src)?;
+                Ok(Frame::Set(parse_vec(src, len)?))
+            }
+            b'>' => {
+                let len = get_required_length(src)?;
+                Ok(Frame::Push(parse_vec(src, len)?))
+            }
+            b'%' => {
+                let len = get_required_length(src)?;
+                Ok(Frame::Map(parse_pairs(src, len)?))
+            }
+            b'|' => {
+                let len = get_required_length(src)?;
+                let attributes = parse_pairs(src, len)?;
+                let data = Box::new(Frame::parse(src)?);
+                Ok(Frame::Attribute { attributes, data })
+            }
+            // _\r\n
+            b'_' => {
+                if !get_line(src)?.is_empty() {
+                    return Err("protocol error;Invalid frame format".into());
+                }
+                Ok(Frame::Null)
+            }
+            b',' => {
+                let line = std::str::from_utf8(get_line(src)?)
+                    .map_err(|_| "protocol error;invalid double")?;
+                let value = match line {
+                    "inf" | "+inf" => f64::INFINITY,
+                    "-inf" => f64::NEG_INFINITY,
+                    _ => line
+                        .parse::<f64>()
+                        .map_err(|_| "protocol error;invalid double")?,
+                };
+                Ok(Frame::Double(value))
+            }
+            b'#' => match get_line(src)? {
+                b"t" => Ok(Frame::Boolean(true)),
+                b"f" => Ok(Frame::Boolean(false)),
+                _ => Err("protocol error;invalid boolean".into()),
+            },
+            b'(' => {
+                let line = get_line(src)?.to_vec();
+                let string = String::from_utf8(line)?;
+                Ok(Frame::BigNumber(string))
+            }
+            // =15\r\ntxt:Some string\r\n
+            b'=' => {
+                let len = get_required_length(src)?;
+                let data = get_bytes(src, len)?;
+                // 前4个字节是格式和冒号
+                if len < 4 || data[3] != b':' {
+                    return Err("protocol error;invalid verbatim string".into());
+                }
+                let format = String::from_utf8(data[..3].to_vec())?;
+                Ok(Frame::Verbatim {
+                    format,
+                    data: data.slice(4..),
+                })
+            }
+            actual => Err(format!("protocol error;invalid frame byte type,{}", actual).into()),
+        }
+    }
 
-                    Ok(Frame::Null)
-                } else {
-                    // $6\r\nfoobar\r\n
-                    let len = get_decimal(src)? as usize;
-                    let start = len + 2;
-                    if src.remaining() < start {
-                        return Err(Error::Incomplete);
-                    }
-                    let data = Bytes::copy_from_slice(&src.chunk()[start..]);
-                    skip(src, start)?;
-                    Ok(Frame::Bulk(data))
+    // 将frame编码成字节写入dst
+    // RESP2的连接无法识别RESP3新增的类型，按照redis的做法转换成含义相近的RESP2类型
+    pub fn encode(&self, dst: &mut BytesMut, protocol: Protocol) {
+        let resp3 = protocol == Protocol::Resp3;
+
+        match self {
+            Frame::Simple(v) => {
+                dst.put_u8(b'+');
+                dst.put_slice(v.as_bytes());
+                dst.put_slice(b"\r\n");
+            }
+            Frame::Error(v) => {
+                dst.put_u8(b'-');
+                dst.put_slice(v.as_bytes());
+                dst.put_slice(b"\r\n");
+            }
+            Frame::Integer(v) => {
+                dst.put_u8(b':');
+                put_decimal(dst, *v);
+            }
+            Frame::Bulk(v) => put_bulk(dst, v),
+            // redis协议中用$-1\r\n表示空值，RESP3中使用_\r\n
+            Frame::Null if resp3 => dst.put_slice(b"_\r\n"),
+            Frame::Null => dst.put_slice(b"$-1\r\n"),
+            Frame::Array(v) => put_vec(dst, b'*', v, protocol),
+            Frame::Set(v) if resp3 => put_vec(dst, b'~', v, protocol),
+            Frame::Push(v) if resp3 => put_vec(dst, b'>', v, protocol),
+            Frame::Set(v) | Frame::Push(v) => put_vec(dst, b'*', v, protocol),
+            Frame::Map(pairs) if resp3 => put_pairs(dst, b'%', pairs, protocol),
+            // RESP2中Map会被展开成 [key1, value1, key2, value2...] 的数组
+            Frame::Map(pairs) => {
+                dst.put_u8(b'*');
+                put_decimal(dst, (pairs.len() * 2) as i64);
+                for (key, value) in pairs {
+                    key.encode(dst, protocol);
+                    value.encode(dst, protocol);
+                }
+            }
+            Frame::Double(v) if resp3 => {
+                dst.put_u8(b',');
+                dst.put_slice(format_double(*v).as_bytes());
+                dst.put_slice(b"\r\n");
+            }
+            Frame::Double(v) => put_bulk(dst, format_double(*v).as_bytes()),
+            Frame::Boolean(v) if resp3 => {
+                dst.put_slice(if *v { b"#t\r\n" } else { b"#f\r\n" });
+            }
+            Frame::Boolean(v) => {
+                dst.put_u8(b':');
+                put_decimal(dst, *v as i64);
+            }
+            Frame::BigNumber(v) if resp3 => {
+                dst.put_u8(b'(');
+                dst.put_slice(v.as_bytes());
+                dst.put_slice(b"\r\n");
+            }
+            Frame::BigNumber(v) => put_bulk(dst, v.as_bytes()),
+            Frame::Verbatim { format, data } if resp3 => {
+                dst.put_u8(b'=');
+                put_decimal(dst, (data.len() + 4) as i64);
+                dst.put_slice(format.as_bytes());
+                dst.put_u8(b':');
+                dst.put_slice(data);
+                dst.put_slice(b"\r\n");
+            }
+            Frame::Verbatim { data, .. } => put_bulk(dst, data),
+            Frame::Attribute { attributes, data } => {
+                // RESP2中没有对应的类型，直接丢弃附加信息
+                if resp3 {
+                    put_pairs(dst, b'|', attributes, protocol);
                 }
+                data.encode(dst, protocol);
+            }
+        }
+    }
+}
+
+fn check_depth(src: &mut Cursor<&[u8]>, limits: &Limits, depth: usize) -> Result<(), Error> {
+    match get_u8(src)? {
+        b'+' | b'-' | b':' | b'_' | b',' | b'#' | b'(' => {
+            check_line_len(src, limits)?;
+            get_line(src)?;
+            Ok(())
+        }
+        // $5\r\nhello\r\n
+        // 先读取二进制字符串的长度，然后跳过相应的长度(需要加上\r\n)
+        b'$' | b'=' => match get_length(src)? {
+            Some(len) if len > limits.max_bulk_len => Err(format!(
+                "protocol error;bulk length {} exceeds limit {}",
+                len, limits.max_bulk_len
+            )
+            .into()),
+            Some(len) => skip(src, len + 2),
+            None => Ok(()),
+        },
+        // *3\r\n$3\r\nfoo\r\n$3\r\nbar\r\n$3\r\nbaz\r\n
+        ty @ (b'*' | b'~' | b'>' | b'%' | b'|') => {
+            if depth >= limits.max_depth {
+                return Err(format!(
+                    "protocol error;nesting depth exceeds limit {}",
+                    limits.max_depth
+                )
+                .into());
             }
-            b'*' => {
-                // *2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n
-                let len = get_decimal(src)? as usize;
-                let mut res = Vec::with_capacity(len);
 
-                for _ in 0..len {
-                    res.push(Frame::parse(src)?);
+            let len = match get_length(src)? {
+                Some(len) if len > limits.max_elements => {
+                    return Err(format!(
+                        "protocol error;aggregate length {} exceeds limit {}",
+                        len, limits.max_elements
+                    )
+                    .into())
                 }
+                Some(len) => len,
+                // *-1\r\n
+                None => return Ok(()),
+            };
 
-                Ok(Frame::Array(res))
+            // Map的每个元素都是一个键值对，Attribute之后还跟着一个真正的响应frame
+            let frames = match ty {
+                b'%' => len * 2,
+                b'|' => len * 2 + 1,
+                _ => len,
+            };
+
+            for _ in 0..frames {
+                check_depth(src, limits, depth + 1)?;
+            }
+            Ok(())
+        }
+        actual => Err(format!("protocol error;invalid frame byte type,{}", actual).into()),
+    }
+}
+
+// 一行的长度不能超过max_inline_len，一直收不到\r\n的话也不能无限等待下去，否则缓冲区会无限增长
+fn check_line_len(src: &Cursor<&[u8]>, limits: &Limits) -> Result<(), Error> {
+    let max = limits.max_inline_len;
+    let rest = &src.get_ref()[src.position() as usize..];
+    // 长度不超过限制的一行，\r\n一定出现在前max + 2个字节中
+    let window = &rest[..rest.len().min(max + 2)];
+
+    let too_long = !window.windows(2).any(|w| w == b"\r\n")
+        && match window.len() {
+            len if len == max + 2 => true,
+            // 最后一个字节是\r的话，可能下一个字节就是\n，需要再等一等
+            len if len == max + 1 => window[max] != b'\r',
+            _ => false,
+        };
+
+    if too_long {
+        return Err(format!("protocol error;line length exceeds limit {}", max).into());
+    }
+
+    Ok(())
+}
+
+fn parse_vec(src: &mut Cursor<Bytes>, len: usize) -> Result<Vec<Frame>, Error> {
+    // 每个frame至少占3个字节，len不会超过剩余数据的长度，避免按照错误的len预分配过多内存
+    let mut res = Vec::with_capacity(len.min(src.remaining()));
+
+    for _ in 0..len {
+        res.push(Frame::parse(src)?);
+    }
+
+    Ok(res)
+}
+
+fn parse_pairs(src: &mut Cursor<Bytes>, len: usize) -> Result<Vec<(Frame, Frame)>, Error> {
+    let mut res = Vec::with_capacity(len.min(src.remaining()));
+
+    for _ in 0..len {
+        let key = Frame::parse(src)?;
+        let value = Frame::parse(src)?;
+        res.push((key, value));
+    }
+
+    Ok(res)
+}
+
+fn put_decimal(dst: &mut BytesMut, v: i64) {
+    dst.put_slice(v.to_string().as_bytes());
+    dst.put_slice(b"\r\n");
+}
+
+fn put_bulk(dst: &mut BytesMut, v: &[u8]) {
+    dst.put_u8(b'$');
+    put_decimal(dst, v.len() as i64);
+    dst.put_slice(v);
+    dst.put_slice(b"\r\n");
+}
+
+fn put_vec(dst: &mut BytesMut, prefix: u8, v: &[Frame], protocol: Protocol) {
+    dst.put_u8(prefix);
+    put_decimal(dst, v.len() as i64);
+    for item in v {
+        item.encode(dst, protocol);
+    }
+}
+
+fn put_pairs(dst: &mut BytesMut, prefix: u8, pairs: &[(Frame, Frame)], protocol: Protocol) {
+    dst.put_u8(prefix);
+    put_decimal(dst, pairs.len() as i64);
+    for (key, value) in pairs {
+        key.encode(dst, protocol);
+        value.encode(dst, protocol);
+    }
+}
+
+// redis使用inf、-inf、nan表示特殊的浮点数
+fn format_double(v: f64) -> String {
+    if v.is_nan() {
+        "nan".to_string()
+    } else if v.is_infinite() {
+        if v > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        v.to_string()
+    }
+}
+
+// 按照redis的规则将一行内联命令拆分成多个参数，redis-cli也使用同样的规则解析用户的输入
+// 参数之间用空白字符分隔，双引号内支持 \n \t \xHH 等转义，单引号内只支持 \'
+//   set "hello world" 'it\'s'  =>  ["set", "hello world", "it's"]
+pub fn split_args(line: &[u8]) -> Result<Vec<Bytes>, Error> {
+    const INVALID: &str = "protocol error;unbalanced quotes in request";
+
+    let mut args = vec![];
+    let mut chars = line.iter().copied().peekable();
+
+    loop {
+        // 跳过参数之间的空白字符
+        while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+
+        let first = match chars.peek() {
+            Some(&c) => c,
+            None => return Ok(args),
+        };
+
+        let mut arg = vec![];
+
+        match first {
+            b'"' => {
+                chars.next();
+                loop {
+                    match chars.next().ok_or(INVALID)? {
+                        b'"' => break,
+                        b'\\' => match chars.next().ok_or(INVALID)? {
+                            b'n' => arg.push(b'\n'),
+                            b'r' => arg.push(b'\r'),
+                            b't' => arg.push(b'\t'),
+                            b'b' => arg.push(0x08),
+                            b'a' => arg.push(0x07),
+                            b'x' => {
+                                let hi = chars.next().ok_or(INVALID)?;
+                                let lo = chars.next().ok_or(INVALID)?;
+                                let hex = [hi, lo];
+                                let hex = std::str::from_utf8(&hex).map_err(|_| INVALID)?;
+                                arg.push(u8::from_str_radix(hex, 16).map_err(|_| INVALID)?);
+                            }
+                            c => arg.push(c),
+                        },
+                        c => arg.push(c),
+                    }
+                }
+            }
+            b'\'' => {
+                chars.next();
+                loop {
+                    match chars.next().ok_or(INVALID)? {
+                        b'\'' => break,
+                        b'\\' if chars.peek() == Some(&b'\'') => {
+                            chars.next();
+                            arg.push(b'\'');
+                        }
+                        c => arg.push(c),
+                    }
+                }
             }
             _ => {
-                unimplemented!()
+                while let Some(c) = chars.next_if(|c| !c.is_ascii_whitespace()) {
+                    arg.push(c);
+                }
             }
         }
+
+        // 引号结束后必须紧跟空白字符或者行尾
+        if matches!(first, b'"' | b'\'') && chars.peek().is_some_and(|c| !c.is_ascii_whitespace()) {
+            return Err(INVALID.into());
+        }
+
+        args.push(Bytes::from(arg));
     }
 }
 
-fn get_decimal(src: &mut Cursor<&[u8]>) -> Result<u64, Error> {
-    // 将字符串的转换成十进制的数据
-    // 1234556hello => 1234556
-    use atoi::atoi;
-
+// 读取一行并转换成有符号整数，整行都必须是合法的数字
+// -1234556\r\n => -1234556
+fn get_signed<T: AsRef<[u8]>>(src: &mut Cursor<T>) -> Result<i64, Error> {
     let line = get_line(src)?;
 
-    atoi::<u64>(line).ok_or_else(|| "protocol error:invalid frame format".into())
+    std::str::from_utf8(line)
+        .ok()
+        .and_then(|line| line.parse::<i64>().ok())
+        .ok_or_else(|| "protocol error;invalid frame format".into())
 }
 
-fn get_line<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a [u8], Error> {
+// 读取Bulk、数组等类型的长度，-1表示Null，返回None
+fn get_length<T: AsRef<[u8]>>(src: &mut Cursor<T>) -> Result<Option<usize>, Error> {
+    match get_signed(src)? {
+        -1 => Ok(None),
+        len if len < 0 => Err("protocol error;invalid length".into()),
+        len => usize::try_from(len)
+            .map(Some)
+            .map_err(|_| "protocol error;invalid length".into()),
+    }
+}
+
+// RESP3新增的类型不允许使用-1表示Null
+fn get_required_length<T: AsRef<[u8]>>(src: &mut Cursor<T>) -> Result<usize, Error> {
+    get_length(src)?.ok_or_else(|| "protocol error;invalid length".into())
+}
+
+// 从src中取出len个字节，返回的Bytes和src共享同一块内存
+fn get_bytes(src: &mut Cursor<Bytes>, len: usize) -> Result<Bytes, Error> {
     let start = src.position() as usize;
-    let end = src.get_ref().len() - 1;
+    let end = start
+        .checked_add(len)
+        .ok_or("protocol error;invalid length")?;
 
-    for i in start..end {
-        if src.get_ref()[i] == b'\r' && src.get_ref()[i + 1] == b'\n' {
-            src.set_position((i + 2) as u64);
-            let line: &[u8] = &src.get_ref()[start..i];
-            return Ok(line);
-        }
+    if src.get_ref().len() < end + 2 {
+        return Err(Error::Incomplete);
+    }
+
+    if &src.get_ref()[end..end + 2] != b"\r\n" {
+        return Err("protocol error;invalid frame format".into());
     }
 
-    Err(Error::Incomplete)
+    let data = src.get_ref().slice(start..end);
+    src.set_position((end + 2) as u64);
+
+    Ok(data)
+}
+
+fn get_line<T: AsRef<[u8]>>(src: &mut Cursor<T>) -> Result<&[u8], Error> {
+    let start = src.position() as usize;
+    let buf = src.get_ref().as_ref();
+
+    let end = match buf.get(start..).and_then(|rest| {
+        rest.windows(2)
+            .position(|window| window == b"\r\n")
+            .map(|i| start + i)
+    }) {
+        Some(end) => end,
+        None => return Err(Error::Incomplete),
+    };
+
+    src.set_position((end + 2) as u64);
+
+    Ok(&src.get_ref().as_ref()[start..end])
 }
 
-fn skip(src: &mut Cursor<&[u8]>, n: usize) -> Result<(), Error> {
+fn skip<T: AsRef<[u8]>>(src: &mut Cursor<T>, n: usize) -> Result<(), Error> {
     if src.remaining() < n {
         return Err(Error::Incomplete);
     }
@@ -174,14 +675,7 @@ fn skip(src: &mut Cursor<&[u8]>, n: usize) -> Result<(), Error> {
     Ok(())
 }
 
-fn peek_u8(src: &mut Cursor<&[u8]>) -> Result<u8, Error> {
-    if !src.has_remaining() {
-        return Err(Error::Incomplete);
-    }
-    Ok(src.chunk()[0])
-}
-
-fn get_u8(src: &mut Cursor<&[u8]>) -> Result<u8, Error> {
+fn get_u8<T: AsRef<[u8]>>(src: &mut Cursor<T>) -> Result<u8, Error> {
     if !src.has_remaining() {
         return Err(Error::Incomplete);
     }
@@ -208,8 +702,8 @@ impl From<FromUtf8Error> for Error {
 
 impl std::error::Error for Error {}
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::Incomplete => "stream ended early".fmt(f),
             Error::Other(err) => err.fmt(f),
@@ -20,9 +20,7 @@ impl Parse {
     pub fn new(frame: Frame) -> Result<Parse, ParseError> {
         let array = match frame {
             Frame::Array(v) => v,
-            _ => {
-                return Err(format!("protocol error:expected array,got {:?}", frame).into());
-            }
+            _ => return Err(format!("protocol error:expected array,got {:?}", frame).into()),
         };
 
         Ok(Parse {
@@ -40,9 +38,7 @@ impl Parse {
             Frame::Bulk(data) => str::from_utf8(&data[..])
                 .map(|v| v.to_string())
                 .map_err(|_| "protocol error;invalid string".into()),
-            frame => {
-                return Err(format!("protocol error,expected string,got {:?}", frame).into());
-            }
+            frame => Err(format!("protocol error,expected string,got {:?}", frame).into()),
         }
     }
 
@@ -50,9 +46,7 @@ impl Parse {
         match self.next()? {
             Frame::Simple(s) => Ok(Bytes::from(s.into_bytes())),
             Frame::Bulk(data) => Ok(data),
-            frame => {
-                return Err(format!("protocol error,expected bytes,got {:?}", frame).into());
-            }
+            frame => Err(format!("protocol error,expected bytes,got {:?}", frame).into()),
         }
     }
 
@@ -62,15 +56,69 @@ impl Parse {
         const MSG: &str = "protocol error;invalid number";
 
         match self.next()? {
-            Frame::Integer(v) => Ok(v),
+            Frame::Integer(v) => u64::try_from(v).map_err(|_| MSG.into()),
             Frame::Simple(s) => atoi::<u64>(s.as_bytes()).ok_or_else(|| MSG.into()),
             Frame::Bulk(data) => atoi::<u64>(&data).ok_or_else(|| MSG.into()),
-            frame => {
-                return Err(format!("protocol error,expected int frame,got {:?}", frame).into());
+            frame => Err(format!("protocol error,expected int frame,got {:?}", frame).into()),
+        }
+    }
+
+    // 有符号整数，例如INCRBY的增量，和redis一样不合法时返回"value is not an integer or out of range"
+    pub fn next_signed(&mut self) -> Result<i64, ParseError> {
+        const MSG: &str = "value is not an integer or out of range";
+
+        match self.next()? {
+            Frame::Integer(v) => Ok(v),
+            Frame::Simple(s) => parse_i64(s.as_bytes()).ok_or_else(|| MSG.into()),
+            Frame::Bulk(data) => parse_i64(&data).ok_or_else(|| MSG.into()),
+            frame => Err(format!("protocol error,expected int frame,got {:?}", frame).into()),
+        }
+    }
+
+    // 浮点数，例如INCRBYFLOAT的增量，不接受nan和inf
+    pub fn next_float(&mut self) -> Result<f64, ParseError> {
+        const MSG: &str = "value is not a valid float";
+
+        match self.next()? {
+            Frame::Integer(v) => Ok(v as f64),
+            Frame::Double(v) if v.is_finite() => Ok(v),
+            Frame::Simple(s) => parse_f64(s.as_bytes()).ok_or_else(|| MSG.into()),
+            Frame::Bulk(data) => parse_f64(&data).ok_or_else(|| MSG.into()),
+            _ => Err(MSG.into()),
+        }
+    }
+
+    // 有序集合的分数，和next_float不同的是允许inf、+inf、-inf，但是同样不接受nan
+    pub fn next_score(&mut self) -> Result<f64, ParseError> {
+        const MSG: &str = "value is not a valid float";
+
+        match self.next()? {
+            Frame::Integer(v) => Ok(v as f64),
+            Frame::Double(v) if !v.is_nan() => Ok(v),
+            Frame::Simple(s) => parse_score(s.as_bytes()).ok_or_else(|| MSG.into()),
+            Frame::Bulk(data) => parse_score(&data).ok_or_else(|| MSG.into()),
+            _ => Err(MSG.into()),
+        }
+    }
+
+    // 读取剩下的所有参数，例如DEL key [key ...]中的key
+    pub fn remaining_strings(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut strings = vec![];
+
+        loop {
+            match self.next_string() {
+                Ok(s) => strings.push(s),
+                Err(ParseError::EndOfStream) => return Ok(strings),
+                Err(err) => return Err(err),
             }
         }
     }
 
+    // 剩下的参数数量
+    pub fn remaining(&self) -> usize {
+        self.parts.len()
+    }
+
     pub fn finish(&mut self) -> Result<(), ParseError> {
         if self.parts.next().is_none() {
             Result::Ok(())
@@ -80,6 +128,26 @@ impl Parse {
     }
 }
 
+// 按照redis的规则把字符串解析成i64：不允许前后空格和+号，超出范围时返回None
+pub(crate) fn parse_i64(src: &[u8]) -> Option<i64> {
+    if src.first() == Some(&b'+') {
+        return None;
+    }
+    str::from_utf8(src).ok()?.parse().ok()
+}
+
+// 把字符串解析成有限的f64，nan、inf以及带空格的字符串都会返回None
+pub(crate) fn parse_f64(src: &[u8]) -> Option<f64> {
+    let v: f64 = str::from_utf8(src).ok()?.parse().ok()?;
+    v.is_finite().then_some(v)
+}
+
+// 和redis解析有序集合的分数一样，允许inf、+inf、-inf，nan以及带空格的字符串会返回None
+pub(crate) fn parse_score(src: &[u8]) -> Option<f64> {
+    let v: f64 = str::from_utf8(src).ok()?.parse().ok()?;
+    (!v.is_nan()).then_some(v)
+}
+
 // 将String类型转换为ParseError
 impl From<String> for ParseError {
     fn from(value: String) -> Self {
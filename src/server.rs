@@ -1,24 +1,53 @@
-use std::{future::Future, sync::Arc};
+#[cfg(unix)]
+use std::{
+    fs,
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+    path::Path,
+};
+use std::{
+    future::{self, Future},
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
 
+#[cfg(unix)]
+use tokio::net::UnixListener;
 use tokio::{
-    net::{TcpListener, TcpStream},
+    net::TcpListener,
     sync::{broadcast, mpsc, Semaphore},
     time::{self, Duration},
 };
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 use crate::{
-    connection::Connection,
-    db::{Db, DbDropGuard},
-    shutdown,
+    cmd::Command,
+    connection::{Connection, Transport},
+    db::{Db, DbDropGuard, EncodingConfig, ExpireStrategy},
+    frame::Frame,
 };
 
 use crate::shutdown::Shutdown;
-// 实现一个server.run方法，可以传入一个TcpListener，能够获取到请求，对请求进行处理
+// 实现一个server.run方法，可以传入一组Acceptor，能够获取到请求，对请求进行处理
+
+// 服务器监听的socket，TCP和Unix domain socket的客户端都会得到一个Box<dyn Transport>，
+// 之后的处理流程完全相同
+#[derive(Debug)]
+pub enum Acceptor {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
 
 struct Listener {
     db_holder: DbDropGuard,
-    listener: TcpListener,
+    // 所有的Acceptor共享同一个Db和连接数限制
+    acceptors: Vec<Acceptor>,
+    // 每次从不同的Acceptor开始轮询，避免繁忙的TCP端口让Unix socket上的连接一直得不到处理
+    next_acceptor: AtomicUsize,
     limit_connections: Arc<Semaphore>,
     notify_shutdown: broadcast::Sender<()>,
     shutdowm_complete_tx: mpsc::Sender<()>,
@@ -28,21 +57,47 @@ struct Handler {
     db: Db,
     connection: Connection,
     shutdown: Shutdown,
+    // 当handler被drop时，sender也会随之drop，server以此得知所有连接都已处理完毕
+    _shutdown_complete: mpsc::Sender<()>,
+}
+
+// 服务器的配置项
+#[derive(Debug, Clone)]
+pub struct Config {
+    // 收到关闭信号之后，最多等待多久让连接处理完正在执行的命令，超时后直接退出
+    pub shutdown_timeout: Duration,
+    // 后台任务主动清除过期key的策略
+    pub expire_strategy: ExpireStrategy,
+    // 集合类型使用紧凑编码的阈值
+    pub encoding: EncodingConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            shutdown_timeout: Duration::from_secs(10),
+            expire_strategy: ExpireStrategy::default(),
+            encoding: EncodingConfig::default(),
+        }
+    }
 }
 
 // 最大连接数
 const MAX_CONNECTIONS: usize = 250;
 
-pub async fn run(listener: TcpListener, shutdown: impl Future) {
+// 同时在多个socket上接受连接，例如一个TCP端口加上一个Unix socket
+// shutdown完成时开始优雅关闭：不再接受新的连接，通知所有连接退出，并等待它们处理完当前的命令
+pub async fn run(acceptors: Vec<Acceptor>, config: Config, shutdown: impl Future) {
     let (notify_shutdown, _) = broadcast::channel(1);
-    let (shutdowm_complete_tx, _) = mpsc::channel(1);
+    let (shutdowm_complete_tx, mut shutdown_complete_rx) = mpsc::channel(1);
 
     let server: Listener = Listener {
-        db_holder: DbDropGuard::new(),
-        listener,
+        db_holder: DbDropGuard::with_config(config.expire_strategy, config.encoding),
+        acceptors,
+        next_acceptor: AtomicUsize::new(0),
         limit_connections: Arc::new(Semaphore::new(MAX_CONNECTIONS)),
-        notify_shutdown: notify_shutdown,
-        shutdowm_complete_tx: shutdowm_complete_tx,
+        notify_shutdown,
+        shutdowm_complete_tx,
     };
 
     tokio::select! {
@@ -56,8 +111,32 @@ pub async fn run(listener: TcpListener, shutdown: impl Future) {
         }
     }
 
-    // drop掉notify_shutdown
-    // TODO
+    let Listener {
+        db_holder,
+        acceptors,
+        notify_shutdown,
+        shutdowm_complete_tx,
+        ..
+    } = server;
+
+    // 关闭监听的socket，不再接受新的连接
+    drop(acceptors);
+
+    // drop掉notify_shutdown，所有订阅了的Shutdown都会收到关闭信号
+    drop(notify_shutdown);
+    // 只有所有的Handler都退出之后，所有的sender才会被drop，recv才会返回None
+    drop(shutdowm_complete_tx);
+
+    let drained = time::timeout(config.shutdown_timeout, shutdown_complete_rx.recv()).await;
+    if drained.is_err() {
+        info!(
+            timeout = ?config.shutdown_timeout,
+            "connections did not finish in time, shutting down anyway"
+        );
+    }
+
+    // 最后drop掉db_holder，通知后台任务退出并清除数据
+    drop(db_holder);
 }
 
 impl Listener {
@@ -75,8 +154,9 @@ impl Listener {
 
             let mut handler = Handler {
                 db: self.db_holder.db(),
-                connection: Connection::new(socket),
+                connection: Connection::for_server(socket),
                 shutdown: Shutdown::new(self.notify_shutdown.subscribe()),
+                _shutdown_complete: self.shutdowm_complete_tx.clone(),
             };
 
             // 开启一个新的线程来处理
@@ -89,12 +169,26 @@ impl Listener {
             });
         }
     }
-    // 开始接受tcpStream
-    async fn accept(&self) -> crate::Result<TcpStream> {
+    // 从任意一个Acceptor上接受新的连接
+    async fn accept(&self) -> crate::Result<Box<dyn Transport>> {
         let mut backoff = 1;
         loop {
-            match self.listener.accept().await {
-                Ok((socket, _)) => return Ok(socket),
+            let res = future::poll_fn(|cx| {
+                let len = self.acceptors.len();
+                let start = self.next_acceptor.fetch_add(1, Ordering::Relaxed);
+
+                for i in 0..len {
+                    let acceptor = &self.acceptors[(start + i) % len];
+                    if let Poll::Ready(res) = acceptor.poll_accept(cx) {
+                        return Poll::Ready(res);
+                    }
+                }
+                Poll::Pending
+            })
+            .await;
+
+            match res {
+                Ok(socket) => return Ok(socket),
                 Err(e) => {
                     // 如果重试次数大于64的话，返回错误
                     if backoff > 64 {
@@ -110,27 +204,116 @@ impl Listener {
     }
 }
 
+impl Acceptor {
+    fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<Box<dyn Transport>>> {
+        match self {
+            Acceptor::Tcp(listener) => listener
+                .poll_accept(cx)
+                .map_ok(|(socket, _)| Box::new(socket) as Box<dyn Transport>),
+            #[cfg(unix)]
+            Acceptor::Unix(listener) => listener
+                .poll_accept(cx)
+                .map_ok(|(socket, _)| Box::new(socket) as Box<dyn Transport>),
+        }
+    }
+}
+
+// 在path上监听Unix domain socket，mode不为None时修改socket文件的权限
+// 上次运行残留的socket文件会导致bind失败，先删除掉，但是不能删除其他类型的文件
+#[cfg(unix)]
+pub fn bind_unix(path: &Path, mode: Option<u32>) -> crate::Result<Acceptor> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(format!("{} already exists and is not a socket", path.display()).into())
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    let listener = UnixListener::bind(path)?;
+
+    if let Some(mode) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+
+    Ok(listener.into())
+}
+
+impl From<TcpListener> for Acceptor {
+    fn from(listener: TcpListener) -> Acceptor {
+        Acceptor::Tcp(listener)
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixListener> for Acceptor {
+    fn from(listener: UnixListener) -> Acceptor {
+        Acceptor::Unix(listener)
+    }
+}
+
 impl Handler {
     // 处理请求
     // 从TcpStream中读取出frame，并将响应信息写入TcpStream中
     async fn run(&mut self) -> crate::Result<()> {
         while !self.shutdown.is_shutdown() {
             let maybe_frame = tokio::select! {
-                res = self.connection.read_frame() => {
-                    res?
-                }
+                // 关闭信号优先，即使缓冲区中还有命令也不再继续读取
+                biased;
                 _ = self.shutdown.recv() => {
                     return Ok(())
                 }
+                res = self.connection.read_frame() => match res {
+                    Ok(maybe_frame) => maybe_frame,
+                    // 协议错误时连接中剩下的数据已经无法解析，告知客户端原因之后关闭连接
+                    Err(err) => {
+                        let response = Frame::Error(format!("ERR {}", err));
+                        let _ = self.connection.write_frame(&response).await;
+                        let _ = self.connection.flush().await;
+                        return Err(err);
+                    }
+                },
             };
 
-            let frame = match maybe_frame {
+            let mut frame = match maybe_frame {
                 Some(frame) => frame,
                 None => {
                     return Ok(());
                 }
             };
+
+            // 客户端使用pipeline时，缓冲区中可能已经有多个完整的命令
+            // 依次执行完这一批命令之后再flush，所有的响应只需要一次写入
+            loop {
+                self.apply_frame(frame).await?;
+
+                frame = match self.connection.read_buffered_frame()? {
+                    Some(frame) => frame,
+                    None => break,
+                };
+            }
+
+            self.connection.flush().await?;
         }
         Ok(())
     }
+
+    // 将frame解析成具体的命令，然后在db上执行，执行结果会通过connection写回给客户端
+    async fn apply_frame(&mut self, frame: Frame) -> crate::Result<()> {
+        let cmd = match Command::from_frame(frame) {
+            Ok(cmd) => cmd,
+            // 命令的参数有误时把错误信息返回给客户端，连接可以继续使用
+            Err(err) => {
+                let response = Frame::Error(format!("ERR {}", err));
+                self.connection.write_frame(&response).await?;
+                return Ok(());
+            }
+        };
+
+        debug!(?cmd);
+
+        cmd.apply(&self.db, &mut self.connection, &mut self.shutdown)
+            .await
+    }
 }
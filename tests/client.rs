@@ -0,0 +1,282 @@
+use std::{future, net::SocketAddr};
+
+use bytes::Bytes;
+use mini_redis::{
+    client::{Client, Error, Subscriber},
+    frame::Frame,
+    server::{self, Acceptor, Config},
+};
+use tokio::net::TcpListener;
+use tokio_stream::StreamExt;
+
+async fn start_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(server::run(
+        vec![Acceptor::Tcp(listener)],
+        Config::default(),
+        future::pending::<()>(),
+    ));
+
+    addr
+}
+
+fn channels(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+async fn subscriber(addr: SocketAddr, names: &[&str]) -> Subscriber {
+    let client = Client::connect(addr).await.unwrap();
+    client.subscribe(channels(names)).await.unwrap()
+}
+
+// 服务器对重复的channel只保留一个订阅，客户端记录的列表也不能重复
+#[tokio::test]
+async fn subscribe_ignores_duplicate_channels() {
+    let addr = start_server().await;
+    let mut subscriber = subscriber(addr, &["a", "a"]).await;
+    assert_eq!(subscriber.get_subscribed(), channels(&["a"]));
+
+    subscriber.subscribe(&channels(&["b", "a"])).await.unwrap();
+    assert_eq!(subscriber.get_subscribed(), channels(&["a", "b"]));
+
+    // 只取消一次就不再订阅a了
+    subscriber.unsubscribe(&channels(&["a"])).await.unwrap();
+    assert_eq!(subscriber.get_subscribed(), channels(&["b"]));
+}
+
+// 取消没有订阅过的channel不是错误，服务器同样会为它回复一次
+#[tokio::test]
+async fn unsubscribe_channel_that_was_not_subscribed() {
+    let addr = start_server().await;
+    let mut subscriber = subscriber(addr, &["a", "b"]).await;
+
+    subscriber
+        .unsubscribe(&channels(&["missing", "a", "a"]))
+        .await
+        .unwrap();
+    assert_eq!(subscriber.get_subscribed(), channels(&["b"]));
+
+    // 所有的响应都已经读取完，后续的命令不会读到残留的响应
+    subscriber.subscribe(&channels(&["c"])).await.unwrap();
+    assert_eq!(subscriber.get_subscribed(), channels(&["b", "c"]));
+}
+
+// 不带参数时取消所有订阅，一个订阅都没有时服务器仍然会回复一次
+#[tokio::test]
+async fn unsubscribe_all_reads_every_reply() {
+    let addr = start_server().await;
+    let mut subscriber = subscriber(addr, &["a", "b"]).await;
+
+    subscriber.unsubscribe(&[]).await.unwrap();
+    assert!(subscriber.get_subscribed().is_empty());
+
+    subscriber.unsubscribe(&[]).await.unwrap();
+    assert!(subscriber.get_subscribed().is_empty());
+
+    subscriber.subscribe(&channels(&["c"])).await.unwrap();
+    assert_eq!(subscriber.get_subscribed(), channels(&["c"]));
+}
+
+#[tokio::test]
+async fn subscriber_is_a_stream_of_messages() {
+    let addr = start_server().await;
+    let mut subscriber = subscriber(addr, &["news"]).await;
+
+    let mut publisher = Client::connect(addr).await.unwrap();
+    for content in ["one", "two"] {
+        let received = publisher
+            .publish("news", Bytes::from_static(content.as_bytes()))
+            .await
+            .unwrap();
+        assert_eq!(received, 1);
+    }
+
+    for content in ["one", "two"] {
+        let message = subscriber.next().await.unwrap().unwrap();
+        assert_eq!(message.channel, "news");
+        assert_eq!(message.content, content);
+    }
+}
+
+// pipeline中的命令按顺序返回响应，解析失败或者执行失败的命令只影响自己对应的位置
+#[tokio::test]
+async fn pipeline_replies_come_back_in_order() {
+    let addr = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let replies = client
+        .pipeline()
+        .set("a", Bytes::from_static(b"1"))
+        .cmd(vec![Bytes::from_static(b"get")])
+        .cmd(vec![Bytes::from_static(b"incr"), Bytes::from_static(b"a")])
+        .cmd(vec![Bytes::from_static(b"nosuchcommand")])
+        .set("b", Bytes::from_static(b"x"))
+        .cmd(vec![Bytes::from_static(b"incr"), Bytes::from_static(b"b")])
+        .get("a")
+        .ping(None)
+        .execute()
+        .await
+        .unwrap();
+
+    let replies: Vec<_> = replies
+        .into_iter()
+        .map(|reply| match reply {
+            Ok(frame) => frame.to_string(),
+            Err(err) => err.to_string(),
+        })
+        .collect();
+
+    assert_eq!(replies.len(), 8);
+    assert_eq!(replies[0], "OK");
+    assert!(replies[1].starts_with("ERR "), "{}", replies[1]);
+    assert_eq!(replies[2], "2");
+    assert!(
+        replies[3].starts_with("ERR unknown command"),
+        "{}",
+        replies[3]
+    );
+    assert_eq!(replies[4], "OK");
+    assert_eq!(replies[5], "ERR value is not an integer or out of range");
+    assert_eq!(replies[6], "2");
+    assert_eq!(replies[7], "PONG");
+
+    // pipeline之后连接仍然可以正常使用
+    assert_eq!(client.get("a").await.unwrap().unwrap(), "2");
+}
+
+fn args(args: &[&'static str]) -> Vec<Bytes> {
+    args.iter()
+        .map(|arg| Bytes::from_static(arg.as_bytes()))
+        .collect()
+}
+
+fn hello_proto(frame: &Frame) -> i64 {
+    let fields = match frame {
+        Frame::Map(pairs) => pairs.clone(),
+        // RESP2中Map被展开成 [key1, value1, key2, value2...]
+        Frame::Array(items) => items
+            .chunks(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect(),
+        frame => panic!("unexpected frame {:?}", frame),
+    };
+
+    match fields.iter().find(|(key, _)| *key == "proto") {
+        Some((_, Frame::Integer(proto))) => *proto,
+        field => panic!("unexpected proto field {:?}", field),
+    }
+}
+
+// HELLO 3之后Map、Double按照RESP3编码，HELLO 2切换回来之后又变回数组和字符串
+#[tokio::test]
+async fn hello_switches_reply_encoding() {
+    let addr = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .execute(args(&["hset", "h", "f", "v"]))
+        .await
+        .unwrap();
+    client
+        .execute(args(&["zadd", "z", "1.5", "m"]))
+        .await
+        .unwrap();
+
+    let hello = client.hello(None).await.unwrap();
+    assert!(matches!(hello, Frame::Array(_)));
+    assert_eq!(hello_proto(&hello), 2);
+    assert!(matches!(
+        client.execute(args(&["hgetall", "h"])).await.unwrap(),
+        Frame::Array(items) if items.len() == 2
+    ));
+    assert!(matches!(
+        client.execute(args(&["zscore", "z", "m"])).await.unwrap(),
+        Frame::Bulk(score) if score == "1.5"
+    ));
+
+    let hello = client.hello(Some(3)).await.unwrap();
+    assert!(matches!(hello, Frame::Map(_)));
+    assert_eq!(hello_proto(&hello), 3);
+    assert!(matches!(
+        client.execute(args(&["hgetall", "h"])).await.unwrap(),
+        Frame::Map(pairs) if pairs.len() == 1
+    ));
+    assert!(matches!(
+        client.execute(args(&["zscore", "z", "m"])).await.unwrap(),
+        Frame::Double(score) if score == 1.5
+    ));
+    assert!(matches!(
+        client.execute(args(&["get", "missing"])).await.unwrap(),
+        Frame::Null
+    ));
+
+    let hello = client.hello(Some(2)).await.unwrap();
+    assert_eq!(hello_proto(&hello), 2);
+    assert!(matches!(
+        client.execute(args(&["hgetall", "h"])).await.unwrap(),
+        Frame::Array(_)
+    ));
+}
+
+// 不支持的协议版本返回NOPROTO，连接的协议保持不变
+#[tokio::test]
+async fn hello_rejects_unsupported_version() {
+    let addr = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    for version in [1, 4] {
+        match client.hello(Some(version)).await {
+            Err(Error::Server(msg)) => assert!(msg.starts_with("NOPROTO"), "{}", msg),
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    assert_eq!(hello_proto(&client.hello(None).await.unwrap()), 2);
+}
+
+// RESP3的连接上订阅的确认和消息都是Push，订阅期间仍然可以执行普通命令
+#[tokio::test]
+async fn resp3_subscriber_receives_pushes() {
+    let addr = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client.hello(Some(3)).await.unwrap();
+
+    let reply = client.execute(args(&["subscribe", "news"])).await.unwrap();
+    assert!(matches!(
+        &reply,
+        Frame::Push(items) if items[0] == "subscribe" && items[1] == "news"
+    ));
+
+    let mut publisher = Client::connect(addr).await.unwrap();
+    publisher
+        .publish("news", Bytes::from_static(b"hi"))
+        .await
+        .unwrap();
+
+    let message = client.read_reply().await.unwrap();
+    match message {
+        Frame::Push(items) => {
+            assert_eq!(items.len(), 3);
+            assert_eq!(items[0], "message");
+            assert_eq!(items[1], "news");
+            assert_eq!(items[2], "hi");
+        }
+        frame => panic!("unexpected frame {:?}", frame),
+    }
+
+    // RESP2下订阅模式只能执行订阅相关的命令，RESP3没有这个限制
+    client.set("k", Bytes::from_static(b"v")).await.unwrap();
+    assert_eq!(client.get("k").await.unwrap().unwrap(), "v");
+
+    // Subscriber同样可以处理Push类型的确认和消息
+    let mut subscriber = Client::connect(addr).await.unwrap();
+    subscriber.hello(Some(3)).await.unwrap();
+    let mut subscriber = subscriber.subscribe(channels(&["news"])).await.unwrap();
+    publisher
+        .publish("news", Bytes::from_static(b"again"))
+        .await
+        .unwrap();
+    let message = subscriber.next_message().await.unwrap().unwrap();
+    assert_eq!(message.content, "again");
+}
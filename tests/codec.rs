@@ -0,0 +1,101 @@
+use bytes::{Bytes, BytesMut};
+use mini_redis::{
+    codec::RespCodec,
+    frame::{Frame, Limits},
+};
+use tokio_util::codec::Decoder;
+
+fn decode(limits: Limits, data: &[u8]) -> mini_redis::Result<Option<Frame>> {
+    let mut codec = RespCodec::with_limits(limits);
+    codec.decode(&mut BytesMut::from(data))
+}
+
+fn args(frame: Frame) -> Vec<Bytes> {
+    match frame {
+        Frame::Array(frames) => frames
+            .into_iter()
+            .map(|frame| match frame {
+                Frame::Bulk(data) => data,
+                frame => panic!("unexpected frame {:?}", frame),
+            })
+            .collect(),
+        frame => panic!("unexpected frame {:?}", frame),
+    }
+}
+
+// 没有收到换行符时缓冲区超出限制也要返回错误，不能一直等待
+#[test]
+fn inline_line_over_limit_is_rejected() {
+    let limits = Limits {
+        max_inline_len: 16,
+        ..Default::default()
+    };
+
+    assert!(decode(limits, b"get 0123456789a\r\n").unwrap().is_some());
+
+    let err = decode(limits, b"get 0123456789abcdef\r\n").unwrap_err();
+    assert!(err.to_string().contains("too big inline request"));
+
+    assert!(decode(limits, b"get 0123456789ab").unwrap().is_none());
+    let err = decode(limits, b"get 0123456789abcdef").unwrap_err();
+    assert!(err.to_string().contains("too big inline request"));
+}
+
+#[test]
+fn inline_arguments_support_quotes_and_escapes() {
+    let frame = decode(
+        Limits::default(),
+        b"set \"hello world\" \"a\\x41\\n\\\"b\" 'it\\'s' plain\r\n",
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+        args(frame),
+        vec![
+            Bytes::from_static(b"set"),
+            Bytes::from_static(b"hello world"),
+            Bytes::from_static(b"aA\n\"b"),
+            Bytes::from_static(b"it's"),
+            Bytes::from_static(b"plain"),
+        ]
+    );
+
+    // 只有\n结尾的行也可以解析，引号不匹配或者引号后面紧跟其他字符时返回错误
+    let frame = decode(Limits::default(), b"  ping   \n").unwrap().unwrap();
+    assert_eq!(args(frame), vec![Bytes::from_static(b"ping")]);
+
+    for line in [
+        &b"set \"foo\r\n"[..],
+        b"set 'foo\r\n",
+        b"set \"foo\"bar\r\n",
+    ] {
+        let err = decode(Limits::default(), line).unwrap_err();
+        assert!(err.to_string().contains("unbalanced quotes"));
+    }
+}
+
+// 服务端只把*开头的当作RESP数组，其他类型前缀开头的行也是内联命令；客户端解码响应时不受影响
+#[test]
+fn server_decodes_non_array_prefixes_as_inline() {
+    let mut server = RespCodec::for_server();
+
+    for line in [&b"+ping\r\n"[..], b":1\r\n", b"$3\r\n", b"#t\r\n"] {
+        let frame = server.decode(&mut BytesMut::from(line)).unwrap().unwrap();
+        let arg = Bytes::copy_from_slice(&line[..line.len() - 2]);
+        assert_eq!(args(frame), vec![arg]);
+    }
+
+    let frame = server
+        .decode(&mut BytesMut::from(&b"*1\r\n$4\r\nping\r\n"[..]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(args(frame), vec![Bytes::from_static(b"ping")]);
+
+    let mut client = RespCodec::new();
+    let frame = client
+        .decode(&mut BytesMut::from(&b"+PONG\r\n"[..]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(frame, "PONG");
+}
@@ -0,0 +1,298 @@
+use std::{
+    future,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use mini_redis::{
+    client::Client,
+    cmd::Command,
+    db::{Db, DbDropGuard, ExpireCondition, ExpireStrategy, Expiry, SetCondition, SetOptions},
+    frame::Frame,
+    server::{self, Acceptor, Config},
+};
+use tokio::{net::TcpListener, time};
+
+fn set_expires(db: &Db, key: &str, value: &'static str, expire: Duration) {
+    let options = SetOptions {
+        expire: Expiry::After(expire),
+        ..Default::default()
+    };
+    db.set(
+        key.to_string(),
+        Bytes::from_static(value.as_bytes()),
+        options,
+    )
+    .unwrap();
+}
+
+// 过期时间不是整毫秒时，后台任务的定时器会被向上取整到下一毫秒才触发
+// 推进到过期时间的那一刻，只有读取时的检查能够发现key已经过期
+#[tokio::test(start_paused = true)]
+async fn reads_ignore_expired_keys_before_purge() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    set_expires(&db, "foo", "bar", Duration::from_micros(1500));
+    set_expires(&db, "baz", "qux", Duration::from_secs(10));
+
+    time::advance(Duration::from_micros(1499)).await;
+    assert_eq!(db.get("foo").unwrap(), Some(Bytes::from_static(b"bar")));
+
+    time::advance(Duration::from_micros(1)).await;
+    assert_eq!(db.get("foo").unwrap(), None);
+    assert_eq!(db.exists(&["foo".to_string(), "baz".to_string()]), 1);
+    assert_eq!(
+        db.mget(&["foo".to_string(), "baz".to_string()]),
+        vec![None, Some(Bytes::from_static(b"qux"))]
+    );
+    assert_eq!(db.strlen("foo").unwrap(), 0);
+    assert_eq!(db.ttl("foo"), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn writes_treat_expired_keys_as_absent() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    set_expires(&db, "counter", "41", Duration::from_secs(1));
+    set_expires(&db, "lock", "a", Duration::from_secs(1));
+    set_expires(&db, "gone", "x", Duration::from_secs(1));
+
+    time::advance(Duration::from_secs(1)).await;
+
+    // INCR从0开始计算，而不是在过期的值上累加
+    assert_eq!(db.incr_by("counter", 1).unwrap(), 1);
+    assert_eq!(db.ttl("counter"), Some(None));
+
+    // NX认为过期的key不存在，GET返回的旧值也是Null
+    let options = SetOptions {
+        condition: SetCondition::NotExists,
+        get: true,
+        ..Default::default()
+    };
+    let (written, previous) = db
+        .set("lock".to_string(), Bytes::from_static(b"b"), options)
+        .unwrap();
+    assert!(written);
+    assert_eq!(previous, None);
+
+    assert_eq!(db.del(&["gone".to_string()]), 0);
+    assert!(!db.expire(
+        "gone",
+        Expiry::After(Duration::from_secs(1)),
+        ExpireCondition::default()
+    ));
+}
+
+#[tokio::test(start_paused = true)]
+async fn ttl_counts_down_with_the_clock() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    set_expires(&db, "foo", "bar", Duration::from_secs(10));
+
+    time::advance(Duration::from_secs(4)).await;
+    assert_eq!(db.ttl("foo"), Some(Some(Duration::from_secs(6))));
+
+    time::advance(Duration::from_secs(6)).await;
+    assert_eq!(db.ttl("foo"), None);
+}
+
+// 两种策略的后台任务都会在没有任何读取的情况下清除过期的key
+#[tokio::test(start_paused = true)]
+async fn background_task_purges_expired_keys() {
+    for strategy in [ExpireStrategy::Ordered, ExpireStrategy::Sampled] {
+        let guard = DbDropGuard::with_strategy(strategy);
+        let db = guard.db();
+
+        for i in 0..1000 {
+            set_expires(&db, &format!("key:{}", i), "x", Duration::from_secs(1));
+        }
+        set_expires(&db, "later", "x", Duration::from_secs(3600));
+        db.set(
+            "forever".to_string(),
+            Bytes::from_static(b"x"),
+            SetOptions::default(),
+        )
+        .unwrap();
+
+        // 部分key在过期之前被删除或者去掉了过期时间，抽样策略需要正确维护volatile数组
+        assert!(db.persist("key:0"));
+        assert_eq!(db.del(&["key:1".to_string(), "key:999".to_string()]), 2);
+        assert_eq!(db.dbsize(), 1000);
+
+        time::sleep(Duration::from_secs(2)).await;
+
+        assert_eq!(db.dbsize(), 3, "{} strategy", strategy);
+        assert!(db.get("key:0").unwrap().is_some());
+        assert!(db.get("later").unwrap().is_some());
+    }
+}
+
+fn set_persistent(db: &Db, key: &str) {
+    db.set(
+        key.to_string(),
+        Bytes::from_static(b"v"),
+        SetOptions::default(),
+    )
+    .unwrap();
+}
+
+fn condition(options: &str) -> ExpireCondition {
+    let mut condition = ExpireCondition::default();
+    for option in options.split_whitespace() {
+        match option {
+            "NX" => condition.nx = true,
+            "XX" => condition.xx = true,
+            "GT" => condition.gt = true,
+            "LT" => condition.lt = true,
+            _ => panic!("unknown option {}", option),
+        }
+    }
+    condition
+}
+
+// 没有过期时间的key视为永不过期：GT永远不会成功，LT总是成功
+#[tokio::test(start_paused = true)]
+async fn expire_conditions() {
+    let secs = Duration::from_secs;
+
+    // (选项, key原来是否有10秒的过期时间, 新的过期时间, 是否设置成功)
+    let cases = [
+        ("", false, 5, true),
+        ("NX", false, 5, true),
+        ("NX", true, 5, false),
+        ("XX", false, 5, false),
+        ("XX", true, 5, true),
+        ("GT", false, 5, false),
+        ("GT", true, 20, true),
+        ("GT", true, 10, false),
+        ("GT", true, 5, false),
+        ("LT", false, 5, true),
+        ("LT", true, 5, true),
+        ("LT", true, 10, false),
+        ("LT", true, 20, false),
+        ("XX GT", false, 20, false),
+        ("XX GT", true, 20, true),
+        ("XX LT", false, 5, false),
+        ("XX LT", true, 5, true),
+        ("XX LT", true, 20, false),
+    ];
+
+    for (options, volatile, new_ttl, expected) in cases {
+        let guard = DbDropGuard::new();
+        let db = guard.db();
+        if volatile {
+            set_expires(&db, "k", "v", secs(10));
+        } else {
+            set_persistent(&db, "k");
+        }
+        let before = db.ttl("k").unwrap();
+
+        let updated = db.expire("k", Expiry::After(secs(new_ttl)), condition(options));
+        assert_eq!(updated, expected, "{:?} {} {}", options, volatile, new_ttl);
+
+        let after = db.ttl("k").unwrap();
+        if expected {
+            assert_eq!(after, Some(secs(new_ttl)));
+        } else {
+            assert_eq!(after, before);
+        }
+    }
+}
+
+fn command(args: &[&str]) -> mini_redis::Result<Command> {
+    let args = args
+        .iter()
+        .map(|arg| Frame::Bulk(Bytes::copy_from_slice(arg.as_bytes())))
+        .collect();
+    Command::from_frame(Frame::Array(args))
+}
+
+#[test]
+fn conflicting_expire_options_are_rejected() {
+    for options in [&["NX", "XX"][..], &["NX", "GT"], &["lt", "nx"]] {
+        let args = [&["expire", "k", "10"][..], options].concat();
+        let err = command(&args).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "NX and XX, GT or LT options at the same time are not compatible"
+        );
+    }
+
+    let err = command(&["pexpire", "k", "10", "GT", "LT"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "GT and LT options at the same time are not compatible"
+    );
+
+    let err = command(&["expireat", "k", "10", "YY"]).unwrap_err();
+    assert_eq!(err.to_string(), "Unsupported option YY");
+
+    assert!(command(&["expire", "k", "10", "XX", "GT"]).is_ok());
+}
+
+async fn start_server() -> Client {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(server::run(
+        vec![Acceptor::Tcp(listener)],
+        Config::default(),
+        future::pending::<()>(),
+    ));
+
+    Client::connect(addr).await.unwrap()
+}
+
+async fn execute(client: &mut Client, args: &[&str]) -> Frame {
+    let args = args
+        .iter()
+        .map(|arg| Bytes::copy_from_slice(arg.as_bytes()))
+        .collect();
+    client.execute(args).await.unwrap()
+}
+
+async fn integer(client: &mut Client, args: &[&str]) -> i64 {
+    match execute(client, args).await {
+        Frame::Integer(value) => value,
+        frame => panic!("unexpected frame {:?}", frame),
+    }
+}
+
+// EXPIRETIME返回EXPIREAT设置的unix时间，PERSIST去掉过期时间之后返回-1
+#[tokio::test]
+async fn expiretime_and_persist() {
+    let mut client = start_server().await;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let at = now.as_secs() + 100;
+    let at_ms = now.as_millis() as u64 + 100_000;
+
+    execute(&mut client, &["set", "k", "v"]).await;
+    assert_eq!(integer(&mut client, &["expiretime", "k"]).await, -1);
+    assert_eq!(integer(&mut client, &["pexpiretime", "k"]).await, -1);
+    assert_eq!(integer(&mut client, &["expiretime", "missing"]).await, -2);
+    assert_eq!(integer(&mut client, &["persist", "k"]).await, 0);
+
+    let at_arg = at.to_string();
+    assert_eq!(integer(&mut client, &["expireat", "k", &at_arg]).await, 1);
+    assert_eq!(integer(&mut client, &["expiretime", "k"]).await, at as i64);
+    assert_eq!(
+        integer(&mut client, &["pexpiretime", "k"]).await,
+        at as i64 * 1000
+    );
+
+    let at_ms_arg = at_ms.to_string();
+    execute(&mut client, &["pexpireat", "k", &at_ms_arg]).await;
+    assert_eq!(
+        integer(&mut client, &["pexpiretime", "k"]).await,
+        at_ms as i64
+    );
+
+    assert_eq!(integer(&mut client, &["persist", "k"]).await, 1);
+    assert_eq!(integer(&mut client, &["expiretime", "k"]).await, -1);
+    assert_eq!(integer(&mut client, &["ttl", "k"]).await, -1);
+    assert_eq!(integer(&mut client, &["persist", "k"]).await, 0);
+}
@@ -0,0 +1,100 @@
+use std::io::Cursor;
+
+use mini_redis::frame::{Error, Frame, Limits};
+
+fn check(limits: &Limits, data: &[u8]) -> Result<(), Error> {
+    Frame::check(&mut Cursor::new(data), limits)
+}
+
+fn error(result: Result<(), Error>) -> String {
+    match result {
+        Err(Error::Other(err)) => err.to_string(),
+        Err(Error::Incomplete) => panic!("unexpected incomplete frame"),
+        Ok(()) => panic!("unexpected complete frame"),
+    }
+}
+
+// 声明的长度超过限制时不需要等待数据全部到达，读到长度就返回错误
+#[test]
+fn bulk_length_over_limit_is_rejected() {
+    let limits = Limits {
+        max_bulk_len: 10,
+        ..Default::default()
+    };
+
+    assert!(check(&limits, b"$10\r\n0123456789\r\n").is_ok());
+    assert!(matches!(
+        check(&limits, b"$10\r\n0123"),
+        Err(Error::Incomplete)
+    ));
+
+    let err = error(check(&limits, b"$11\r\n"));
+    assert!(err.contains("bulk length 11 exceeds limit 10"), "{}", err);
+
+    let err = error(check(&limits, b"*1\r\n$1000000\r\n"));
+    assert!(err.contains("exceeds limit"), "{}", err);
+}
+
+#[test]
+fn nesting_deeper_than_limit_is_rejected() {
+    let limits = Limits {
+        max_depth: 2,
+        ..Default::default()
+    };
+
+    assert!(check(&limits, b"*1\r\n*1\r\n:1\r\n").is_ok());
+
+    let err = error(check(&limits, b"*1\r\n*1\r\n*1\r\n:1\r\n"));
+    assert!(err.contains("nesting depth exceeds limit 2"), "{}", err);
+
+    // Map中的值同样计算嵌套层数
+    let err = error(check(&limits, b"%1\r\n+k\r\n*1\r\n*1\r\n"));
+    assert!(err.contains("nesting depth"), "{}", err);
+}
+
+// 聚合类型声明的元素数量超过限制时同样不需要等待元素到达
+#[test]
+fn aggregate_length_over_limit_is_rejected() {
+    let limits = Limits {
+        max_elements: 2,
+        ..Default::default()
+    };
+
+    assert!(check(&limits, b"*2\r\n:1\r\n:2\r\n").is_ok());
+
+    let err = error(check(&limits, b"*3\r\n"));
+    assert!(
+        err.contains("aggregate length 3 exceeds limit 2"),
+        "{}",
+        err
+    );
+
+    let err = error(check(&limits, b"%3\r\n"));
+    assert!(err.contains("aggregate length"), "{}", err);
+}
+
+// 简单字符串、错误、数值等一行的类型超过长度限制时返回错误，不能一直等待\r\n
+#[test]
+fn line_over_limit_is_rejected() {
+    let limits = Limits {
+        max_inline_len: 8,
+        ..Default::default()
+    };
+
+    assert!(check(&limits, b"+01234567\r\n").is_ok());
+    assert!(matches!(
+        check(&limits, b"+01234567\r"),
+        Err(Error::Incomplete)
+    ));
+
+    for data in [
+        &b"+012345678\r\n"[..],
+        b"+012345678",
+        b"-ERR 01234567",
+        b":1234567890",
+        b"*1\r\n(12345678901234567890\r\n",
+    ] {
+        let err = error(check(&limits, data));
+        assert!(err.contains("line length exceeds limit 8"), "{}", err);
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+
+use bytes::Bytes;
+use mini_redis::db::{Db, DbDropGuard, EncodingConfig, ExpireStrategy, SetOptions};
+
+fn pair(field: &str, value: &str) -> (Bytes, Bytes) {
+    (
+        Bytes::copy_from_slice(field.as_bytes()),
+        Bytes::copy_from_slice(value.as_bytes()),
+    )
+}
+
+fn small_listpack() -> DbDropGuard {
+    let encoding = EncodingConfig {
+        hash_max_listpack_entries: 4,
+        hash_max_listpack_value: 8,
+        ..Default::default()
+    };
+    DbDropGuard::with_config(ExpireStrategy::default(), encoding)
+}
+
+// 用HSCAN遍历整个hash，返回每一轮的游标和遍历到的所有field
+fn scan_all(db: &Db, key: &str, pattern: Option<&[u8]>) -> (Vec<u64>, HashSet<Bytes>) {
+    let mut cursors = vec![];
+    let mut fields = HashSet::new();
+    let mut cursor = 0;
+    loop {
+        let (next, pairs) = db.hscan(key, cursor, pattern, 5).unwrap();
+        fields.extend(pairs.into_iter().map(|(field, _)| field));
+        cursors.push(next);
+        cursor = next;
+        if cursor == 0 {
+            return (cursors, fields);
+        }
+    }
+}
+
+// field数量超过hash_max_listpack_entries，或者field、value的长度超过hash_max_listpack_value时
+// 从listpack转换成哈希表，之后即使删除了field也不会再转换回去
+#[tokio::test]
+async fn listpack_converts_to_hashtable() {
+    let guard = small_listpack();
+    let db = guard.db();
+
+    let pairs = (0..4).map(|i| pair(&format!("f{}", i), "v")).collect();
+    assert_eq!(db.hset("entries", pairs).unwrap(), 4);
+    assert_eq!(db.object_encoding("entries"), Some("listpack"));
+
+    db.hset("entries", vec![pair("f4", "v")]).unwrap();
+    assert_eq!(db.object_encoding("entries"), Some("hashtable"));
+    assert_eq!(db.hdel("entries", &[Bytes::from_static(b"f4")]).unwrap(), 1);
+    assert_eq!(db.object_encoding("entries"), Some("hashtable"));
+    assert_eq!(db.hlen("entries").unwrap(), 4);
+
+    db.hset("value", vec![pair("f", "12345678")]).unwrap();
+    assert_eq!(db.object_encoding("value"), Some("listpack"));
+    db.hset("value", vec![pair("f", "123456789")]).unwrap();
+    assert_eq!(db.object_encoding("value"), Some("hashtable"));
+    assert_eq!(db.hget("value", b"f").unwrap().unwrap(), "123456789");
+
+    db.hset("field", vec![pair("a-long-field", "v")]).unwrap();
+    assert_eq!(db.object_encoding("field"), Some("hashtable"));
+
+    // HINCRBY写入的值同样会检查长度
+    db.hset("incr", vec![pair("n", "1")]).unwrap();
+    db.hincr_by("incr", Bytes::from_static(b"n"), 1_000_000_000)
+        .unwrap();
+    assert_eq!(db.object_encoding("incr"), Some("hashtable"));
+}
+
+#[tokio::test]
+async fn hincrby_rejects_overflow_and_non_integers() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    let n = || Bytes::from_static(b"n");
+
+    assert_eq!(db.hincr_by("h", n(), i64::MAX).unwrap(), i64::MAX);
+    let err = db.hincr_by("h", n(), 1).unwrap_err();
+    assert_eq!(err.to_string(), "ERR increment or decrement would overflow");
+    assert_eq!(db.hget("h", b"n").unwrap().unwrap(), i64::MAX.to_string());
+
+    assert_eq!(db.hincr_by("h", n(), i64::MIN).unwrap(), -1);
+    assert_eq!(db.hincr_by("h", n(), i64::MIN + 1).unwrap(), i64::MIN);
+    let err = db.hincr_by("h", n(), -1).unwrap_err();
+    assert_eq!(err.to_string(), "ERR increment or decrement would overflow");
+
+    db.hset("h", vec![pair("s", "abc"), pair("padded", " 1")])
+        .unwrap();
+    for field in ["s", "padded"] {
+        let err = db
+            .hincr_by("h", Bytes::copy_from_slice(field.as_bytes()), 1)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "ERR hash value is not an integer");
+    }
+}
+
+#[tokio::test]
+async fn hash_commands_on_wrong_type() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    db.set(
+        "s".to_string(),
+        Bytes::from_static(b"v"),
+        SetOptions::default(),
+    )
+    .unwrap();
+
+    let wrongtype = "WRONGTYPE Operation against a key holding the wrong kind of value";
+    assert_eq!(
+        db.hset("s", vec![pair("f", "v")]).unwrap_err().to_string(),
+        wrongtype
+    );
+    assert_eq!(db.hget("s", b"f").unwrap_err().to_string(), wrongtype);
+    assert_eq!(db.hgetall("s").unwrap_err().to_string(), wrongtype);
+    assert_eq!(
+        db.hincr_by("s", Bytes::from_static(b"f"), 1)
+            .unwrap_err()
+            .to_string(),
+        wrongtype
+    );
+    assert_eq!(
+        db.hscan("s", 0, None, 10).unwrap_err().to_string(),
+        wrongtype
+    );
+
+    // 原来的值不受影响
+    assert_eq!(db.get("s").unwrap().unwrap(), "v");
+}
+
+// listpack编码时一次返回所有的field，游标直接为0；哈希表编码时分多次返回，不会遗漏
+#[tokio::test]
+async fn hscan_returns_every_field() {
+    let guard = small_listpack();
+    let db = guard.db();
+
+    db.hset("small", vec![pair("a", "1"), pair("b", "2")])
+        .unwrap();
+    let (cursors, fields) = scan_all(&db, "small", None);
+    assert_eq!(cursors, vec![0]);
+    assert_eq!(fields.len(), 2);
+
+    let pairs = (0..100).map(|i| pair(&format!("f{}", i), "v")).collect();
+    db.hset("big", pairs).unwrap();
+    let (cursors, fields) = scan_all(&db, "big", None);
+    assert!(cursors.len() > 1);
+    let expected: HashSet<Bytes> = (0..100).map(|i| Bytes::from(format!("f{}", i))).collect();
+    assert_eq!(fields, expected);
+
+    // pattern只过滤返回的元素，不影响遍历
+    let (_, fields) = scan_all(&db, "big", Some(b"f1?"));
+    let expected: HashSet<Bytes> = (10..20).map(|i| Bytes::from(format!("f{}", i))).collect();
+    assert_eq!(fields, expected);
+
+    assert_eq!(db.hscan("missing", 0, None, 10).unwrap(), (0, vec![]));
+}
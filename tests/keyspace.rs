@@ -0,0 +1,419 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use mini_redis::{
+    cmd::Command,
+    db::{
+        Db, DbDropGuard, EncodingConfig, ExpireStrategy, Expiry, ListEnd, RestoreOptions,
+        SetOptions, StreamId, XAddId, ZAddOptions, ZRange,
+    },
+    frame::Frame,
+};
+
+fn bytes(items: &[&'static str]) -> Vec<Bytes> {
+    items
+        .iter()
+        .map(|item| Bytes::from_static(item.as_bytes()))
+        .collect()
+}
+
+fn pairs(items: &[(&'static str, &'static str)]) -> Vec<(Bytes, Bytes)> {
+    items
+        .iter()
+        .map(|(field, value)| {
+            (
+                Bytes::from_static(field.as_bytes()),
+                Bytes::from_static(value.as_bytes()),
+            )
+        })
+        .collect()
+}
+
+fn keys(items: &[&str]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+fn set(db: &Db, key: &str, value: &'static str, expire: Expiry) {
+    let options = SetOptions {
+        expire,
+        ..Default::default()
+    };
+    db.set(
+        key.to_string(),
+        Bytes::from_static(value.as_bytes()),
+        options,
+    )
+    .unwrap();
+}
+
+// 哈希表编码的集合类型遍历顺序不固定，排序之后再比较
+fn sorted<T: Ord>(mut items: Vec<T>) -> Vec<T> {
+    items.sort();
+    items
+}
+
+// 用一个key的全部内容和编码来比较DUMP之前和RESTORE之后的值
+fn contents(db: &Db, key: &str) -> (Option<&'static str>, String) {
+    let value = match db.key_type(key).unwrap() {
+        "string" => format!("{:?}", db.get(key).unwrap()),
+        "list" => format!("{:?}", db.lrange(key, 0, -1).unwrap()),
+        "hash" => format!("{:?}", sorted(db.hgetall(key).unwrap())),
+        "set" => format!("{:?}", sorted(db.smembers(key).unwrap())),
+        "zset" => format!(
+            "{:?}",
+            db.zrange(key, ZRange::Rank(0, -1), false, None).unwrap()
+        ),
+        "stream" => format!(
+            "{:?}",
+            db.xrange(key, StreamId::MIN, StreamId::MAX, None, false)
+                .unwrap()
+        ),
+        ty => panic!("unexpected type {}", ty),
+    };
+
+    (db.object_encoding(key), value)
+}
+
+// 和redis相同的CRC-64/Jones，用于构造版本号不同或者带有多余数据、但是校验和正确的payload
+fn crc64(data: &[u8]) -> u64 {
+    let mut crc = 0u64;
+    for &b in data {
+        crc ^= b as u64;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x95ac_9329_ac4b_c9b5
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+fn with_checksum(mut body: Vec<u8>) -> Vec<u8> {
+    let crc = crc64(&body);
+    body.extend_from_slice(&crc.to_le_bytes());
+    body
+}
+
+// 每种类型以及每种编码的值DUMP之后都可以原样RESTORE
+#[tokio::test]
+async fn dump_restore_round_trips_every_type() {
+    let encoding = EncodingConfig {
+        hash_max_listpack_entries: 2,
+        set_max_intset_entries: 2,
+        ..Default::default()
+    };
+    let guard = DbDropGuard::with_config(ExpireStrategy::default(), encoding);
+    let db = guard.db();
+
+    set(&db, "int", "12345", Expiry::Persist);
+    set(&db, "embstr", "hello", Expiry::Persist);
+    db.set(
+        "raw".to_string(),
+        Bytes::from(vec![b'\0'; 100]),
+        SetOptions::default(),
+    )
+    .unwrap();
+    db.push("list", bytes(&["a", "", "c"]), ListEnd::Right)
+        .unwrap();
+    db.push(
+        "quicklist",
+        vec![Bytes::from(vec![b'x'; 9000])],
+        ListEnd::Right,
+    )
+    .unwrap();
+    db.hset("hash", pairs(&[("f1", "v1")])).unwrap();
+    db.hset(
+        "hashtable",
+        pairs(&[("f1", "v1"), ("f2", ""), ("f3", "v3")]),
+    )
+    .unwrap();
+    db.sadd("intset", bytes(&["1", "-2"])).unwrap();
+    db.sadd("set", bytes(&["a", "b", "3"])).unwrap();
+    db.zadd(
+        "zset",
+        vec![
+            (1.5, Bytes::from_static(b"a")),
+            (f64::NEG_INFINITY, Bytes::from_static(b"b")),
+        ],
+        ZAddOptions::default(),
+    )
+    .unwrap();
+    for _ in 0..2 {
+        db.xadd("stream", XAddId::Auto, pairs(&[("f", "v")]), false, None)
+            .unwrap();
+    }
+
+    for key in [
+        "int",
+        "embstr",
+        "raw",
+        "list",
+        "quicklist",
+        "hash",
+        "hashtable",
+        "intset",
+        "set",
+        "zset",
+        "stream",
+    ] {
+        let payload = db.dump(key).unwrap();
+        let restored = format!("{}-restored", key);
+        db.restore(&restored, &payload, RestoreOptions::default())
+            .unwrap();
+
+        assert_eq!(contents(&db, &restored), contents(&db, key), "{}", key);
+    }
+
+    assert_eq!(db.object_encoding("int"), Some("int"));
+    assert_eq!(db.object_encoding("embstr"), Some("embstr"));
+    assert_eq!(db.object_encoding("raw"), Some("raw"));
+    assert_eq!(db.object_encoding("list"), Some("listpack"));
+    assert_eq!(db.object_encoding("quicklist"), Some("quicklist"));
+    assert_eq!(db.object_encoding("hash"), Some("listpack"));
+    assert_eq!(db.object_encoding("hashtable"), Some("hashtable"));
+    assert_eq!(db.object_encoding("intset"), Some("intset"));
+    assert_eq!(db.object_encoding("set"), Some("hashtable"));
+    assert_eq!(db.object_encoding("zset"), Some("skiplist"));
+    assert_eq!(db.object_encoding("stream"), Some("stream"));
+    assert_eq!(db.object_encoding("missing"), None);
+}
+
+#[tokio::test]
+async fn restore_rejects_corrupted_payload() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    set(&db, "k", "value", Expiry::Persist);
+
+    let payload = db.dump("k").unwrap().to_vec();
+    let (body, checksum) = payload.split_at(payload.len() - 8);
+    assert_eq!(checksum, crc64(body).to_le_bytes());
+    let (content, _version) = body.split_at(body.len() - 2);
+
+    let restore = |payload: &[u8]| {
+        db.restore("new", payload, RestoreOptions::default())
+            .unwrap_err()
+            .to_string()
+    };
+
+    // 校验和不匹配
+    let mut corrupted = payload.clone();
+    *corrupted.last_mut().unwrap() ^= 1;
+    assert_eq!(
+        restore(&corrupted),
+        "ERR DUMP payload version or checksum are wrong"
+    );
+    assert_eq!(
+        restore(&payload[..5]),
+        "ERR DUMP payload version or checksum are wrong"
+    );
+
+    // 校验和正确，但是版本号不认识
+    let mut other_version = content.to_vec();
+    other_version.extend_from_slice(&2u16.to_le_bytes());
+    assert_eq!(
+        restore(&with_checksum(other_version)),
+        "ERR DUMP payload version or checksum are wrong"
+    );
+
+    // 内容之后有多余的数据
+    let mut trailing = content.to_vec();
+    trailing.push(0);
+    trailing.extend_from_slice(&1u16.to_le_bytes());
+    assert_eq!(restore(&with_checksum(trailing)), "ERR Bad data format");
+
+    assert_eq!(db.exists(&["new".to_string()]), 0);
+}
+
+#[tokio::test]
+async fn restore_existing_key_requires_replace() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    set(&db, "src", "new", Expiry::Persist);
+    set(&db, "dst", "old", Expiry::Persist);
+    let payload = db.dump("src").unwrap();
+
+    let err = db
+        .restore("dst", &payload, RestoreOptions::default())
+        .unwrap_err();
+    assert_eq!(err.to_string(), "BUSYKEY Target key name already exists.");
+    assert_eq!(db.get("dst").unwrap().unwrap(), "old");
+
+    let replace = RestoreOptions {
+        replace: true,
+        ..Default::default()
+    };
+    db.restore("dst", &payload, replace).unwrap();
+    assert_eq!(db.get("dst").unwrap().unwrap(), "new");
+}
+
+fn restore_command(key: &str, ttl: &str, payload: Bytes, options: &[&str]) -> RestoreOptions {
+    let mut args = vec![Frame::Bulk(Bytes::from_static(b"restore"))];
+    for arg in [key, ttl] {
+        args.push(Frame::Bulk(Bytes::copy_from_slice(arg.as_bytes())));
+    }
+    args.push(Frame::Bulk(payload));
+    for arg in options {
+        args.push(Frame::Bulk(Bytes::copy_from_slice(arg.as_bytes())));
+    }
+
+    match Command::from_frame(Frame::Array(args)).unwrap() {
+        Command::Restore(restore) => *restore.options(),
+        _ => panic!("unexpected command"),
+    }
+}
+
+// 带ABSTTL时ttl是unix时间(毫秒)，已经过去的时间不会创建key
+#[tokio::test]
+async fn restore_absttl_sets_unix_time_expiry() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    set(&db, "src", "v", Expiry::Persist);
+    let payload = db.dump("src").unwrap();
+
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let at = (now_ms + 100_000).to_string();
+
+    let options = restore_command("k", &at, payload.clone(), &["ABSTTL"]);
+    assert_eq!(
+        options.expire,
+        Expiry::At(UNIX_EPOCH + Duration::from_millis(at.parse().unwrap()))
+    );
+    db.restore("k", &payload, options).unwrap();
+    let ttl = db.ttl("k").unwrap().unwrap();
+    assert!(ttl > Duration::from_secs(90) && ttl <= Duration::from_secs(100));
+
+    // 不带ABSTTL时同样的数值是相对时间
+    let options = restore_command("rel", "100000", payload.clone(), &[]);
+    assert_eq!(options.expire, Expiry::After(Duration::from_secs(100)));
+
+    let past = (now_ms - 1000).to_string();
+    let options = restore_command("past", &past, payload.clone(), &["ABSTTL"]);
+    db.restore("past", &payload, options).unwrap();
+    assert_eq!(db.exists(&["past".to_string()]), 0);
+}
+
+// RENAME、COPY之后新的key保留原来的过期时间
+#[tokio::test]
+async fn rename_and_copy_keep_ttl() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    let ttl = Duration::from_secs(100);
+    set(&db, "a", "v", Expiry::After(ttl));
+    set(&db, "persistent", "v", Expiry::Persist);
+
+    assert!(db.rename("a", "b", false).unwrap());
+    assert_eq!(db.ttl("a"), None);
+    let left = db.ttl("b").unwrap().unwrap();
+    assert!(left > Duration::from_secs(90) && left <= ttl);
+
+    assert!(db.copy("b", "c", false).unwrap());
+    let left = db.ttl("c").unwrap().unwrap();
+    assert!(left > Duration::from_secs(90) && left <= ttl);
+    assert!(db.ttl("b").unwrap().is_some());
+
+    // 被覆盖的key原来的过期时间也会被覆盖
+    assert!(db.copy("persistent", "c", true).unwrap());
+    assert_eq!(db.ttl("c"), Some(None));
+    assert!(db.rename("b", "persistent", false).unwrap());
+    assert!(db.ttl("persistent").unwrap().is_some());
+}
+
+// OBJECT FREQ和IDLETIME不会更新访问信息，RESTORE可以指定它们的值
+#[tokio::test]
+async fn object_freq_and_idle_time() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    set(&db, "k", "v", Expiry::Persist);
+
+    // 新建的key的计数器从5开始，和redis的LFU_INIT_VAL一致
+    assert_eq!(db.object_freq("k"), Some(5));
+    assert!(db.object_idle_time("k").unwrap() < Duration::from_secs(1));
+    assert_eq!(db.object_freq("missing"), None);
+    assert_eq!(db.object_idle_time("missing"), None);
+
+    let payload = db.dump("k").unwrap();
+    let freq = RestoreOptions {
+        freq: Some(100),
+        ..Default::default()
+    };
+    db.restore("hot", &payload, freq).unwrap();
+    assert_eq!(db.object_freq("hot"), Some(100));
+    assert_eq!(db.object_freq("hot"), Some(100));
+
+    let idle = RestoreOptions {
+        idle_time: Some(Duration::from_secs(30)),
+        ..Default::default()
+    };
+    db.restore("idle", &payload, idle).unwrap();
+    let idle_time = db.object_idle_time("idle").unwrap();
+    assert!(idle_time >= Duration::from_secs(30) && idle_time < Duration::from_secs(31));
+
+    // 读取之后访问时间更新
+    db.get("idle").unwrap();
+    assert!(db.object_idle_time("idle").unwrap() < Duration::from_secs(1));
+}
+
+// 只要有一个key已经存在，MSETNX就一个key都不写入
+#[tokio::test]
+async fn msetnx_is_all_or_nothing() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    set(&db, "b", "old", Expiry::Persist);
+    db.push("list", bytes(&["x"]), ListEnd::Right).unwrap();
+
+    let pairs = |items: &[(&str, &'static str)]| {
+        items
+            .iter()
+            .map(|(key, value)| (key.to_string(), Bytes::from_static(value.as_bytes())))
+            .collect::<Vec<_>>()
+    };
+
+    assert!(!db.msetnx(pairs(&[("a", "1"), ("b", "2"), ("c", "3")])));
+    assert_eq!(db.exists(&keys(&["a", "c"])), 0);
+    assert_eq!(db.get("b").unwrap().unwrap(), "old");
+
+    // 其他类型的key同样算作已经存在
+    assert!(!db.msetnx(pairs(&[("a", "1"), ("list", "2")])));
+    assert_eq!(db.exists(&keys(&["a"])), 0);
+    assert_eq!(db.key_type("list"), Some("list"));
+
+    // 已经过期的key视为不存在
+    set(
+        &db,
+        "expired",
+        "v",
+        Expiry::After(Duration::from_millis(10)),
+    );
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(db.msetnx(pairs(&[("a", "1"), ("expired", "2")])));
+    assert_eq!(
+        db.mget(&keys(&["a", "expired", "list", "missing"])),
+        vec![
+            Some(Bytes::from_static(b"1")),
+            Some(Bytes::from_static(b"2")),
+            None,
+            None
+        ]
+    );
+    assert_eq!(db.ttl("expired"), Some(None));
+}
+
+// EXISTS中重复的key会被重复计算，DEL、UNLINK中重复的key只删除一次
+#[tokio::test]
+async fn exists_counts_repeated_keys() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    set(&db, "a", "1", Expiry::Persist);
+    set(&db, "b", "2", Expiry::Persist);
+    set(&db, "c", "3", Expiry::Persist);
+
+    assert_eq!(db.exists(&keys(&["a", "a", "missing", "b"])), 3);
+    assert_eq!(db.del(&keys(&["a", "a", "missing"])), 1);
+    assert_eq!(db.unlink(&keys(&["b", "b", "c"])), 2);
+    assert_eq!(db.exists(&keys(&["a", "b", "c"])), 0);
+}
@@ -0,0 +1,382 @@
+use std::{
+    future,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use mini_redis::{
+    client::Client,
+    db::{BlockingPop, Db, DbDropGuard, ListEnd},
+    frame::Frame,
+    server::{self, Acceptor, Config},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+fn bytes(items: &[&'static str]) -> Vec<Bytes> {
+    items
+        .iter()
+        .map(|item| Bytes::from_static(item.as_bytes()))
+        .collect()
+}
+
+fn list(db: &Db, items: &[&'static str]) {
+    db.del(&["list".to_string()]);
+    db.push("list", bytes(items), ListEnd::Right).unwrap();
+}
+
+fn blocked(pop: BlockingPop) -> mini_redis::db::ListWaiter {
+    match pop {
+        BlockingPop::Blocked(waiter) => waiter,
+        BlockingPop::Popped(key, _) => panic!("unexpected pop from {}", key),
+    }
+}
+
+// 元素已经交给等待者，但是等待者在取走之前断开了，元素要放回原来弹出的那一端
+#[tokio::test]
+async fn dropped_waiter_returns_handed_over_element() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    let keys = ["list".to_string()];
+
+    let left = blocked(db.blocking_pop(&keys, ListEnd::Left, None).unwrap());
+    db.push("list", vec![Bytes::from_static(b"a")], ListEnd::Right)
+        .unwrap();
+    db.push("list", vec![Bytes::from_static(b"b")], ListEnd::Right)
+        .unwrap();
+    assert_eq!(
+        db.lrange("list", 0, -1).unwrap(),
+        vec![Bytes::from_static(b"b")]
+    );
+    drop(left);
+    assert_eq!(
+        db.lrange("list", 0, -1).unwrap(),
+        vec![Bytes::from_static(b"a"), Bytes::from_static(b"b")]
+    );
+
+    let right = blocked(
+        db.blocking_pop(&["other".to_string()], ListEnd::Right, None)
+            .unwrap(),
+    );
+    db.push("other", vec![Bytes::from_static(b"x")], ListEnd::Right)
+        .unwrap();
+    assert_eq!(db.llen("other").unwrap(), 0);
+    drop(right);
+    assert_eq!(
+        db.lrange("other", 0, -1).unwrap(),
+        vec![Bytes::from_static(b"x")]
+    );
+}
+
+// 放回的元素会继续交给下一个等待者
+#[tokio::test]
+async fn dropped_waiter_hands_element_to_next_waiter() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    let keys = ["list".to_string()];
+
+    let first = blocked(db.blocking_pop(&keys, ListEnd::Left, None).unwrap());
+    let mut second = blocked(db.blocking_pop(&keys, ListEnd::Left, None).unwrap());
+
+    db.push("list", vec![Bytes::from_static(b"a")], ListEnd::Right)
+        .unwrap();
+    drop(first);
+
+    assert_eq!(
+        second.recv().await,
+        Some(("list".to_string(), Bytes::from_static(b"a")))
+    );
+    assert_eq!(db.llen("list").unwrap(), 0);
+}
+
+// 多个客户端阻塞在同一个key上时，按照阻塞的先后顺序依次得到元素
+#[tokio::test]
+async fn waiters_are_served_in_blocking_order() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    let keys = ["list".to_string()];
+
+    let mut first = blocked(db.blocking_pop(&keys, ListEnd::Left, None).unwrap());
+    let mut second = blocked(db.blocking_pop(&keys, ListEnd::Left, None).unwrap());
+
+    // 一次写入两个元素，每个等待者各得到一个，按照列表的顺序分配
+    db.push(
+        "list",
+        vec![Bytes::from_static(b"a"), Bytes::from_static(b"b")],
+        ListEnd::Right,
+    )
+    .unwrap();
+
+    assert_eq!(
+        first.recv().await,
+        Some(("list".to_string(), Bytes::from_static(b"a")))
+    );
+    assert_eq!(
+        second.recv().await,
+        Some(("list".to_string(), Bytes::from_static(b"b")))
+    );
+    assert_eq!(db.llen("list").unwrap(), 0);
+}
+
+// 等待多个key时，先写入元素的key唤醒等待者，返回值中带有key
+#[tokio::test]
+async fn waiter_on_several_keys_is_woken_by_any_of_them() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    let keys = ["a".to_string(), "b".to_string()];
+
+    let mut waiter = blocked(db.blocking_pop(&keys, ListEnd::Left, None).unwrap());
+    db.push("b", vec![Bytes::from_static(b"x")], ListEnd::Right)
+        .unwrap();
+
+    assert_eq!(
+        waiter.recv().await,
+        Some(("b".to_string(), Bytes::from_static(b"x")))
+    );
+
+    // 已经有元素的时候不会阻塞，按照key的顺序检查
+    db.push("b", vec![Bytes::from_static(b"y")], ListEnd::Right)
+        .unwrap();
+    db.push("a", vec![Bytes::from_static(b"z")], ListEnd::Right)
+        .unwrap();
+    match db.blocking_pop(&keys, ListEnd::Left, None).unwrap() {
+        BlockingPop::Popped(key, value) => {
+            assert_eq!(key, "a");
+            assert_eq!(value, Bytes::from_static(b"z"));
+        }
+        BlockingPop::Blocked(_) => panic!("the list is not empty"),
+    }
+}
+
+async fn start_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(server::run(
+        vec![Acceptor::Tcp(listener)],
+        Config::default(),
+        future::pending::<()>(),
+    ));
+
+    addr
+}
+
+fn args(args: &[&'static str]) -> Vec<Bytes> {
+    args.iter()
+        .map(|arg| Bytes::from_static(arg.as_bytes()))
+        .collect()
+}
+
+// 超时之后返回Null，超时之前其他客户端写入元素时立即返回
+#[tokio::test]
+async fn blpop_times_out_or_wakes_on_push() {
+    let addr = start_server().await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let start = Instant::now();
+    let response = client
+        .execute(args(&["blpop", "list", "0.1"]))
+        .await
+        .unwrap();
+    assert!(matches!(response, Frame::Null));
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    let blpop = tokio::spawn(async move { client.execute(args(&["blpop", "list", "10"])).await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let mut pusher = Client::connect(addr).await.unwrap();
+    pusher
+        .execute(args(&["rpush", "list", "a", "b"]))
+        .await
+        .unwrap();
+
+    let response = blpop.await.unwrap().unwrap();
+    assert!(
+        matches!(&response, Frame::Array(frames) if frames.len() == 2 && frames[0] == "list" && frames[1] == "a"),
+        "{:?}",
+        response
+    );
+
+    let response = pusher
+        .execute(args(&["lrange", "list", "0", "-1"]))
+        .await
+        .unwrap();
+    assert!(matches!(&response, Frame::Array(frames) if frames.len() == 1 && frames[0] == "b"));
+}
+
+// 阻塞期间客户端继续发送的命令不会丢失，也不会全部读进内存，唤醒之后按顺序执行
+#[tokio::test]
+async fn commands_sent_while_blocked_run_after_wakeup() {
+    const INCRS: usize = 20_000;
+
+    let addr = start_server().await;
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let (mut reader, mut writer) = stream.into_split();
+
+    let mut request = b"*3\r\n$5\r\nBLPOP\r\n$4\r\nlist\r\n$2\r\n10\r\n".to_vec();
+    request.extend(b"*2\r\n$4\r\nINCR\r\n$1\r\nn\r\n".repeat(INCRS));
+    // 请求比服务器阻塞期间读取的上限大，写入可能要等到服务器被唤醒之后才能完成
+    let write = tokio::spawn(async move {
+        writer.write_all(&request).await.unwrap();
+        writer
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut pusher = Client::connect(addr).await.unwrap();
+    pusher.execute(args(&["rpush", "list", "a"])).await.unwrap();
+
+    let mut expected = b"*2\r\n$4\r\nlist\r\n$1\r\na\r\n".to_vec();
+    for n in 1..=INCRS {
+        expected.extend(format!(":{}\r\n", n).as_bytes());
+    }
+    let mut replies = vec![0; expected.len()];
+    reader.read_exact(&mut replies).await.unwrap();
+    assert!(replies == expected);
+
+    let _writer = write.await.unwrap();
+}
+
+// 阻塞期间客户端断开连接时，命令立即结束，之后写入的元素不会被它取走
+#[tokio::test]
+async fn disconnected_client_stops_blocking() {
+    let addr = start_server().await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"*3\r\n$5\r\nBLPOP\r\n$4\r\nlist\r\n$1\r\n0\r\n")
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    drop(stream);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let mut pusher = Client::connect(addr).await.unwrap();
+    pusher.execute(args(&["rpush", "list", "a"])).await.unwrap();
+    let response = pusher
+        .execute(args(&["lrange", "list", "0", "-1"]))
+        .await
+        .unwrap();
+    assert!(matches!(&response, Frame::Array(frames) if frames.len() == 1 && frames[0] == "a"));
+}
+
+// 负数的index从末尾开始计算，超出范围或者key不存在时返回错误，列表保持不变
+#[tokio::test]
+async fn lset_and_lindex_with_negative_index() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    list(&db, &["a", "b", "c"]);
+
+    assert_eq!(db.lindex("list", -1).unwrap().unwrap(), "c");
+    assert_eq!(db.lindex("list", -3).unwrap().unwrap(), "a");
+    assert_eq!(db.lindex("list", -4).unwrap(), None);
+    assert_eq!(db.lindex("list", 3).unwrap(), None);
+
+    db.lset("list", -1, Bytes::from_static(b"z")).unwrap();
+    db.lset("list", 0, Bytes::from_static(b"x")).unwrap();
+    assert_eq!(db.lrange("list", 0, -1).unwrap(), bytes(&["x", "b", "z"]));
+
+    for index in [3, -4] {
+        let err = db
+            .lset("list", index, Bytes::from_static(b"v"))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "ERR index out of range");
+    }
+    let err = db.lset("missing", 0, Bytes::from_static(b"v")).unwrap_err();
+    assert_eq!(err.to_string(), "ERR no such key");
+    assert_eq!(db.lrange("list", 0, -1).unwrap(), bytes(&["x", "b", "z"]));
+}
+
+// count为正数时从头部开始删除，为负数时从尾部开始删除，为0时删除所有相等的元素
+#[tokio::test]
+async fn lrem_direction_follows_count_sign() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    let items = ["a", "x", "b", "x", "c", "x"];
+
+    list(&db, &items);
+    assert_eq!(db.lrem("list", 2, b"x").unwrap(), 2);
+    assert_eq!(
+        db.lrange("list", 0, -1).unwrap(),
+        bytes(&["a", "b", "c", "x"])
+    );
+
+    list(&db, &items);
+    assert_eq!(db.lrem("list", -2, b"x").unwrap(), 2);
+    assert_eq!(
+        db.lrange("list", 0, -1).unwrap(),
+        bytes(&["a", "x", "b", "c"])
+    );
+
+    list(&db, &items);
+    assert_eq!(db.lrem("list", 0, b"x").unwrap(), 3);
+    assert_eq!(db.lrange("list", 0, -1).unwrap(), bytes(&["a", "b", "c"]));
+
+    // count超过相等元素的数量时全部删除，删空之后key也被删除
+    list(&db, &["x", "x"]);
+    assert_eq!(db.lrem("list", -10, b"x").unwrap(), 2);
+    assert_eq!(db.exists(&["list".to_string()]), 0);
+    assert_eq!(db.lrem("missing", 0, b"x").unwrap(), 0);
+}
+
+// 范围支持负数下标，超出列表的部分被忽略，范围为空时删除key
+#[tokio::test]
+async fn ltrim_deletes_key_when_empty() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    list(&db, &["a", "b", "c", "d", "e"]);
+    db.ltrim("list", 1, -2).unwrap();
+    assert_eq!(db.lrange("list", 0, -1).unwrap(), bytes(&["b", "c", "d"]));
+
+    db.ltrim("list", -100, 100).unwrap();
+    assert_eq!(db.lrange("list", 0, -1).unwrap(), bytes(&["b", "c", "d"]));
+
+    db.ltrim("list", -1, -1).unwrap();
+    assert_eq!(db.lrange("list", 0, -1).unwrap(), bytes(&["d"]));
+
+    // start大于stop或者start超出列表长度时结果为空
+    db.ltrim("list", 1, 0).unwrap();
+    assert_eq!(db.exists(&["list".to_string()]), 0);
+
+    list(&db, &["a", "b"]);
+    db.ltrim("list", 5, 10).unwrap();
+    assert_eq!(db.exists(&["list".to_string()]), 0);
+}
+
+// LINSERT在第一个等于pivot的元素前后插入，找不到pivot时返回-1，key不存在时返回0
+#[tokio::test]
+async fn linsert_before_and_after_pivot() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    list(&db, &["a", "p", "b", "p"]);
+
+    assert_eq!(
+        db.linsert("list", true, b"p", Bytes::from_static(b"x"))
+            .unwrap(),
+        5
+    );
+    assert_eq!(
+        db.linsert("list", false, b"p", Bytes::from_static(b"y"))
+            .unwrap(),
+        6
+    );
+    assert_eq!(
+        db.lrange("list", 0, -1).unwrap(),
+        bytes(&["a", "x", "p", "y", "b", "p"])
+    );
+
+    assert_eq!(
+        db.linsert("list", true, b"missing", Bytes::from_static(b"z"))
+            .unwrap(),
+        -1
+    );
+    assert_eq!(
+        db.linsert("missing", true, b"p", Bytes::from_static(b"z"))
+            .unwrap(),
+        0
+    );
+    assert_eq!(db.llen("list").unwrap(), 6);
+    assert_eq!(db.exists(&["missing".to_string()]), 0);
+}
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+
+use bytes::Bytes;
+use mini_redis::db::{Db, DbDropGuard, SetOptions};
+
+fn set(db: &Db, key: String) {
+    db.set(key, Bytes::from_static(b"v"), SetOptions::default())
+        .unwrap();
+}
+
+// 遍历期间不断写入和删除key，哈希表会多次扩容、缩容，一直存在的key仍然都能被遍历到
+#[tokio::test]
+async fn scan_returns_every_key_across_rehash() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    let stable: HashSet<String> = (0..200).map(|i| format!("stable:{}", i)).collect();
+    for key in &stable {
+        set(&db, key.clone());
+    }
+    for i in 0..300 {
+        set(&db, format!("tmp:{}", i));
+    }
+
+    let mut seen = HashSet::new();
+    let mut cursor = 0;
+    let mut round = 0;
+    loop {
+        let (next, keys) = db.scan(cursor, None, None, 10);
+        seen.extend(keys);
+
+        // 前半段不断写入新的key触发扩容，后半段删除临时的key触发缩容
+        if round < 40 {
+            for i in 0..50 {
+                set(&db, format!("new:{}:{}", round, i));
+            }
+        } else if round < 50 {
+            let keys: Vec<String> = (0..30)
+                .map(|i| format!("tmp:{}", (round - 40) * 30 + i))
+                .collect();
+            db.del(&keys);
+        }
+
+        round += 1;
+        cursor = next;
+        if cursor == 0 {
+            break;
+        }
+    }
+
+    // 遍历需要经历扩容和缩容两个阶段才有意义
+    assert!(round > 50);
+
+    let missing: Vec<_> = stable.difference(&seen).collect();
+    assert!(missing.is_empty(), "missing keys: {:?}", missing);
+}
+
+#[tokio::test]
+async fn scan_filters_by_pattern_and_type() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    for i in 0..20 {
+        set(&db, format!("user:{}", i));
+        set(&db, format!("order:{}", i));
+    }
+    db.sadd("user:set", vec![Bytes::from_static(b"m")]).unwrap();
+
+    let mut users = HashSet::new();
+    let mut strings = HashSet::new();
+    let mut cursor = 0;
+    loop {
+        let (next, keys) = db.scan(cursor, Some(b"user:*"), Some("set"), 5);
+        users.extend(keys);
+        cursor = next;
+        if cursor == 0 {
+            break;
+        }
+    }
+    loop {
+        let (next, keys) = db.scan(cursor, Some(b"order:1?"), Some("string"), 5);
+        strings.extend(keys);
+        cursor = next;
+        if cursor == 0 {
+            break;
+        }
+    }
+
+    assert_eq!(users, HashSet::from(["user:set".to_string()]));
+    assert_eq!(
+        strings,
+        (10..20)
+            .map(|i| format!("order:{}", i))
+            .collect::<HashSet<_>>()
+    );
+}
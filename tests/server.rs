@@ -0,0 +1,156 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use mini_redis::{
+    client::Client,
+    server::{self, Acceptor, Config},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::oneshot,
+    task::JoinHandle,
+    time,
+};
+
+const BIG_VALUE_LEN: usize = 1024 * 1024;
+const PIPELINED_GETS: usize = 32;
+
+async fn start_server(config: Config) -> (SocketAddr, oneshot::Sender<()>, JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = oneshot::channel();
+
+    let handle = tokio::spawn(server::run(vec![Acceptor::Tcp(listener)], config, rx));
+
+    (addr, tx, handle)
+}
+
+// 一次发送很多个GET，响应的总大小超过socket的缓冲区，客户端不读取的话连接会一直停在写响应的地方
+async fn stalled_connection(addr: SocketAddr) -> TcpStream {
+    let mut client = Client::connect(addr).await.unwrap();
+    client
+        .set("big", Bytes::from(vec![b'x'; BIG_VALUE_LEN]))
+        .await
+        .unwrap();
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(&b"*2\r\n$3\r\nGET\r\n$3\r\nbig\r\n".repeat(PIPELINED_GETS))
+        .await
+        .unwrap();
+
+    // 等待服务器读取这些命令并开始写响应
+    time::sleep(Duration::from_millis(100)).await;
+
+    stream
+}
+
+// 收到关闭信号时正在执行的命令会执行完，响应全部写给客户端之后run才会返回
+#[tokio::test]
+async fn shutdown_waits_for_in_flight_commands() {
+    let config = Config {
+        shutdown_timeout: Duration::from_secs(30),
+        ..Default::default()
+    };
+    let (addr, shutdown, handle) = start_server(config).await;
+    let mut stream = stalled_connection(addr).await;
+
+    shutdown.send(()).unwrap();
+    time::sleep(Duration::from_millis(200)).await;
+    assert!(!handle.is_finished());
+
+    // 开始读取之后服务器写完所有的响应，然后关闭连接
+    let mut replies = vec![];
+    stream.read_to_end(&mut replies).await.unwrap();
+    let reply_len = format!("${}\r\n", BIG_VALUE_LEN).len() + BIG_VALUE_LEN + 2;
+    assert_eq!(replies.len(), reply_len * PIPELINED_GETS);
+
+    time::timeout(Duration::from_secs(5), handle)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // 监听的socket已经关闭
+    assert!(TcpStream::connect(addr).await.is_err());
+}
+
+// 连接一直没有处理完时，最多等待shutdown_timeout
+#[tokio::test]
+async fn shutdown_timeout_bounds_the_wait() {
+    let config = Config {
+        shutdown_timeout: Duration::from_millis(300),
+        ..Default::default()
+    };
+    let (addr, shutdown, handle) = start_server(config).await;
+    let _stream = stalled_connection(addr).await;
+
+    let start = Instant::now();
+    shutdown.send(()).unwrap();
+    time::timeout(Duration::from_secs(5), handle)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(300), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+}
+
+// 没有连接时收到关闭信号立即返回
+#[tokio::test]
+async fn shutdown_without_connections_returns_immediately() {
+    let (_addr, shutdown, handle) = start_server(Config::default()).await;
+
+    shutdown.send(()).unwrap();
+    time::timeout(Duration::from_secs(1), handle)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+// 上次运行残留的socket文件会被替换，之后可以通过Unix domain socket执行命令
+#[cfg(unix)]
+#[tokio::test]
+async fn serves_commands_over_unix_socket() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::env::temp_dir().join(format!("mini-redis-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let acceptor = server::bind_unix(&path, Some(0o700)).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+
+    tokio::spawn(server::run(
+        vec![acceptor],
+        Config::default(),
+        std::future::pending::<()>(),
+    ));
+
+    let stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+    let mut client = Client::new(stream);
+    client.set("k", Bytes::from_static(b"v")).await.unwrap();
+    assert_eq!(client.get("k").await.unwrap().unwrap(), "v");
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+// 已经存在的普通文件不是残留的socket，不能删除
+#[cfg(unix)]
+#[tokio::test]
+async fn bind_unix_keeps_other_files() {
+    let path = std::env::temp_dir().join(format!("mini-redis-{}.file", std::process::id()));
+    std::fs::write(&path, b"data").unwrap();
+
+    let err = server::bind_unix(&path, None).unwrap_err();
+    assert!(err.to_string().contains("is not a socket"), "{}", err);
+    assert_eq!(std::fs::read(&path).unwrap(), b"data");
+
+    std::fs::remove_file(&path).unwrap();
+}
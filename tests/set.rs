@@ -0,0 +1,149 @@
+use bytes::Bytes;
+use mini_redis::db::{Db, DbDropGuard, EncodingConfig, ExpireStrategy, SetOp};
+
+fn members(items: &[&'static str]) -> Vec<Bytes> {
+    items
+        .iter()
+        .map(|item| Bytes::from_static(item.as_bytes()))
+        .collect()
+}
+
+fn keys(items: &[&str]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+// 集合运算的结果没有顺序，排序之后再比较
+fn sorted(mut members: Vec<Bytes>) -> Vec<Bytes> {
+    members.sort();
+    members
+}
+
+fn fill(db: &Db) {
+    db.sadd("a", members(&["1", "2", "3", "x"])).unwrap();
+    db.sadd("b", members(&["2", "3", "4"])).unwrap();
+    db.sadd("c", members(&["3", "x", "y"])).unwrap();
+}
+
+#[tokio::test]
+async fn set_algebra_results() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    fill(&db);
+
+    assert_eq!(
+        sorted(db.set_op(SetOp::Inter, &keys(&["a", "b"])).unwrap()),
+        members(&["2", "3"])
+    );
+    assert_eq!(
+        sorted(db.set_op(SetOp::Inter, &keys(&["a", "b", "c"])).unwrap()),
+        members(&["3"])
+    );
+    // 不存在的key视为空集合
+    assert!(db
+        .set_op(SetOp::Inter, &keys(&["a", "missing"]))
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        sorted(db.set_op(SetOp::Union, &keys(&["b", "c"])).unwrap()),
+        members(&["2", "3", "4", "x", "y"])
+    );
+    assert_eq!(
+        sorted(db.set_op(SetOp::Diff, &keys(&["a", "b", "c"])).unwrap()),
+        members(&["1"])
+    );
+}
+
+#[tokio::test]
+async fn sdiffstore_overwrites_or_deletes_destination() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    fill(&db);
+
+    assert_eq!(
+        db.set_op_store(SetOp::Diff, "dst", &keys(&["a", "b"]))
+            .unwrap(),
+        2
+    );
+    assert_eq!(sorted(db.smembers("dst").unwrap()), members(&["1", "x"]));
+
+    // 目标key可以是参与运算的key之一
+    assert_eq!(
+        db.set_op_store(SetOp::Diff, "a", &keys(&["a", "c"]))
+            .unwrap(),
+        2
+    );
+    assert_eq!(sorted(db.smembers("a").unwrap()), members(&["1", "2"]));
+
+    // 结果为空时删除destination
+    assert_eq!(
+        db.set_op_store(SetOp::Diff, "dst", &keys(&["b", "b"]))
+            .unwrap(),
+        0
+    );
+    assert_eq!(db.exists(&keys(&["dst"])), 0);
+}
+
+// 只包含整数并且数量不超过set_max_intset_entries时使用intset，intset按数值升序返回成员；
+// 超出之后转换成哈希表，成员保持不变
+#[tokio::test]
+async fn intset_converts_to_hashtable() {
+    let encoding = EncodingConfig {
+        set_max_intset_entries: 4,
+        ..Default::default()
+    };
+    let guard = DbDropGuard::with_config(ExpireStrategy::default(), encoding);
+    let db = guard.db();
+
+    db.sadd("ints", members(&["10", "-3", "2"])).unwrap();
+    assert_eq!(db.smembers("ints").unwrap(), members(&["-3", "2", "10"]));
+    assert_eq!(db.object_encoding("ints"), Some("intset"));
+
+    db.sadd("ints", members(&["4", "5"])).unwrap();
+    assert_eq!(
+        sorted(db.smembers("ints").unwrap()),
+        members(&["-3", "10", "2", "4", "5"])
+    );
+    assert_eq!(db.object_encoding("ints"), Some("hashtable"));
+    // 删除成员之后不会再转换回intset
+    assert_eq!(db.srem("ints", &members(&["5"])).unwrap(), 1);
+    assert_eq!(db.object_encoding("ints"), Some("hashtable"));
+    assert!(db.sismember("ints", b"4").unwrap());
+
+    db.sadd("mixed", members(&["1", "2"])).unwrap();
+    assert_eq!(db.object_encoding("mixed"), Some("intset"));
+    db.sadd("mixed", members(&["a"])).unwrap();
+    assert_eq!(db.object_encoding("mixed"), Some("hashtable"));
+    assert_eq!(
+        sorted(db.smembers("mixed").unwrap()),
+        members(&["1", "2", "a"])
+    );
+
+    // 非规范形式的整数不能放进intset，否则读取出来的内容会改变
+    db.sadd("padded", members(&["01"])).unwrap();
+    assert_eq!(db.object_encoding("padded"), Some("hashtable"));
+    assert!(db.sismember("padded", b"01").unwrap());
+    assert!(!db.sismember("padded", b"1").unwrap());
+}
+
+// count为负数时返回的成员数量不受集合大小的限制，成员可以重复
+#[tokio::test]
+async fn srandmember_negative_count_repeats_members() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    db.sadd("s", members(&["a", "b"])).unwrap();
+
+    let count = 1024 * 1024 + 1;
+    let sampled = db.srandmember("s", -count).unwrap();
+    assert_eq!(sampled.len(), count as usize);
+    assert!(sampled.iter().all(|member| member == "a" || member == "b"));
+
+    // count为正数时最多返回集合中的所有成员，不会重复
+    assert_eq!(
+        sorted(db.srandmember("s", count).unwrap()),
+        members(&["a", "b"])
+    );
+
+    assert!(db.srandmember("missing", -3).unwrap().is_empty());
+    let err = db.srandmember("s", i64::MIN).unwrap_err();
+    assert_eq!(err.to_string(), "ERR value is out of range");
+}
@@ -0,0 +1,229 @@
+use bytes::Bytes;
+use mini_redis::{
+    cmd::Command,
+    db::{DbDropGuard, StreamEntry, StreamId, StreamRead, XAddId, XReadGroupOptions},
+    frame::Frame,
+};
+
+fn command(args: &[&'static str]) -> Frame {
+    Frame::Array(
+        args.iter()
+            .map(|arg| Frame::Bulk(Bytes::from_static(arg.as_bytes())))
+            .collect(),
+    )
+}
+
+// field缺少value时整个命令都是错误的，不能只写入前面成对的部分
+#[test]
+fn xadd_rejects_odd_field_arguments() {
+    for args in [
+        &["xadd", "s1", "*"][..],
+        &["xadd", "s1", "*", "a"],
+        &["xadd", "s1", "*", "a", "1", "b"],
+    ] {
+        let err = Command::from_frame(command(args)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "wrong number of arguments for 'xadd' command"
+        );
+    }
+
+    assert!(Command::from_frame(command(&["xadd", "s1", "*", "a", "1", "b", "2"])).is_ok());
+}
+
+fn fields() -> Vec<(Bytes, Bytes)> {
+    vec![(Bytes::from_static(b"f"), Bytes::from_static(b"v"))]
+}
+
+fn ids(entries: &[StreamEntry]) -> Vec<StreamId> {
+    entries.iter().map(|entry| entry.id).collect()
+}
+
+fn read_entries(read: StreamRead) -> Vec<(String, Vec<StreamEntry>)> {
+    match read {
+        StreamRead::Entries(entries) => entries,
+        StreamRead::Blocked(_) => panic!("unexpected block"),
+    }
+}
+
+// ID必须严格递增，自动生成的ID在时间戳相同时递增序号
+#[tokio::test]
+async fn xadd_ids_are_strictly_increasing() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    let add = |id| db.xadd("s", id, fields(), false, None);
+
+    assert_eq!(
+        add(XAddId::Explicit(StreamId::new(5, 1))).unwrap(),
+        Some(StreamId::new(5, 1))
+    );
+    assert_eq!(add(XAddId::AutoSeq(5)).unwrap(), Some(StreamId::new(5, 2)));
+    assert_eq!(add(XAddId::AutoSeq(7)).unwrap(), Some(StreamId::new(7, 0)));
+
+    let err = add(XAddId::Explicit(StreamId::new(7, 0))).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ERR The ID specified in XADD is equal or smaller than the target stream top item"
+    );
+    assert!(add(XAddId::AutoSeq(6)).is_err());
+    assert!(add(XAddId::Explicit(StreamId::MIN)).is_err());
+
+    let auto = add(XAddId::Auto).unwrap().unwrap();
+    assert!(auto > StreamId::new(7, 0));
+    assert!(add(XAddId::Auto).unwrap().unwrap() > auto);
+
+    // 失败的XADD不会写入条目，也不会创建key
+    assert_eq!(db.xlen("s").unwrap(), 5);
+    assert!(db
+        .xadd(
+            "new",
+            XAddId::Explicit(StreamId::MIN),
+            fields(),
+            false,
+            None
+        )
+        .is_err());
+    assert_eq!(db.exists(&["new".to_string()]), 0);
+
+    let entries = db
+        .xrange("s", StreamId::MIN, StreamId::MAX, None, false)
+        .unwrap();
+    let mut sorted = ids(&entries);
+    sorted.sort();
+    assert_eq!(ids(&entries), sorted);
+}
+
+// 阻塞的XREAD在有新条目写入之后被唤醒，$只读取阻塞之后写入的条目
+#[tokio::test]
+async fn xread_blocks_until_new_entries() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    let keys = ["s".to_string()];
+
+    db.xadd(
+        "s",
+        XAddId::Explicit(StreamId::new(1, 0)),
+        fields(),
+        false,
+        None,
+    )
+    .unwrap();
+
+    let mut last = [None];
+    let mut waiter = match db.xread(&keys, &mut last, None, true).unwrap() {
+        StreamRead::Blocked(waiter) => waiter,
+        StreamRead::Entries(entries) => panic!("unexpected entries {:?}", entries),
+    };
+    assert_eq!(last, [Some(StreamId::new(1, 0))]);
+
+    db.xadd(
+        "s",
+        XAddId::Explicit(StreamId::new(2, 0)),
+        fields(),
+        false,
+        None,
+    )
+    .unwrap();
+    assert!(waiter.recv().await);
+
+    let entries = read_entries(db.xread(&keys, &mut last, None, true).unwrap());
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].0, "s");
+    assert_eq!(ids(&entries[0].1), vec![StreamId::new(2, 0)]);
+
+    // 不阻塞时没有新条目直接返回空的结果
+    let mut after = [Some(StreamId::new(2, 0))];
+    assert!(read_entries(db.xread(&keys, &mut after, None, false).unwrap()).is_empty());
+}
+
+// 读取过的条目进入消费者的待确认列表，XACK之后从列表中移除，重复确认不计算在内
+#[tokio::test]
+async fn xreadgroup_entries_are_pending_until_acked() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    let keys = ["s".to_string()];
+
+    for ms in 1..=3 {
+        db.xadd(
+            "s",
+            XAddId::Explicit(StreamId::new(ms, 0)),
+            fields(),
+            false,
+            None,
+        )
+        .unwrap();
+    }
+    db.xgroup_create("s", "g", Some(StreamId::MIN), false)
+        .unwrap();
+
+    let options = XReadGroupOptions {
+        count: Some(2),
+        ..Default::default()
+    };
+    let read = read_entries(
+        db.xread_group("g", "alice", &keys, &[None], &options)
+            .unwrap(),
+    );
+    assert_eq!(
+        ids(&read[0].1),
+        vec![StreamId::new(1, 0), StreamId::new(2, 0)]
+    );
+
+    // 新条目只会投递一次，bob只能读到剩下的条目
+    let read = read_entries(
+        db.xread_group("g", "bob", &keys, &[None], &options)
+            .unwrap(),
+    );
+    assert_eq!(ids(&read[0].1), vec![StreamId::new(3, 0)]);
+
+    let summary = db.xpending_summary("s", "g").unwrap();
+    assert_eq!(summary.count, 3);
+    assert_eq!(
+        summary.consumers,
+        vec![("alice".to_string(), 2), ("bob".to_string(), 1)]
+    );
+
+    assert_eq!(
+        db.xack("s", "g", &[StreamId::new(1, 0), StreamId::new(9, 0)])
+            .unwrap(),
+        1
+    );
+    assert_eq!(db.xack("s", "g", &[StreamId::new(1, 0)]).unwrap(), 0);
+
+    // 指定ID时读取的是自己的待确认列表
+    let history = read_entries(
+        db.xread_group("g", "alice", &keys, &[Some(StreamId::MIN)], &options)
+            .unwrap(),
+    );
+    assert_eq!(ids(&history[0].1), vec![StreamId::new(2, 0)]);
+
+    let summary = db.xpending_summary("s", "g").unwrap();
+    assert_eq!(summary.count, 2);
+    assert_eq!(
+        summary.bounds,
+        Some((StreamId::new(2, 0), StreamId::new(3, 0)))
+    );
+
+    // NOACK读取的条目不进入待确认列表
+    db.xadd(
+        "s",
+        XAddId::Explicit(StreamId::new(4, 0)),
+        fields(),
+        false,
+        None,
+    )
+    .unwrap();
+    let noack = XReadGroupOptions {
+        noack: true,
+        ..Default::default()
+    };
+    db.xread_group("g", "alice", &keys, &[None], &noack)
+        .unwrap();
+    assert_eq!(db.xpending_summary("s", "g").unwrap().count, 2);
+
+    let err = db
+        .xread_group("missing", "alice", &keys, &[None], &options)
+        .unwrap_err();
+    assert!(err.to_string().starts_with("NOGROUP"));
+}
@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use mini_redis::{
+    cmd::Command,
+    db::{Db, DbDropGuard, Expiry, ListEnd, SetCondition, SetOptions},
+    frame::Frame,
+};
+
+const WRONGTYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+
+fn set(db: &Db, key: &str, value: &'static str, options: SetOptions) -> (bool, Option<Bytes>) {
+    db.set(
+        key.to_string(),
+        Bytes::from_static(value.as_bytes()),
+        options,
+    )
+    .unwrap()
+}
+
+fn expire_after(secs: u64) -> SetOptions {
+    SetOptions {
+        expire: Expiry::After(Duration::from_secs(secs)),
+        ..Default::default()
+    }
+}
+
+fn command(args: &[&str]) -> mini_redis::Result<Command> {
+    let args = args
+        .iter()
+        .map(|arg| Frame::Bulk(Bytes::copy_from_slice(arg.as_bytes())))
+        .collect();
+    Command::from_frame(Frame::Array(args))
+}
+
+// 和redis一样保留小数点后17位并去掉末尾的0，0.1加0.2的结果是0.3而不是0.30000000000000004
+#[tokio::test]
+async fn incr_by_float_formats_like_redis() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    for (current, delta, expected) in [
+        ("0.1", 0.2, "0.3"),
+        ("10.5", 0.1, "10.6"),
+        ("5.0e3", 2.0e2, "5200"),
+        ("-0.1", 0.1, "0"),
+        ("100", -99.99, "0.01"),
+        ("1", 1e20, "100000000000000000000"),
+        // 参数0.123456789012345678解析成f64之后是0.12345678901234568
+        ("1", 0.12345678901234568, "1.12345678901234568"),
+        ("0", 0.12345678901234568, "0.12345678901234568"),
+        ("0", 1e-18, "0"),
+        ("0", -6e-18, "-0.00000000000000001"),
+        ("-1e-20", 0.0, "0"),
+    ] {
+        db.set(
+            "f".to_string(),
+            Bytes::from_static(current.as_bytes()),
+            SetOptions::default(),
+        )
+        .unwrap();
+
+        let value = db.incr_by_float("f", delta).unwrap();
+        assert_eq!(value, Bytes::from_static(expected.as_bytes()));
+        assert_eq!(db.get("f").unwrap(), Some(value));
+    }
+}
+
+// KEEPTTL保留原来的过期时间，不带任何过期参数的SET会清除过期时间
+#[tokio::test(start_paused = true)]
+async fn set_keepttl_keeps_expiration() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    set(&db, "k", "v1", expire_after(100));
+    let keep = SetOptions {
+        expire: Expiry::Keep,
+        ..Default::default()
+    };
+    set(&db, "k", "v2", keep);
+    assert_eq!(db.get("k").unwrap().unwrap(), "v2");
+    assert_eq!(db.ttl("k"), Some(Some(Duration::from_secs(100))));
+
+    set(&db, "k", "v3", SetOptions::default());
+    assert_eq!(db.ttl("k"), Some(None));
+
+    // key原来没有过期时间的话，KEEPTTL之后同样没有
+    set(&db, "new", "v", keep);
+    assert_eq!(db.ttl("new"), Some(None));
+}
+
+// 原来的值不是字符串时SET GET返回WRONGTYPE，并且不会覆盖原来的值
+#[tokio::test]
+async fn set_get_on_wrong_type() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    db.push("list", vec![Bytes::from_static(b"a")], ListEnd::Left)
+        .unwrap();
+
+    let get = SetOptions {
+        get: true,
+        ..Default::default()
+    };
+    let err = db
+        .set("list".to_string(), Bytes::from_static(b"v"), get)
+        .unwrap_err();
+    assert_eq!(err.to_string(), WRONGTYPE);
+    assert_eq!(db.key_type("list"), Some("list"));
+
+    // 不带GET时直接覆盖
+    set(&db, "list", "v", SetOptions::default());
+    assert_eq!(db.key_type("list"), Some("string"));
+
+    // NX没有写入时GET同样返回原来的值
+    let nx_get = SetOptions {
+        condition: SetCondition::NotExists,
+        get: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        set(&db, "list", "other", nx_get),
+        (false, Some(Bytes::from_static(b"v")))
+    );
+    assert_eq!(set(&db, "missing", "v", nx_get), (true, None));
+}
+
+#[test]
+fn set_rejects_conflicting_options() {
+    for options in [
+        &["NX", "XX"][..],
+        &["EX", "10", "PX", "100"],
+        &["KEEPTTL", "EX", "10"],
+        &["PXAT", "100", "KEEPTTL"],
+        &["GET", "GET"],
+        &["FOO"],
+    ] {
+        let args = [&["set", "k", "v"][..], options].concat();
+        let err = command(&args).unwrap_err();
+        assert!(
+            err.to_string().contains("syntax error"),
+            "{:?}: {}",
+            options,
+            err
+        );
+    }
+
+    assert!(command(&["set", "k", "v", "xx", "get", "keepttl"]).is_ok());
+    assert!(command(&["set", "k", "v", "get", "nx", "px", "100"]).is_ok());
+}
+
+// GETEX不带参数时不修改过期时间，PERSIST清除过期时间，过去的时间会删除key
+#[tokio::test(start_paused = true)]
+async fn getex_updates_expiration() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    set(&db, "k", "v", expire_after(100));
+
+    assert_eq!(db.get_ex("k", Expiry::Keep).unwrap().unwrap(), "v");
+    assert_eq!(db.ttl("k"), Some(Some(Duration::from_secs(100))));
+
+    db.get_ex("k", Expiry::After(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(db.ttl("k"), Some(Some(Duration::from_secs(5))));
+
+    db.get_ex("k", Expiry::Persist).unwrap();
+    assert_eq!(db.ttl("k"), Some(None));
+
+    assert_eq!(db.get_ex("missing", Expiry::Persist).unwrap(), None);
+
+    let past = Expiry::At(std::time::UNIX_EPOCH);
+    assert_eq!(db.get_ex("k", past).unwrap().unwrap(), "v");
+    assert_eq!(db.ttl("k"), None);
+
+    // 只能指定一个过期参数
+    for options in [
+        &["EX", "10", "PERSIST"][..],
+        &["PERSIST", "PX", "10"],
+        &["KEEPTTL"],
+    ] {
+        let args = [&["getex", "k"][..], options].concat();
+        let err = command(&args).unwrap_err();
+        assert_eq!(err.to_string(), "syntax error", "{:?}", options);
+    }
+}
+
+#[tokio::test]
+async fn getdel_removes_only_strings() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    set(&db, "k", "v", expire_after(100));
+    db.push("list", vec![Bytes::from_static(b"a")], ListEnd::Left)
+        .unwrap();
+
+    assert_eq!(db.get_del("k").unwrap().unwrap(), "v");
+    assert_eq!(db.get("k").unwrap(), None);
+    assert_eq!(db.ttl("k"), None);
+    assert_eq!(db.get_del("k").unwrap(), None);
+
+    assert_eq!(db.get_del("list").unwrap_err().to_string(), WRONGTYPE);
+    assert_eq!(db.key_type("list"), Some("list"));
+}
+
+// 结果是inf或者NaN时返回错误，原来的值不是合法的浮点数时同样返回错误，两种情况都不会修改原来的值
+#[tokio::test]
+async fn incr_by_float_rejects_invalid_values() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+    set(&db, "f", "1.5", SetOptions::default());
+
+    for delta in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+        let err = db.incr_by_float("f", delta).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR increment would produce NaN or Infinity"
+        );
+    }
+    assert_eq!(db.get("f").unwrap().unwrap(), "1.5");
+
+    // 两个有限的数相加也可能溢出成inf
+    set(&db, "big", "1e308", SetOptions::default());
+    let err = db.incr_by_float("big", 1e308).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ERR increment would produce NaN or Infinity"
+    );
+    assert_eq!(db.get("big").unwrap().unwrap(), "1e308");
+
+    for current in ["abc", "1.5x", "", " 1"] {
+        set(&db, "s", current, SetOptions::default());
+        let err = db.incr_by_float("s", 1.0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR value is not a valid float",
+            "{:?}",
+            current
+        );
+        assert_eq!(db.get("s").unwrap().unwrap(), current);
+    }
+
+    db.push("list", vec![Bytes::from_static(b"1")], ListEnd::Left)
+        .unwrap();
+    assert_eq!(
+        db.incr_by_float("list", 1.0).unwrap_err().to_string(),
+        WRONGTYPE
+    );
+}
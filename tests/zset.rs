@@ -0,0 +1,217 @@
+use bytes::Bytes;
+use mini_redis::{
+    cmd::Command,
+    db::{DbDropGuard, ScoreBound, ZAddOptions, ZRange},
+    frame::Frame,
+};
+
+fn command(args: &[&'static str]) -> Frame {
+    Frame::Array(
+        args.iter()
+            .map(|arg| Frame::Bulk(Bytes::from_static(arg.as_bytes())))
+            .collect(),
+    )
+}
+
+fn pairs(items: &[(f64, &'static str)]) -> Vec<(f64, Bytes)> {
+    items
+        .iter()
+        .map(|&(score, member)| (score, Bytes::from_static(member.as_bytes())))
+        .collect()
+}
+
+// GT、LT只限制更新已经存在的成员，新成员总是会被添加；CH让返回值包括分数发生变化的成员
+#[tokio::test]
+async fn zadd_gt_lt_ch() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    assert_eq!(
+        db.zadd(
+            "z",
+            pairs(&[(1.0, "a"), (2.0, "b")]),
+            ZAddOptions::default()
+        )
+        .unwrap(),
+        2
+    );
+
+    let gt = ZAddOptions {
+        gt: true,
+        ch: true,
+        ..Default::default()
+    };
+    // a变大被更新，b变小不更新，c是新成员
+    assert_eq!(
+        db.zadd("z", pairs(&[(5.0, "a"), (0.0, "b"), (3.0, "c")]), gt)
+            .unwrap(),
+        2
+    );
+    assert_eq!(db.zscore("z", b"a").unwrap(), Some(5.0));
+    assert_eq!(db.zscore("z", b"b").unwrap(), Some(2.0));
+    assert_eq!(db.zscore("z", b"c").unwrap(), Some(3.0));
+
+    let lt = ZAddOptions {
+        lt: true,
+        ..Default::default()
+    };
+    // 不带CH时只计算新增的成员
+    assert_eq!(
+        db.zadd("z", pairs(&[(1.0, "a"), (9.0, "b"), (4.0, "d")]), lt)
+            .unwrap(),
+        1
+    );
+    assert_eq!(db.zscore("z", b"a").unwrap(), Some(1.0));
+    assert_eq!(db.zscore("z", b"b").unwrap(), Some(2.0));
+
+    // 分数没有变化时CH也不计算在内
+    let ch = ZAddOptions {
+        ch: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        db.zadd("z", pairs(&[(1.0, "a"), (2.5, "b")]), ch).unwrap(),
+        1
+    );
+
+    // XX只更新，一个成员都没有写入时不会留下空的key
+    let xx = ZAddOptions {
+        xx: true,
+        ..Default::default()
+    };
+    assert_eq!(db.zadd("empty", pairs(&[(1.0, "a")]), xx).unwrap(), 0);
+    assert_eq!(db.exists(&["empty".to_string()]), 0);
+}
+
+#[test]
+fn zadd_rejects_conflicting_options() {
+    for (args, expected) in [
+        (
+            &["zadd", "z", "nx", "xx", "1", "a"][..],
+            "XX and NX options at the same time are not compatible",
+        ),
+        (
+            &["zadd", "z", "gt", "lt", "1", "a"],
+            "GT, LT, and/or NX options at the same time are not compatible",
+        ),
+        (
+            &["zadd", "z", "nx", "gt", "1", "a"],
+            "GT, LT, and/or NX options at the same time are not compatible",
+        ),
+        (
+            &["zadd", "z", "incr", "1", "a", "2", "b"],
+            "INCR option supports a single increment-element pair",
+        ),
+        (&["zadd", "z", "1", "a", "2"], "syntax error"),
+        (&["zadd", "z", "nan", "a"], "value is not a valid float"),
+    ] {
+        let err = Command::from_frame(command(args)).unwrap_err();
+        assert_eq!(err.to_string(), expected);
+    }
+
+    assert!(Command::from_frame(command(&["zadd", "z", "xx", "gt", "ch", "1", "a"])).is_ok());
+}
+
+// ZADD INCR返回新的分数，因为条件没有写入时返回None
+#[tokio::test]
+async fn zadd_incr() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    let member = || Bytes::from_static(b"m");
+
+    assert_eq!(
+        db.zincr_by("z", member(), 2.5, ZAddOptions::default())
+            .unwrap(),
+        Some(2.5)
+    );
+    assert_eq!(
+        db.zincr_by("z", member(), 1.0, ZAddOptions::default())
+            .unwrap(),
+        Some(3.5)
+    );
+
+    let gt = ZAddOptions {
+        gt: true,
+        ..Default::default()
+    };
+    assert_eq!(db.zincr_by("z", member(), -1.0, gt).unwrap(), None);
+    assert_eq!(db.zscore("z", b"m").unwrap(), Some(3.5));
+
+    let nx = ZAddOptions {
+        nx: true,
+        ..Default::default()
+    };
+    assert_eq!(db.zincr_by("z", member(), 1.0, nx).unwrap(), None);
+
+    // 结果为nan时返回错误并且不修改分数
+    db.zadd(
+        "inf",
+        pairs(&[(f64::INFINITY, "m")]),
+        ZAddOptions::default(),
+    )
+    .unwrap();
+    assert!(db
+        .zincr_by("inf", member(), f64::NEG_INFINITY, ZAddOptions::default())
+        .is_err());
+    assert_eq!(db.zscore("inf", b"m").unwrap(), Some(f64::INFINITY));
+}
+
+// 分数相同的成员按照字典序排列
+#[tokio::test]
+async fn rank_queries() {
+    let guard = DbDropGuard::new();
+    let db = guard.db();
+
+    db.zadd(
+        "z",
+        pairs(&[(3.0, "c"), (1.0, "a"), (2.0, "b2"), (2.0, "b1"), (5.0, "e")]),
+        ZAddOptions::default(),
+    )
+    .unwrap();
+
+    assert_eq!(db.zrank("z", b"a", false).unwrap(), Some((0, 1.0)));
+    assert_eq!(db.zrank("z", b"b1", false).unwrap(), Some((1, 2.0)));
+    assert_eq!(db.zrank("z", b"b2", false).unwrap(), Some((2, 2.0)));
+    assert_eq!(db.zrank("z", b"e", true).unwrap(), Some((0, 5.0)));
+    assert_eq!(db.zrank("z", b"a", true).unwrap(), Some((4, 1.0)));
+    assert_eq!(db.zrank("z", b"missing", false).unwrap(), None);
+    assert_eq!(db.zrank("missing", b"a", false).unwrap(), None);
+
+    let members = |range: Vec<(Bytes, f64)>| {
+        range
+            .into_iter()
+            .map(|(member, _)| member)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        members(db.zrange("z", ZRange::Rank(1, -2), false, None).unwrap()),
+        vec![
+            Bytes::from_static(b"b1"),
+            Bytes::from_static(b"b2"),
+            Bytes::from_static(b"c")
+        ]
+    );
+    assert_eq!(
+        members(db.zrange("z", ZRange::Rank(0, 1), true, None).unwrap()),
+        vec![Bytes::from_static(b"e"), Bytes::from_static(b"c")]
+    );
+    assert_eq!(
+        members(
+            db.zrange(
+                "z",
+                ZRange::Score(ScoreBound::Exclusive(1.0), ScoreBound::Inclusive(3.0)),
+                false,
+                Some((1, 2)),
+            )
+            .unwrap()
+        ),
+        vec![Bytes::from_static(b"b2"), Bytes::from_static(b"c")]
+    );
+    assert_eq!(
+        db.zcount("z", ScoreBound::Inclusive(2.0), ScoreBound::Inclusive(5.0))
+            .unwrap(),
+        4
+    );
+}